@@ -0,0 +1,36 @@
+/* C interface to binary_tree_map, built with the `ffi` feature, e.g.
+ *
+ *     cargo rustc --release --features ffi --crate-type staticlib
+ *
+ * Keys are int64_t, values byte strings copied into the map. A pointer
+ * returned by btm_get stays valid until the next mutation or btm_free.
+ */
+#ifndef BINARY_TREE_MAP_H
+#define BINARY_TREE_MAP_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct BtmMap BtmMap;
+
+/* Return false to stop the iteration */
+typedef bool (*BtmVisit)(int64_t key, const uint8_t *value, size_t len, void *context);
+
+BtmMap *btm_new(void);
+void btm_free(BtmMap *map);
+size_t btm_len(const BtmMap *map);
+void btm_insert(BtmMap *map, int64_t key, const uint8_t *value, size_t len);
+bool btm_get(const BtmMap *map, int64_t key, const uint8_t **value, size_t *len);
+bool btm_delete(BtmMap *map, int64_t key);
+void btm_for_each(const BtmMap *map, BtmVisit visit, void *context);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,51 @@
+use crate::{BalancePolicy, BinaryTreeMap};
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+// Replays an arbitrary sequence of inserts and deletes instead of decoding a
+// list of entries, so fuzzers reach the shapes that deletion leaves behind
+// (successor splices, lopsided subtrees) and not only freshly built trees
+impl<'a, K, V, P> Arbitrary<'a> for BinaryTreeMap<K, V, P>
+where
+    K: Ord + Arbitrary<'a>,
+    V: Arbitrary<'a>,
+    P: BalancePolicy<K, V> + Default,
+{
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut map = BinaryTreeMap::default();
+        u.arbitrary_loop(None, None, |u| {
+            // Roughly one delete for every three inserts
+            if u.ratio(1, 4)? {
+                let _ = map.delete(&K::arbitrary(u)?);
+            } else {
+                map.insert(K::arbitrary(u)?, V::arbitrary(u)?);
+            }
+            Ok(core::ops::ControlFlow::Continue(()))
+        })?;
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Avl;
+
+    #[test]
+    fn test_same_bytes_build_the_same_map() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let first = BinaryTreeMap::<u8, u16>::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+        let second = BinaryTreeMap::<u8, u16>::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+        assert!(!first.is_empty());
+        assert!(first.iter().eq(second.iter()));
+        assert!(first.iter().map(|(k, _)| k).is_sorted());
+    }
+
+    #[test]
+    fn test_balanced_policy_and_empty_input() {
+        let bytes: Vec<u8> = (0..4096u32).map(|i| (i * 37 % 251) as u8).collect();
+        let map = BinaryTreeMap::<u16, (), Avl>::arbitrary(&mut Unstructured::new(&bytes)).unwrap();
+        assert!(map.iter().map(|(k, _)| k).is_sorted());
+        let empty = BinaryTreeMap::<u8, u8>::arbitrary(&mut Unstructured::new(&[])).unwrap();
+        assert!(empty.is_empty());
+    }
+}
@@ -0,0 +1,333 @@
+use crate::linked::{self, Edge, LinkedNode, LinkedTree, Slot, NIL};
+use crate::{BinaryTreeMap, MapError, MemoryReport};
+use allocator_api2::alloc::{AllocError, Allocator, Global};
+use allocator_api2::vec::Vec;
+
+// Each slot's tag is its generation: `EntryId`s handed out for a slot
+// match it while the slot holds that entry, and it is bumped when the
+// entry is removed
+type ArenaSlot<K, V> = Slot<K, V, u32>;
+
+// Handle to one entry of an `ArenaTreeMap`: the slot it lives in and the
+// slot's generation when it was handed out. Entries never move between
+// slots while they live, and a removed entry's slot gets a new generation,
+// so a handle to a removed entry stays invalid even once its slot is
+// reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntryId {
+    slot: u32,
+    generation: u32,
+}
+
+// Unbalanced BST whose nodes live in one `Vec` and link to each other by
+// `u32` index instead of `Box`. Nodes are packed together for locality and
+// inserting only allocates when the vector has to grow. That vector comes
+// from `A`, so the whole map can live in a bump arena or pool allocator.
+#[derive(Debug)]
+pub struct ArenaTreeMap<K, V, A: Allocator = Global> {
+    tree: LinkedTree<Vec<ArenaSlot<K, V>, A>>,
+}
+
+impl<K: Ord, V> Default for ArenaTreeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> BinaryTreeMap<K, V> {
+    pub fn new_in_arena() -> ArenaTreeMap<K, V> {
+        ArenaTreeMap::new()
+    }
+}
+
+impl<K: Ord, V> ArenaTreeMap<K, V> {
+    pub fn new() -> Self {
+        Self::new_in(Global)
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_in(capacity, Global)
+    }
+}
+
+impl<K: Ord, V, A: Allocator> ArenaTreeMap<K, V, A> {
+    pub fn new_in(alloc: A) -> Self {
+        Self::with_capacity_in(0, alloc)
+    }
+
+    pub fn with_capacity_in(capacity: usize, alloc: A) -> Self {
+        ArenaTreeMap { tree: LinkedTree::new(Vec::with_capacity_in(capacity, alloc), NIL) }
+    }
+
+    pub fn allocator(&self) -> &A {
+        self.tree.slots.allocator()
+    }
+
+    pub fn memory_usage(&self) -> MemoryReport {
+        MemoryReport::for_slots::<ArenaSlot<K, V>>(self.tree.len, self.tree.slots.capacity())
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.tree.slots.capacity()
+    }
+
+    // Ensures `additional` more entries fit without growing, or fails
+    // without aborting if the allocator cannot provide the room
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+        self.tree.slots.try_reserve(additional).map_err(|_| AllocError)
+    }
+
+    // Reuses a free slot, taking on its generation, or grows the vector
+    fn allocate(&mut self, node: LinkedNode<K, V, u32>) -> u32 {
+        self.tree.take_free(node).unwrap_or_else(|node| {
+            let slots = &mut self.tree.slots;
+            let index = u32::try_from(slots.len()).ok().filter(|index| *index != NIL).expect("arena index overflow");
+            slots.push(Slot::Occupied(node));
+            index
+        })
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        self.insert_with_id(key, value);
+    }
+
+    // Updating an existing key keeps its entry, and so its id
+    pub fn insert_with_id(&mut self, key: K, value: V) -> EntryId {
+        let edge = self.tree.find_edge(&key);
+        let mut index = self.tree.target(edge);
+        if index != NIL {
+            self.tree.node_mut(index).value = value; // Update existing value
+        } else {
+            index = self.allocate(LinkedNode { key, value, parent: NIL, left: NIL, right: NIL, tag: 0 });
+            self.tree.attach(edge, index);
+        }
+        EntryId { slot: index, generation: self.tree.node(index).tag }
+    }
+
+    pub fn id_of(&self, key: &K) -> Option<EntryId> {
+        match self.tree.target(self.tree.find_edge(key)) {
+            NIL => None,
+            index => Some(EntryId { slot: index, generation: self.tree.node(index).tag }),
+        }
+    }
+
+    // Slot holding the entry `id` refers to, if it is still in the map
+    fn live_slot(&self, id: EntryId) -> Option<u32> {
+        match self.tree.slots.get(id.slot as usize) {
+            Some(Slot::Occupied(node)) if node.tag == id.generation => Some(id.slot),
+            _ => None,
+        }
+    }
+
+    // O(1), comparing no keys. Ids of removed entries give `KeyNotFound`.
+    pub fn get_by_id(&self, id: EntryId) -> Result<(&K, &V), MapError> {
+        let node = self.tree.node(self.live_slot(id).ok_or(MapError::KeyNotFound)?);
+        Ok((&node.key, &node.value))
+    }
+
+    pub fn get_by_id_mut(&mut self, id: EntryId) -> Result<&mut V, MapError> {
+        let index = self.live_slot(id).ok_or(MapError::KeyNotFound)?;
+        Ok(&mut self.tree.node_mut(index).value)
+    }
+
+    // Compares no keys: the parent link gives the edge the entry hangs
+    // from. Relinking a node with two children still walks down to its
+    // successor, as `delete` does.
+    pub fn remove_by_id(&mut self, id: EntryId) -> Result<(K, V), MapError> {
+        let index = self.live_slot(id).ok_or(MapError::KeyNotFound)?;
+        let node = self.unlink(self.tree.edge_of(index), index);
+        Ok((node.key, node.value))
+    }
+
+    pub fn get(&self, key: &K) -> Result<&V, MapError> {
+        match self.tree.target(self.tree.find_edge(key)) {
+            NIL => Err(MapError::KeyNotFound),
+            index => Ok(&self.tree.node(index).value),
+        }
+    }
+
+    pub fn delete(&mut self, key: &K) -> Result<V, MapError> {
+        let edge = self.tree.find_edge(key);
+        match self.tree.target(edge) {
+            NIL => Err(MapError::KeyNotFound),
+            index => Ok(self.unlink(edge, index).value),
+        }
+    }
+
+    // Removes the node at `index`, which hangs from `edge`, retiring the
+    // ids handed out for it
+    fn unlink(&mut self, edge: Edge, index: u32) -> LinkedNode<K, V, u32> {
+        let generation = self.tree.node(index).tag.wrapping_add(1);
+        self.tree.unlink(edge, index, generation)
+    }
+
+    // Iterates in key order using O(1) extra space
+    pub fn iter(&self) -> Iter<'_, K, V, A> {
+        Iter(self.tree.iter())
+    }
+}
+
+pub struct Iter<'a, K, V, A: Allocator = Global>(linked::Iter<'a, Vec<ArenaSlot<K, V>, A>>);
+
+impl<'a, K: Ord, V, A: Allocator> Iterator for Iter<'a, K, V, A> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K: Ord, V, A: Allocator> ExactSizeIterator for Iter<'_, K, V, A> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use allocator_api2::alloc::{AllocError, Layout};
+    use std::cell::Cell;
+    use std::ptr::NonNull;
+
+    // Forwards to the global allocator, counting live allocations
+    #[derive(Default)]
+    struct CountingAlloc {
+        live: Cell<usize>,
+    }
+
+    unsafe impl Allocator for &CountingAlloc {
+        fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+            self.live.set(self.live.get() + 1);
+            Global.allocate(layout)
+        }
+
+        unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+            self.live.set(self.live.get() - 1);
+            unsafe { Global.deallocate(ptr, layout) }
+        }
+    }
+
+    #[test]
+    fn test_nodes_live_in_custom_allocator() {
+        let alloc = CountingAlloc::default();
+        let mut map = ArenaTreeMap::with_capacity_in(16, &alloc);
+        for key in 0..16 {
+            map.insert(key, key * 2);
+        }
+
+        assert_eq!(alloc.live.get(), 1);
+        assert_eq!(map.get(&7), Ok(&14));
+        assert_eq!(map.allocator().live.get(), 1);
+
+        drop(map);
+        assert_eq!(alloc.live.get(), 0);
+    }
+
+    #[test]
+    fn test_insert_get_delete() {
+        let mut map = BinaryTreeMap::new_in_arena();
+        for key in [50, 30, 70, 20, 40, 60, 80] {
+            map.insert(key, key.to_string());
+        }
+        map.insert(40, "forty".to_string());
+
+        assert_eq!(map.len(), 7);
+        assert_eq!(map.get(&40), Ok(&"forty".to_string()));
+        assert_eq!(map.delete(&50), Ok("50".to_string()));
+        assert_eq!(map.delete(&30), Ok("30".to_string()));
+        assert_eq!(map.delete(&30), Err(MapError::KeyNotFound));
+        assert_eq!(map.len(), 5);
+        for key in [20, 40, 60, 70, 80] {
+            assert!(map.get(&key).is_ok(), "missing {key}");
+        }
+    }
+
+    #[test]
+    fn test_iter_follows_parent_links() {
+        let mut map = ArenaTreeMap::new();
+        for key in [50, 30, 70, 20, 40, 60, 80, 35, 45] {
+            map.insert(key, key * 2);
+        }
+        map.delete(&30).unwrap();
+        map.delete(&50).unwrap();
+
+        let entries: std::vec::Vec<_> = map.iter().map(|(key, value)| (*key, *value)).collect();
+        assert_eq!(entries, [(20, 40), (35, 70), (40, 80), (45, 90), (60, 120), (70, 140), (80, 160)]);
+        assert_eq!(map.iter().len(), 7);
+        assert_eq!(ArenaTreeMap::<i32, i32>::new().iter().next(), None);
+    }
+
+    #[test]
+    fn test_deleted_slots_are_reused() {
+        let mut map = ArenaTreeMap::with_capacity(4);
+        for key in 0..4 {
+            map.insert(key, key);
+        }
+        let capacity = map.capacity();
+
+        for round in 0..100 {
+            assert_eq!(map.delete(&(round % 4)), Ok(round % 4));
+            map.insert(round % 4, round % 4);
+        }
+
+        assert_eq!(map.capacity(), capacity);
+        assert_eq!(map.tree.slots.len(), 4);
+    }
+
+    #[test]
+    fn test_delete_successor_deep_in_right_subtree() {
+        let mut map = ArenaTreeMap::new();
+        for key in [10, 5, 20, 15, 25, 12, 13] {
+            map.insert(key, ());
+        }
+
+        assert_eq!(map.delete(&10), Ok(()));
+
+        assert_eq!(map.tree.node(map.tree.root).key, 12);
+        for key in [5, 13, 15, 20, 25] {
+            assert_eq!(map.get(&key), Ok(&()));
+        }
+        assert_eq!(map.get(&10), Err(MapError::KeyNotFound));
+    }
+
+    #[test]
+    fn test_entry_ids_outlive_other_mutations() {
+        let mut map = ArenaTreeMap::new();
+        let ids: std::vec::Vec<_> = [50, 30, 70, 20, 40, 60, 80].into_iter().map(|key| map.insert_with_id(key, key * 10)).collect();
+        assert_eq!(map.insert_with_id(40, 400), ids[4]);
+        assert_eq!(map.id_of(&40), Some(ids[4]));
+
+        // Removing the two-child root relinks 60 but leaves every slot alone
+        assert_eq!(map.remove_by_id(ids[0]), Ok((50, 500)));
+        assert_eq!(map.get_by_id(ids[5]), Ok((&60, &600)));
+        *map.get_by_id_mut(ids[1]).unwrap() += 1;
+        assert_eq!(map.get(&30), Ok(&301));
+
+        // The freed slot is reused, under a new generation
+        let reused = map.insert_with_id(55, 550);
+        assert_eq!(reused.slot, ids[0].slot);
+        assert_eq!(map.get_by_id(ids[0]), Err(MapError::KeyNotFound));
+        assert_eq!(map.remove_by_id(ids[0]), Err(MapError::KeyNotFound));
+        assert_eq!(map.get_by_id(reused), Ok((&55, &550)));
+        assert!(map.iter().map(|(k, _)| *k).eq([20, 30, 40, 55, 60, 70, 80]));
+    }
+
+    #[test]
+    fn test_try_reserve() {
+        let mut map: ArenaTreeMap<u64, u64> = ArenaTreeMap::new();
+        assert_eq!(map.try_reserve(16), Ok(()));
+        assert!(map.capacity() >= 16);
+        assert_eq!(map.try_reserve(usize::MAX), Err(AllocError));
+        map.insert(1, 1);
+        assert_eq!(map.len(), 1);
+    }
+}
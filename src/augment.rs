@@ -0,0 +1,250 @@
+use crate::node::{Link, Node};
+use crate::{BalancePolicy, BinaryTreeMap, WeightBalanced};
+use core::marker::PhantomData;
+use core::ops::{Bound, RangeBounds};
+use alloc::boxed::Box;
+
+// A per-subtree summary forming a monoid: `Summary::default()` is the
+// summary of an empty subtree and `combine` must be associative. The map
+// keeps every node's summary of its whole subtree, in key order, current
+// through inserts, deletes and rotations. Summaries may depend on values,
+// so augmented maps have no `par_iter_mut`; `par_update_values` edits
+// values in parallel and then recomputes them.
+pub trait Augment<K, V> {
+    type Summary: Default + Clone;
+
+    fn summarize(key: &K, value: &V) -> Self::Summary;
+
+    fn combine(left: &Self::Summary, right: &Self::Summary) -> Self::Summary;
+}
+
+// Balance policy carrying an `Augment` summary in every node. Balancing is
+// by weight, which needs no metadata of its own beside the summary.
+pub struct Augmented<A>(PhantomData<A>);
+
+pub type AugmentedTreeMap<K, V, A> = BinaryTreeMap<K, V, Augmented<A>>;
+
+// Derives would require `A` itself to implement each trait
+impl<A> Default for Augmented<A> {
+    fn default() -> Self {
+        Augmented(PhantomData)
+    }
+}
+
+impl<A> Clone for Augmented<A> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A> Copy for Augmented<A> {}
+
+impl<A> core::fmt::Debug for Augmented<A> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("Augmented")
+    }
+}
+
+impl<K, V, A: Augment<K, V>> BalancePolicy<K, V> for Augmented<A> {
+    type Meta = A::Summary;
+
+    fn update(node: &mut Node<K, V, A::Summary>) {
+        let own = A::summarize(&node.key, &node.value);
+        let with_left = match &node.left {
+            Some(left) => A::combine(&left.meta, &own),
+            None => own,
+        };
+        node.meta = match &node.right {
+            Some(right) => A::combine(&with_left, &right.meta),
+            None => with_left,
+        };
+    }
+
+    fn after_insert(node: Box<Node<K, V, A::Summary>>) -> Box<Node<K, V, A::Summary>> {
+        WeightBalanced::balance::<_, _, Self>(node)
+    }
+
+    fn after_delete(node: Box<Node<K, V, A::Summary>>) -> Box<Node<K, V, A::Summary>> {
+        WeightBalanced::balance::<_, _, Self>(node)
+    }
+
+    fn check(node: &Node<K, V, A::Summary>) -> Result<(), &'static str> {
+        WeightBalanced::check_weights(node)
+    }
+
+    fn join_direction(left: Option<&Node<K, V, A::Summary>>, right: Option<&Node<K, V, A::Summary>>) -> core::cmp::Ordering {
+        WeightBalanced::join_by_weight(left, right)
+    }
+}
+
+impl<K: Ord, V, A: Augment<K, V>> BinaryTreeMap<K, V, Augmented<A>> {
+    // Summary of every entry, read off the root in O(1)
+    pub fn summary(&self) -> A::Summary {
+        self.root.as_ref().map_or_else(A::Summary::default, |root| root.meta.clone())
+    }
+
+    // Summary of the entries with keys in `range`, in O(log n): below the
+    // node where the bounds' search paths split, each path adds whole
+    // subtrees hanging off its inner side
+    pub fn range_fold<R: RangeBounds<K>>(&self, range: R) -> A::Summary {
+        let mut current = &self.root;
+        while let Some(n) = current {
+            if !after_start(&n.key, range.start_bound()) {
+                current = &n.right;
+            } else if !before_end(&n.key, range.end_bound()) {
+                current = &n.left;
+            } else {
+                let from = Self::fold_from(&n.left, range.start_bound());
+                let own = A::combine(&from, &A::summarize(&n.key, &n.value));
+                return A::combine(&own, &Self::fold_to(&n.right, range.end_bound()));
+            }
+        }
+        A::Summary::default()
+    }
+
+    // Summary of the keys in `link` at or after `start`
+    fn fold_from(mut link: &Link<K, V, A::Summary>, start: Bound<&K>) -> A::Summary {
+        let mut folded = A::Summary::default();
+        while let Some(n) = link {
+            if after_start(&n.key, start) {
+                let mut chunk = A::summarize(&n.key, &n.value);
+                if let Some(right) = &n.right {
+                    chunk = A::combine(&chunk, &right.meta);
+                }
+                folded = A::combine(&chunk, &folded);
+                link = &n.left;
+            } else {
+                link = &n.right;
+            }
+        }
+        folded
+    }
+
+    // Summary of the keys in `link` at or before `end`
+    fn fold_to(mut link: &Link<K, V, A::Summary>, end: Bound<&K>) -> A::Summary {
+        let mut folded = A::Summary::default();
+        while let Some(n) = link {
+            if before_end(&n.key, end) {
+                let mut chunk = A::summarize(&n.key, &n.value);
+                if let Some(left) = &n.left {
+                    chunk = A::combine(&left.meta, &chunk);
+                }
+                folded = A::combine(&folded, &chunk);
+                link = &n.right;
+            } else {
+                link = &n.left;
+            }
+        }
+        folded
+    }
+}
+
+pub(crate) fn after_start<K: Ord>(key: &K, start: Bound<&K>) -> bool {
+    match start {
+        Bound::Included(start) => key >= start,
+        Bound::Excluded(start) => key > start,
+        Bound::Unbounded => true,
+    }
+}
+
+pub(crate) fn before_end<K: Ord>(key: &K, end: Bound<&K>) -> bool {
+    match end {
+        Bound::Included(end) => key <= end,
+        Bound::Excluded(end) => key < end,
+        Bound::Unbounded => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sum of the values
+    struct Sum;
+
+    impl<K> Augment<K, i64> for Sum {
+        type Summary = i64;
+
+        fn summarize(_: &K, value: &i64) -> i64 {
+            *value
+        }
+
+        fn combine(left: &i64, right: &i64) -> i64 {
+            left + right
+        }
+    }
+
+    // First and last key: only associative, not commutative, so it catches
+    // summaries combined out of order
+    struct Span;
+
+    impl<V> Augment<u32, V> for Span {
+        type Summary = Option<(u32, u32)>;
+
+        fn summarize(key: &u32, _: &V) -> Self::Summary {
+            Some((*key, *key))
+        }
+
+        fn combine(left: &Self::Summary, right: &Self::Summary) -> Self::Summary {
+            match (left, right) {
+                (Some((first, _)), Some((_, last))) => Some((*first, *last)),
+                (summary, None) | (None, summary) => *summary,
+            }
+        }
+    }
+
+    #[test]
+    fn test_sum_follows_inserts_and_deletes() {
+        let mut map: AugmentedTreeMap<u32, i64, Sum> = AugmentedTreeMap::default();
+        assert_eq!(map.summary(), 0);
+        for key in 0..100 {
+            map.insert(key, i64::from(key));
+        }
+        assert_eq!(map.summary(), 4950);
+        map.insert(10, 0);
+        map.delete(&99).unwrap();
+        assert_eq!(map.summary(), 4950 - 10 - 99);
+        assert!(map.update(&50, |v| *v += 1000));
+        assert_eq!(map.summary(), 4950 - 10 - 99 + 1000);
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_order_survives_rotations() {
+        let mut map: AugmentedTreeMap<u32, (), Span> = AugmentedTreeMap::default();
+        for key in (0..500).map(|i| i * 37 % 500) {
+            map.insert(key, ());
+        }
+        assert_eq!(map.summary(), Some((0, 499)));
+        for key in 0..250 {
+            map.delete(&key).unwrap();
+        }
+        assert_eq!(map.summary(), Some((250, 499)));
+    }
+
+    #[test]
+    fn test_range_fold_matches_scan() {
+        let mut map: AugmentedTreeMap<u32, i64, Sum> = AugmentedTreeMap::default();
+        for key in (0..200).map(|i| i * 7 % 200) {
+            map.insert(key * 2, i64::from(key));
+        }
+        let scan = |lo: u32, hi: u32| map.iter().filter(|(k, _)| (lo..hi).contains(*k)).map(|(_, v)| v).sum::<i64>();
+        for (lo, hi) in [(0, 400), (11, 12), (10, 11), (5, 3), (37, 251), (398, 1000)] {
+            assert_eq!(map.range_fold(lo..hi), scan(lo, hi));
+        }
+        assert_eq!(map.range_fold(10..=20), 5 + 6 + 7 + 8 + 9 + 10);
+        assert_eq!(map.range_fold((Bound::Excluded(10), Bound::Unbounded)), map.summary() - (0..=5).sum::<i64>());
+        assert_eq!(map.range_fold(..), map.summary());
+    }
+
+    #[test]
+    fn test_range_fold_combines_in_key_order() {
+        let mut map: AugmentedTreeMap<u32, (), Span> = AugmentedTreeMap::default();
+        for key in (0..300).map(|i| i * 37 % 300) {
+            map.insert(key, ());
+        }
+        assert_eq!(map.range_fold(17..=240), Some((17, 240)));
+        assert_eq!(map.range_fold(..100), Some((0, 99)));
+        assert_eq!(map.range_fold(300..), None);
+    }
+}
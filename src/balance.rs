@@ -0,0 +1,179 @@
+use crate::node::{Link, Node};
+use alloc::boxed::Box;
+
+// Strategy for keeping a `BinaryTreeMap` in shape. After every structural
+// change the map walks back up the search path, calling `update` to refresh
+// each node's metadata and then the matching hook, which may rotate and
+// returns the new root of that subtree.
+pub trait BalancePolicy<K, V>: Sized {
+    type Meta: Default;
+
+    // Recomputes `node.meta()` from its children. Sizes are already current.
+    fn update(_node: &mut Node<K, V, Self::Meta>) {}
+
+    fn after_insert(node: Box<Node<K, V, Self::Meta>>) -> Box<Node<K, V, Self::Meta>> {
+        node
+    }
+
+    fn after_delete(node: Box<Node<K, V, Self::Meta>>) -> Box<Node<K, V, Self::Meta>> {
+        node
+    }
+
+    // Settles work the metadata holds for the whole subtree into the node
+    // and its children. Called on each node the map descends through while
+    // inserting or deleting, and before a rotation moves it.
+    fn push_down(_node: &mut Node<K, V, Self::Meta>) {}
+
+    // Used when joining two trees under a new parent: `Greater` if `left` is
+    // too heavy to sit directly beside `right`, `Less` if `right` is, and
+    // `Equal` if the parent can simply adopt both.
+    fn join_direction(_left: Option<&Node<K, V, Self::Meta>>, _right: Option<&Node<K, V, Self::Meta>>) -> core::cmp::Ordering {
+        core::cmp::Ordering::Equal
+    }
+
+    // Checks this node's metadata and balance against its children, which
+    // `check_invariants` has already verified. Returns what is wrong.
+    fn check(_node: &Node<K, V, Self::Meta>) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+// Policies whose metadata depends only on keys and shape, so values can be
+// changed in place, as `par_iter_mut` does, without refreshing any node
+pub trait ValueIndependent<K, V>: BalancePolicy<K, V> {}
+
+// Plain binary search tree: the shape depends entirely on insertion order
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Unbalanced;
+
+impl<K, V> BalancePolicy<K, V> for Unbalanced {
+    type Meta = ();
+}
+
+impl<K, V> ValueIndependent<K, V> for Unbalanced {}
+
+// Height-balanced AVL tree; the metadata is the subtree height
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Avl;
+
+impl Avl {
+    fn height<K, V>(node: &Link<K, V, usize>) -> usize {
+        node.as_ref().map_or(0, |n| n.meta)
+    }
+
+    fn balance_factor<K, V>(node: &Node<K, V, usize>) -> isize {
+        Self::height(&node.left) as isize - Self::height(&node.right) as isize
+    }
+
+    fn rebalance<K, V>(mut node: Box<Node<K, V, usize>>) -> Box<Node<K, V, usize>> {
+        let factor = Self::balance_factor(&node);
+        if factor > 1 {
+            if node.left.as_ref().is_some_and(|left| Self::balance_factor(left) < 0) {
+                node.map_left::<Self, _>(Node::rotate_left::<Self>);
+            }
+            Node::rotate_right::<Self>(node)
+        } else if factor < -1 {
+            if node.right.as_ref().is_some_and(|right| Self::balance_factor(right) > 0) {
+                node.map_right::<Self, _>(Node::rotate_right::<Self>);
+            }
+            Node::rotate_left::<Self>(node)
+        } else {
+            node
+        }
+    }
+}
+
+impl<K, V> ValueIndependent<K, V> for Avl {}
+
+impl<K, V> BalancePolicy<K, V> for Avl {
+    type Meta = usize;
+
+    fn update(node: &mut Node<K, V, usize>) {
+        node.meta = 1 + Self::height(&node.left).max(Self::height(&node.right));
+    }
+
+    fn after_insert(node: Box<Node<K, V, usize>>) -> Box<Node<K, V, usize>> {
+        Self::rebalance(node)
+    }
+
+    fn after_delete(node: Box<Node<K, V, usize>>) -> Box<Node<K, V, usize>> {
+        Self::rebalance(node)
+    }
+
+    fn check(node: &Node<K, V, usize>) -> Result<(), &'static str> {
+        if node.meta != 1 + Self::height(&node.left).max(Self::height(&node.right)) {
+            return Err("stored height does not match the children");
+        }
+        if Self::balance_factor(node).abs() > 1 {
+            return Err("subtree heights differ by more than one");
+        }
+        Ok(())
+    }
+
+    fn join_direction(left: Option<&Node<K, V, usize>>, right: Option<&Node<K, V, usize>>) -> core::cmp::Ordering {
+        let left_height = left.map_or(0, |n| n.meta);
+        let right_height = right.map_or(0, |n| n.meta);
+        if left_height > right_height + 1 {
+            core::cmp::Ordering::Greater
+        } else if right_height > left_height + 1 {
+            core::cmp::Ordering::Less
+        } else {
+            core::cmp::Ordering::Equal
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BinaryTreeMap;
+
+    fn assert_avl<K, V>(node: &Link<K, V, usize>) {
+        if let Some(n) = node {
+            assert!(Avl::balance_factor(n).abs() <= 1);
+            assert_eq!(n.meta, 1 + Avl::height(&n.left).max(Avl::height(&n.right)));
+            assert_avl(&n.left);
+            assert_avl(&n.right);
+        }
+    }
+
+    #[test]
+    fn test_avl_sorted_inserts_stay_balanced() {
+        let mut map = BinaryTreeMap::with_policy(Avl);
+        for key in 0..1000 {
+            map.insert(key, key);
+        }
+
+        assert_avl(&map.root);
+        // AVL height is below 1.44 log2(n + 2)
+        assert!(Avl::height(&map.root) <= 14);
+    }
+
+    #[test]
+    fn test_avl_deletes_stay_balanced() {
+        let mut map = BinaryTreeMap::with_policy(Avl);
+        for key in 0..500 {
+            map.insert(key, key);
+        }
+        for key in (0..500).filter(|k| k % 3 != 0) {
+            assert_eq!(map.delete(&key), Ok(key));
+        }
+
+        assert_avl(&map.root);
+        assert_eq!(map.len(), 167);
+        assert_eq!(map.get(&300), Ok(&300));
+    }
+
+    #[test]
+    fn test_unbalanced_keeps_insertion_shape() {
+        let mut map: BinaryTreeMap<i32, ()> = BinaryTreeMap::new();
+        for key in 0..10 {
+            map.insert(key, ());
+        }
+
+        // Sorted input degenerates into a right spine
+        let root = map.root.as_deref().unwrap();
+        assert_eq!(root.key, 0);
+        assert!(root.left.is_none());
+    }
+}
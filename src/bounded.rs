@@ -0,0 +1,124 @@
+use crate::{Avl, BalancePolicy, BinaryTreeMap};
+use alloc::boxed::Box;
+
+// Which entry a full `BoundedTreeMap` gives up to make room. The age of a
+// key is its place in key order, as for timestamps or sequence numbers:
+// `OldestKey` evicts the smallest key and `NewestKey` the largest.
+// `Custom` returns the in-order index of the entry to evict.
+#[allow(clippy::type_complexity)]
+pub enum EvictionPolicy<K, V, P: BalancePolicy<K, V> = Avl> {
+    OldestKey,
+    NewestKey,
+    Custom(Box<dyn FnMut(&BinaryTreeMap<K, V, P>) -> usize>),
+}
+
+// Map holding at most `capacity` entries: inserting a new key into a full
+// map first evicts one according to its policy
+pub struct BoundedTreeMap<K, V, P: BalancePolicy<K, V> = Avl> {
+    map: BinaryTreeMap<K, V, P>,
+    capacity: usize,
+    eviction: EvictionPolicy<K, V, P>,
+}
+
+impl<K: Ord + Clone, V> BoundedTreeMap<K, V> {
+    pub fn with_capacity(capacity: usize, eviction: EvictionPolicy<K, V>) -> Self {
+        Self::with_policy(capacity, eviction, Avl)
+    }
+}
+
+impl<K: Ord + Clone, V, P: BalancePolicy<K, V>> BoundedTreeMap<K, V, P> {
+    pub fn with_policy(capacity: usize, eviction: EvictionPolicy<K, V, P>, policy: P) -> Self {
+        assert!(capacity > 0, "a bounded map needs room for at least one entry");
+        BoundedTreeMap { map: BinaryTreeMap::with_policy(policy), capacity, eviction }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn map(&self) -> &BinaryTreeMap<K, V, P> {
+        &self.map
+    }
+
+    pub fn into_map(self) -> BinaryTreeMap<K, V, P> {
+        self.map
+    }
+
+    // Returns the evicted entry, if the insert needed room. Updating an
+    // existing key never evicts.
+    pub fn insert(&mut self, key: K, value: V) -> Option<(K, V)> {
+        let evicted = if self.map.len() >= self.capacity && self.map.get(&key).is_err() {
+            self.evict()
+        } else {
+            None
+        };
+        self.map.insert(key, value);
+        evicted
+    }
+
+    pub fn delete(&mut self, key: &K) -> Result<V, crate::MapError> {
+        self.map.delete(key)
+    }
+
+    fn evict(&mut self) -> Option<(K, V)> {
+        match &mut self.eviction {
+            EvictionPolicy::OldestKey => self.map.pop_first(),
+            EvictionPolicy::NewestKey => self.map.pop_last(),
+            EvictionPolicy::Custom(choose) => {
+                let index = choose(&self.map);
+                let (key, _) = self.map.nth(index).expect("eviction index out of range");
+                let key = key.clone();
+                let value = self.map.delete(&key).ok()?;
+                Some((key, value))
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn keys<P: BalancePolicy<u32, u32>>(map: &BoundedTreeMap<u32, u32, P>) -> Vec<u32> {
+        map.map().iter().map(|(k, _)| *k).collect()
+    }
+
+    #[test]
+    fn test_evicts_by_key_order() {
+        let mut oldest = BoundedTreeMap::with_capacity(3, EvictionPolicy::OldestKey);
+        let mut newest = BoundedTreeMap::with_capacity(3, EvictionPolicy::NewestKey);
+        for key in [5, 1, 9] {
+            assert_eq!(oldest.insert(key, key), None);
+            assert_eq!(newest.insert(key, key), None);
+        }
+        assert_eq!(oldest.insert(5, 50), None);
+        assert_eq!(oldest.insert(7, 7), Some((1, 1)));
+        assert_eq!(newest.insert(7, 7), Some((9, 9)));
+        assert_eq!(keys(&oldest), [5, 7, 9]);
+        assert_eq!(keys(&newest), [1, 5, 7]);
+        assert_eq!(oldest.map().get(&5), Ok(&50));
+    }
+
+    #[test]
+    fn test_custom_eviction_picks_index() {
+        // Evict the median entry
+        let median = EvictionPolicy::Custom(Box::new(|map: &BinaryTreeMap<u32, u32, Avl>| map.len() / 2));
+        let mut map = BoundedTreeMap::with_capacity(4, median);
+        for key in 0..4 {
+            map.insert(key, key);
+        }
+        assert_eq!(map.insert(10, 10), Some((2, 2)));
+        assert_eq!(map.insert(11, 11), Some((3, 3)));
+        assert_eq!(keys(&map), [0, 1, 10, 11]);
+        assert_eq!(map.len(), map.capacity());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::builder::dedup_keep_last;
+use crate::node::{Link, Node};
+use crate::{BalancePolicy, BinaryTreeMap};
+use core::ops::RangeBounds;
+use alloc::vec::Vec;
+
+impl<K: Ord, V, P: BalancePolicy<K, V> + Default> BinaryTreeMap<K, V, P> {
+    // Builds a perfectly balanced tree in O(n) without comparing keys. The
+    // keys must already be strictly ascending; this is not checked.
+    pub fn from_sorted_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self::from_sorted_vec(iter.into_iter().collect())
+    }
+
+    pub fn from_sorted_vec(entries: Vec<(K, V)>) -> Self {
+        let count = entries.len();
+        let mut entries = entries.into_iter();
+        BinaryTreeMap::from_root(Self::build_balanced(&mut entries, count))
+    }
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Adds a batch of entries meant to arrive in ascending key order. The
+    // batch is built into a balanced subtree in O(k); if every key is above
+    // the current maximum it is spliced onto the right in O(log n),
+    // otherwise it is merged with `union`. Unsorted batches are sorted
+    // first, and later entries win over earlier ones and over the map.
+    pub fn extend_sorted<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let mut entries: Vec<(K, V)> = iter.into_iter().collect();
+        if !entries.windows(2).all(|pair| pair[0].0 < pair[1].0) {
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            dedup_keep_last(&mut entries);
+        }
+        let appends = match (Self::max_key(&self.root), entries.first()) {
+            (Some(max), Some((first, _))) => first > max,
+            _ => true,
+        };
+
+        let count = entries.len();
+        let batch = Self::build_balanced(&mut entries.into_iter(), count);
+        let root = self.root.take();
+        self.root = if appends {
+            Self::join_pair(root, batch)
+        } else {
+            Self::from_root(root).union(Self::from_root(batch), |_, _, new| new).root.take()
+        };
+    }
+
+    // Consumes the next `count` entries and returns them as a balanced
+    // subtree; the left half is built first so entries are taken in order
+    pub(crate) fn build_balanced(entries: &mut impl Iterator<Item = (K, V)>, count: usize) -> Link<K, V, P::Meta> {
+        if count == 0 {
+            return None;
+        }
+        let left_count = count / 2;
+        let left = Self::build_balanced(entries, left_count);
+        let (key, value) = entries.next().expect("fewer entries than counted");
+        let right = Self::build_balanced(entries, count - left_count - 1);
+
+        let mut node = Node::leaf::<P>(key, value);
+        node.left = left;
+        node.right = right;
+        node.refresh::<P>();
+        Some(node)
+    }
+}
+
+impl<K: Ord + Clone, V: Clone, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Copies the entries in `range` into a new balanced map, in O(k + log n)
+    // for k entries: the range iterator finds them in order, so the build
+    // needs no sorting
+    pub fn clone_range<R: RangeBounds<K>>(&self, range: R) -> Self {
+        let entries: Vec<(K, V)> = self.range(range).map(|(k, v)| (k.clone(), v.clone())).collect();
+        let count = entries.len();
+        Self::from_root(Self::build_balanced(&mut entries.into_iter(), count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Avl, BinaryTreeMap, MapError};
+
+    #[test]
+    fn test_from_sorted_vec_is_minimal_height() {
+        let entries: Vec<_> = (0..1000).map(|key| (key, key * 3)).collect();
+        let map: BinaryTreeMap<_, _> = BinaryTreeMap::from_sorted_vec(entries);
+
+        assert_eq!(map.len(), 1000);
+        assert_eq!(map.height(), 10);
+        assert!(map.is_balanced());
+        assert_eq!(map.get(&999), Ok(&2997));
+        assert_eq!(map.nth(123), Some((&123, &369)));
+    }
+
+    #[test]
+    fn test_from_sorted_iter_with_policy() {
+        let mut map: BinaryTreeMap<_, _, Avl> = BinaryTreeMap::from_sorted_iter((0..20).map(|key| (key, ())));
+        assert_eq!(*map.root.as_ref().unwrap().meta(), map.height());
+
+        for key in 20..40 {
+            map.insert(key, ());
+        }
+        assert!(map.is_balanced());
+    }
+
+    #[test]
+    fn test_extend_sorted_appends_past_maximum() {
+        let mut map = BinaryTreeMap::with_policy(Avl);
+        for key in 0..100 {
+            map.insert(key, "old");
+        }
+
+        map.extend_sorted((100..1000).map(|key| (key, "new")));
+
+        assert_eq!(map.len(), 1000);
+        assert!(map.is_balanced());
+        assert_eq!(map.get(&99), Ok(&"old"));
+        assert_eq!(map.get(&100), Ok(&"new"));
+    }
+
+    #[test]
+    fn test_extend_sorted_merges_overlapping_batch() {
+        let mut map: BinaryTreeMap<_, _> = BinaryTreeMap::from_sorted_iter((0..10).map(|key| (key * 2, "old")));
+
+        map.extend_sorted([(5, "new"), (3, "new"), (4, "new"), (5, "newer")]);
+
+        assert_eq!(map.len(), 12);
+        assert_eq!(map.get(&4), Ok(&"new"));
+        assert_eq!(map.get(&5), Ok(&"newer"));
+        assert_eq!(map.get(&6), Ok(&"old"));
+        assert_eq!(map.rank(&6), 5);
+    }
+
+    #[test]
+    fn test_clone_range_shards_by_key() {
+        let mut map = BinaryTreeMap::with_policy(Avl);
+        for key in 0..1000 {
+            map.insert(key, key * 2);
+        }
+        let shard = map.clone_range(250..500);
+        assert_eq!(shard.len(), 250);
+        assert_eq!(shard.height(), 8);
+        assert_eq!(shard.get(&250), Ok(&500));
+        assert_eq!(shard.get(&500), Err(MapError::KeyNotFound));
+        assert_eq!(shard.check_invariants(), Ok(()));
+        assert_eq!(map.len(), 1000);
+        assert!(map.clone_range(2000..).is_empty());
+    }
+
+    #[test]
+    fn test_from_empty_iter() {
+        let map: BinaryTreeMap<i32, i32> = BinaryTreeMap::from_sorted_iter(std::iter::empty());
+        assert!(map.is_empty());
+        assert_eq!(map.get(&1), Err(MapError::KeyNotFound));
+    }
+}
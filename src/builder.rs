@@ -0,0 +1,98 @@
+use crate::{BalancePolicy, BinaryTreeMap};
+use alloc::vec::Vec;
+
+// Collects entries in any order and builds a balanced map in one shot:
+// sort, drop duplicate keys (the last one pushed wins), then build in O(n).
+#[derive(Debug, Clone)]
+pub struct MapBuilder<K, V> {
+    entries: Vec<(K, V)>,
+}
+
+impl<K: Ord, V> Default for MapBuilder<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> MapBuilder<K, V> {
+    pub fn new() -> Self {
+        MapBuilder { entries: Vec::new() }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        MapBuilder { entries: Vec::with_capacity(capacity) }
+    }
+
+    pub fn push(&mut self, key: K, value: V) -> &mut Self {
+        self.entries.push((key, value));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn build<P: BalancePolicy<K, V> + Default>(mut self) -> BinaryTreeMap<K, V, P> {
+        // Stable sort keeps pushes of the same key in order, so keeping the
+        // final element of each run gives last-write-wins semantics
+        self.entries.sort_by(|a, b| a.0.cmp(&b.0));
+        dedup_keep_last(&mut self.entries);
+        BinaryTreeMap::from_sorted_vec(self.entries)
+    }
+}
+
+// Collapses runs of equal keys in a stably sorted vector to their last entry
+pub(crate) fn dedup_keep_last<K: Ord, V>(entries: &mut Vec<(K, V)>) {
+    entries.dedup_by(|later, earlier| {
+        if later.0 == earlier.0 {
+            core::mem::swap(later, earlier);
+            true
+        } else {
+            false
+        }
+    });
+}
+
+impl<K: Ord, V> Extend<(K, V)> for MapBuilder<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.entries.extend(iter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MapError;
+
+    #[test]
+    fn test_build_sorts_and_balances() {
+        let mut builder = MapBuilder::new();
+        for key in (0..100).rev() {
+            builder.push(key, key + 1);
+        }
+        let map: BinaryTreeMap<_, _> = builder.build();
+
+        assert_eq!(map.len(), 100);
+        assert_eq!(map.height(), 7);
+        assert_eq!(map.nth(0), Some((&0, &1)));
+        assert_eq!(map.get(&99), Ok(&100));
+    }
+
+    #[test]
+    fn test_duplicate_keys_last_wins() {
+        let mut builder = MapBuilder::new();
+        builder.push(2, "first").push(1, "only").push(2, "second");
+        builder.extend([(3, "a"), (2, "third"), (3, "b")]);
+        let map: BinaryTreeMap<_, _> = builder.build();
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&1), Ok(&"only"));
+        assert_eq!(map.get(&2), Ok(&"third"));
+        assert_eq!(map.get(&3), Ok(&"b"));
+        assert_eq!(map.get(&4), Err(MapError::KeyNotFound));
+    }
+}
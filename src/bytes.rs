@@ -0,0 +1,325 @@
+use crate::node::{drop_link, Link, Node};
+use crate::{BalancePolicy, BinaryTreeMap, MapError};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// Markers in the preorder stream: every node is `NODE key value` followed by
+// its left and right subtrees, every empty child a single `EMPTY`
+const EMPTY: u8 = 0;
+const NODE: u8 = 1;
+
+// Compact self-delimiting binary encoding for keys and values. Integers are
+// LEB128 varints (signed ones zigzag-encoded first), strings and byte
+// vectors a varint length followed by the bytes.
+pub trait ByteCodec: Sized {
+    fn encode(&self, out: &mut Vec<u8>);
+    fn decode(input: &mut &[u8]) -> Result<Self, MapError>;
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u128) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+fn read_varint(input: &mut &[u8]) -> Result<u128, MapError> {
+    let mut n = 0u128;
+    for shift in (0..128).step_by(7) {
+        let (&byte, rest) = input.split_first().ok_or(MapError::InvalidEncoding)?;
+        *input = rest;
+        n |= u128::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+    Err(MapError::InvalidEncoding)
+}
+
+fn read_bytes<'a>(input: &mut &'a [u8]) -> Result<&'a [u8], MapError> {
+    let len = usize::try_from(read_varint(input)?).map_err(|_| MapError::InvalidEncoding)?;
+    if len > input.len() {
+        return Err(MapError::InvalidEncoding);
+    }
+    let (bytes, rest) = input.split_at(len);
+    *input = rest;
+    Ok(bytes)
+}
+
+macro_rules! unsigned_codec {
+    ($($t:ty),*) => {$(
+        impl ByteCodec for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                write_varint(out, *self as u128);
+            }
+
+            fn decode(input: &mut &[u8]) -> Result<Self, MapError> {
+                <$t>::try_from(read_varint(input)?).map_err(|_| MapError::InvalidEncoding)
+            }
+        }
+    )*};
+}
+
+macro_rules! signed_codec {
+    ($($t:ty),*) => {$(
+        impl ByteCodec for $t {
+            fn encode(&self, out: &mut Vec<u8>) {
+                let n = *self as i128;
+                write_varint(out, ((n << 1) ^ (n >> 127)) as u128);
+            }
+
+            fn decode(input: &mut &[u8]) -> Result<Self, MapError> {
+                let n = read_varint(input)?;
+                let n = (n >> 1) as i128 ^ -((n & 1) as i128);
+                <$t>::try_from(n).map_err(|_| MapError::InvalidEncoding)
+            }
+        }
+    )*};
+}
+
+unsigned_codec!(u8, u16, u32, u64, u128, usize);
+signed_codec!(i8, i16, i32, i64, i128, isize);
+
+impl ByteCodec for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(u8::from(*self));
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, MapError> {
+        match u8::decode(input)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(MapError::InvalidEncoding),
+        }
+    }
+}
+
+impl ByteCodec for char {
+    fn encode(&self, out: &mut Vec<u8>) {
+        u32::from(*self).encode(out);
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, MapError> {
+        char::from_u32(u32::decode(input)?).ok_or(MapError::InvalidEncoding)
+    }
+}
+
+impl ByteCodec for () {
+    fn encode(&self, _out: &mut Vec<u8>) {}
+
+    fn decode(_input: &mut &[u8]) -> Result<Self, MapError> {
+        Ok(())
+    }
+}
+
+impl ByteCodec for Vec<u8> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        write_varint(out, self.len() as u128);
+        out.extend_from_slice(self);
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, MapError> {
+        read_bytes(input).map(<[u8]>::to_vec)
+    }
+}
+
+impl ByteCodec for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        write_varint(out, self.len() as u128);
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, MapError> {
+        let bytes = read_bytes(input)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| MapError::InvalidEncoding)
+    }
+}
+
+// Every node and every empty child in preorder, the order both `to_bytes`
+// and `shape` write trees in
+pub(crate) fn preorder<K, V, M>(root: &Link<K, V, M>) -> impl Iterator<Item = Option<&Node<K, V, M>>> {
+    let mut pending = vec![root.as_deref()];
+    core::iter::from_fn(move || {
+        let link = pending.pop()?;
+        if let Some(node) = link {
+            pending.push(node.right());
+            pending.push(node.left());
+        }
+        Some(link)
+    })
+}
+
+// Rebuilds a tree from its `preorder` items one at a time
+pub(crate) struct PreorderBuilder<K, V, P: BalancePolicy<K, V>> {
+    // Nodes waiting for their children, with whether the left one is set
+    #[allow(clippy::type_complexity)]
+    open: Vec<(Box<Node<K, V, P::Meta>>, bool)>,
+}
+
+// Input that ends or fails partway leaves open nodes holding finished
+// subtrees of any depth, which are freed without recursing
+impl<K, V, P: BalancePolicy<K, V>> Drop for PreorderBuilder<K, V, P> {
+    fn drop(&mut self) {
+        for (node, _) in self.open.drain(..) {
+            drop_link(Some(node));
+        }
+    }
+}
+
+impl<K, V, P: BalancePolicy<K, V>> PreorderBuilder<K, V, P> {
+    pub(crate) fn new() -> Self {
+        PreorderBuilder { open: Vec::new() }
+    }
+
+    pub(crate) fn node(&mut self, key: K, value: V) {
+        self.open.push((Node::leaf::<P>(key, value), false));
+    }
+
+    // Returns the root once this empty child completes the tree
+    pub(crate) fn empty(&mut self) -> Option<Link<K, V, P::Meta>> {
+        // Hand each finished subtree to its parent, closing every parent
+        // that now has both children. Without a parent it is the root.
+        let mut finished = None;
+        loop {
+            match self.open.last_mut() {
+                None => return Some(finished),
+                Some((parent, left_done @ false)) => {
+                    parent.left = finished;
+                    *left_done = true;
+                    return None;
+                },
+                Some(_) => {
+                    let (mut parent, _) = self.open.pop().unwrap();
+                    parent.right = finished;
+                    parent.refresh::<P>();
+                    finished = Some(parent);
+                },
+            }
+        }
+    }
+}
+
+impl<K: Ord + ByteCodec, V: ByteCodec, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Preorder encoding with null markers, which records the exact shape.
+    // Both directions use explicit stacks, so any depth round-trips.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        write_varint(&mut out, self.len() as u128);
+        for link in preorder(&self.root) {
+            match link {
+                None => out.push(EMPTY),
+                Some(node) => {
+                    out.push(NODE);
+                    node.key.encode(&mut out);
+                    node.value.encode(&mut out);
+                },
+            }
+        }
+        out
+    }
+
+    // Rejects truncated or trailing input, a wrong entry count, and trees
+    // failing `check_invariants`: keys out of order, or a shape the policy
+    // does not allow
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, MapError> {
+        let mut input = bytes;
+        let count = read_varint(&mut input)?;
+        let mut builder = PreorderBuilder::<K, V, P>::new();
+        let root = loop {
+            let (&marker, rest) = input.split_first().ok_or(MapError::InvalidEncoding)?;
+            input = rest;
+            match marker {
+                EMPTY => {
+                    if let Some(root) = builder.empty() {
+                        break root;
+                    }
+                },
+                NODE => {
+                    let key = K::decode(&mut input)?;
+                    let value = V::decode(&mut input)?;
+                    builder.node(key, value);
+                },
+                _ => return Err(MapError::InvalidEncoding),
+            }
+        };
+        let map = BinaryTreeMap::from_root(root);
+        if !input.is_empty() || map.len() as u128 != count || map.check_invariants().is_err() {
+            return Err(MapError::InvalidEncoding);
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{BinaryTreeMap, ByteCodec, MapError, Node, Unbalanced, WeightBalancedTreeMap};
+
+    #[test]
+    fn test_round_trip_preserves_shape() {
+        let mut map = BinaryTreeMap::new();
+        for key in [0i64, -300, 7, 200_000, -1] {
+            map.insert(key, format!("v{key}"));
+        }
+        let bytes = map.to_bytes();
+        let copy: BinaryTreeMap<i64, String> = BinaryTreeMap::from_bytes(&bytes).unwrap();
+        assert_eq!(copy.height(), map.height());
+        assert_eq!(copy.get(&-300), Ok(&"v-300".to_string()));
+        assert_eq!(copy.to_bytes(), bytes);
+
+        let empty: WeightBalancedTreeMap<u8, ()> = BinaryTreeMap::from_bytes(&[0, 0]).unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_malformed_input() {
+        let mut map = BinaryTreeMap::new();
+        map.insert(2u32, true);
+        map.insert(1, false);
+        let bytes = map.to_bytes();
+        let decode = |bytes: &[u8]| BinaryTreeMap::<u32, bool>::from_bytes(bytes);
+
+        assert_eq!(decode(&bytes[..bytes.len() - 1]).unwrap_err(), MapError::InvalidEncoding);
+        assert_eq!(decode(&[bytes.as_slice(), &[0]].concat()).unwrap_err(), MapError::InvalidEncoding);
+        // Count says 3, and separately keys 2 then 3 to the left of 2
+        assert_eq!(decode(&[3, 1, 2, 1, 0, 0, 0, 0]).unwrap_err(), MapError::InvalidEncoding);
+        assert_eq!(decode(&[2, 1, 2, 1, 1, 3, 0, 0, 0, 0]).unwrap_err(), MapError::InvalidEncoding);
+
+        // A spine of three is a fine unbalanced tree but not an AVL tree
+        let spine = crate::right_spine(3).to_bytes();
+        assert!(BinaryTreeMap::<u32, u32>::from_bytes(&spine).is_ok());
+        assert_eq!(BinaryTreeMap::<u32, u32, crate::Avl>::from_bytes(&spine).unwrap_err(), MapError::InvalidEncoding);
+    }
+
+    #[test]
+    fn test_truncated_deep_spine_is_rejected() {
+        // A left spine whose final empty child is missing, so decoding stops
+        // with the whole tree still hanging off the open root
+        let depth = 200_000u32;
+        let mut bytes = Vec::new();
+        depth.encode(&mut bytes);
+        for key in (0..depth).rev() {
+            bytes.push(super::NODE);
+            key.encode(&mut bytes);
+        }
+        bytes.resize(bytes.len() + depth as usize, super::EMPTY);
+        let decoded = BinaryTreeMap::<u32, ()>::from_bytes(&bytes);
+        assert_eq!(decoded.unwrap_err(), MapError::InvalidEncoding);
+    }
+
+    #[test]
+    fn test_round_trip_degenerate_tree() {
+        let mut map = BinaryTreeMap::new();
+        for key in (0..100_000u32).rev() {
+            let mut node = Node::leaf::<Unbalanced>(key, ());
+            node.right = map.root.take();
+            node.refresh::<Unbalanced>();
+            map.root = Some(node);
+        }
+        let copy: BinaryTreeMap<u32, ()> = BinaryTreeMap::from_bytes(&map.to_bytes()).unwrap();
+        assert_eq!(copy.len(), 100_000);
+        assert_eq!(copy.height(), 100_000);
+    }
+}
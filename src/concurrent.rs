@@ -0,0 +1,218 @@
+use crate::MapError;
+use parking_lot::{ArcRwLockReadGuard, ArcRwLockWriteGuard, RawRwLock, RwLock};
+use std::cmp::Ordering;
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::Arc;
+
+// Every link is its own lock. Sharing it through an `Arc` lets a guard own
+// the slot it locks, so traversals can hand guards down the tree one level
+// at a time instead of keeping the whole path borrowed.
+type Slot<K, V> = Arc<RwLock<Option<Entry<K, V>>>>;
+type SlotGuard<K, V> = ArcRwLockWriteGuard<RawRwLock, Option<Entry<K, V>>>;
+type SlotReadGuard<K, V> = ArcRwLockReadGuard<RawRwLock, Option<Entry<K, V>>>;
+
+#[derive(Debug)]
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    left: Slot<K, V>,
+    right: Slot<K, V>,
+}
+
+fn empty<K, V>() -> Slot<K, V> {
+    Arc::new(RwLock::new(None))
+}
+
+// Unbalanced map safe to share between threads. Operations use
+// hand-over-hand locking: a child link is locked before its parent is
+// released, so readers share every lock on their path and writers only hold
+// the link they are about to change. Writers on disjoint subtrees proceed in
+// parallel once their paths diverge.
+#[derive(Debug)]
+pub struct BinaryTreeMap<K, V> {
+    root: Slot<K, V>,
+    len: AtomicUsize,
+}
+
+impl<K: Ord, V> Default for BinaryTreeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Nested `Arc`s would drop recursively, once per level
+impl<K, V> Drop for BinaryTreeMap<K, V> {
+    fn drop(&mut self) {
+        let mut pending = vec![self.root.write().take()];
+        while let Some(link) = pending.pop() {
+            if let Some(entry) = link {
+                pending.push(entry.left.write().take());
+                pending.push(entry.right.write().take());
+            }
+        }
+    }
+}
+
+impl<K: Ord, V> BinaryTreeMap<K, V> {
+    pub fn new() -> Self {
+        BinaryTreeMap {
+            root: empty(),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.load(atomic::Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get(&self, key: &K) -> Result<V, MapError>
+    where
+        V: Clone,
+    {
+        self.get_with(key, V::clone)
+    }
+
+    // Values cannot be borrowed past the lock that guards them, so callers
+    // either clone them out or inspect them in place
+    pub fn get_with<R, F: FnOnce(&V) -> R>(&self, key: &K, f: F) -> Result<R, MapError> {
+        let mut guard: SlotReadGuard<K, V> = self.root.read_arc();
+        loop {
+            let next = match &*guard {
+                None => return Err(MapError::KeyNotFound),
+                Some(entry) => match key.cmp(&entry.key) {
+                    Ordering::Equal => return Ok(f(&entry.value)),
+                    Ordering::Less => entry.left.read_arc(),
+                    Ordering::Greater => entry.right.read_arc(),
+                },
+            };
+            guard = next;
+        }
+    }
+
+    // Write-locks the link holding `key`, or the empty link where it would
+    // be inserted
+    fn lock_slot(&self, key: &K) -> SlotGuard<K, V> {
+        let mut guard = self.root.write_arc();
+        loop {
+            let next = match &*guard {
+                None => return guard,
+                Some(entry) => match key.cmp(&entry.key) {
+                    Ordering::Equal => return guard,
+                    Ordering::Less => entry.left.write_arc(),
+                    Ordering::Greater => entry.right.write_arc(),
+                },
+            };
+            guard = next;
+        }
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        let mut slot = self.lock_slot(&key);
+        match &mut *slot {
+            Some(entry) => entry.value = value, // Update existing value
+            None => {
+                *slot = Some(Entry { key, value, left: empty(), right: empty() });
+                self.len.fetch_add(1, atomic::Ordering::Relaxed);
+            },
+        }
+    }
+
+    pub fn delete(&self, key: &K) -> Result<V, MapError> {
+        let mut slot = self.lock_slot(key);
+        let entry = slot.as_ref().ok_or(MapError::KeyNotFound)?;
+        let mut left = entry.left.write_arc();
+        let mut right = entry.right.write_arc();
+        let value = if left.is_none() || right.is_none() {
+            // At most one child: it takes the entry's place
+            let child = left.take().or_else(|| right.take());
+            std::mem::replace(&mut *slot, child).expect("target is locked").value
+        } else {
+            // Two children: move the in-order successor's entry up. The
+            // target stays locked, so nothing new can enter the right
+            // subtree while we walk down its left spine.
+            drop(left);
+            let mut cursor = right;
+            loop {
+                let next = cursor.as_ref().map(|entry| entry.left.write_arc());
+                match next {
+                    Some(next) if next.is_some() => cursor = next,
+                    _ => break,
+                }
+            }
+            let successor = cursor.take().expect("cursor rests on the successor");
+            *cursor = successor.right.write().take();
+            let target = slot.as_mut().expect("target is locked");
+            target.key = successor.key;
+            std::mem::replace(&mut target.value, successor.value)
+        };
+        self.len.fetch_sub(1, atomic::Ordering::Relaxed);
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    // Visits 0..n in a scattered order so the tree stays shallow
+    fn scattered(n: u64) -> impl Iterator<Item = u64> {
+        (0..n).map(move |i| i * 7919 % n)
+    }
+
+    #[test]
+    fn test_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<BinaryTreeMap<String, Vec<u8>>>();
+    }
+
+    #[test]
+    fn test_parallel_inserts_on_disjoint_keys() {
+        let map = BinaryTreeMap::new();
+        thread::scope(|s| {
+            for t in 0..4 {
+                let map = &map;
+                s.spawn(move || {
+                    for i in scattered(1000) {
+                        map.insert(i * 4 + t, i);
+                    }
+                });
+            }
+        });
+        assert_eq!(map.len(), 4000);
+        for key in 0..4000 {
+            assert_eq!(map.get(&key), Ok(key / 4));
+        }
+    }
+
+    #[test]
+    fn test_readers_alongside_deleting_writer() {
+        let map = BinaryTreeMap::new();
+        for key in scattered(2000) {
+            map.insert(key, key * 10);
+        }
+        thread::scope(|s| {
+            s.spawn(|| {
+                for key in (0..2000).filter(|k| k % 2 == 1) {
+                    assert_eq!(map.delete(&key), Ok(key * 10));
+                }
+            });
+            for _ in 0..3 {
+                s.spawn(|| {
+                    // Even keys are never touched and must stay visible
+                    for key in (0..2000).filter(|k| k % 2 == 0) {
+                        assert_eq!(map.get_with(&key, |v| *v), Ok(key * 10));
+                    }
+                });
+            }
+        });
+        assert_eq!(map.len(), 1000);
+        assert_eq!(map.get(&1), Err(MapError::KeyNotFound));
+        assert_eq!(map.delete(&1), Err(MapError::KeyNotFound));
+        assert_eq!(map.get(&1998), Ok(19980));
+    }
+}
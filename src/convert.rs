@@ -0,0 +1,119 @@
+use crate::{BalancePolicy, BinaryTreeMap};
+#[cfg(feature = "std")]
+use crate::MapBuilder;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::hash::{BuildHasher, Hash};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Consumes the map into its entries in key order. Uses an explicit stack
+    // rather than recursion so degenerate trees can't overflow.
+    pub fn into_sorted_vec(mut self) -> Vec<(K, V)> {
+        let mut entries = Vec::with_capacity(self.len());
+        let mut stack = Vec::new();
+        let mut current = self.root.take();
+        loop {
+            while let Some(mut node) = current {
+                current = node.left.take();
+                stack.push(node);
+            }
+            let Some(node) = stack.pop() else {
+                break;
+            };
+            let node = *node;
+            entries.push((node.key, node.value));
+            current = node.right;
+        }
+        entries
+    }
+}
+
+// A `BTreeMap` iterates in ascending order already, so this is the O(n)
+// balanced build with no sorting
+impl<K: Ord, V, P: BalancePolicy<K, V> + Default> From<BTreeMap<K, V>> for BinaryTreeMap<K, V, P> {
+    fn from(map: BTreeMap<K, V>) -> Self {
+        Self::from_sorted_iter(map)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Ord, V, S, P: BalancePolicy<K, V> + Default> From<HashMap<K, V, S>> for BinaryTreeMap<K, V, P> {
+    fn from(map: HashMap<K, V, S>) -> Self {
+        let mut builder = MapBuilder::with_capacity(map.len());
+        builder.extend(map);
+        builder.build()
+    }
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> From<BinaryTreeMap<K, V, P>> for BTreeMap<K, V> {
+    fn from(map: BinaryTreeMap<K, V, P>) -> Self {
+        map.into_sorted_vec().into_iter().collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K, V, S, P> From<BinaryTreeMap<K, V, P>> for HashMap<K, V, S>
+where
+    K: Ord + Hash,
+    S: BuildHasher + Default,
+    P: BalancePolicy<K, V>,
+{
+    fn from(map: BinaryTreeMap<K, V, P>) -> Self {
+        map.into_sorted_vec().into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Avl, BinaryTreeMap};
+    use std::collections::BTreeMap;
+    #[cfg(feature = "std")]
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_into_sorted_vec() {
+        let mut map = BinaryTreeMap::new();
+        for key in [50, 30, 70, 20, 40, 60, 80] {
+            map.insert(key, key.to_string());
+        }
+
+        let entries = map.into_sorted_vec();
+        let keys: Vec<_> = entries.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, vec![20, 30, 40, 50, 60, 70, 80]);
+        assert_eq!(entries[3], (50, "50".to_string()));
+    }
+
+    #[test]
+    fn test_into_sorted_vec_of_degenerate_tree() {
+        let mut map = BinaryTreeMap::new();
+        for key in (0..2_000).rev() {
+            map.insert(key, ());
+        }
+
+        let entries = map.into_sorted_vec();
+        assert_eq!(entries.len(), 2_000);
+        assert!(entries.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    }
+
+    #[test]
+    fn test_btree_map_round_trip() {
+        let source: BTreeMap<u32, u32> = (0..100).map(|key| (key, key * 2)).collect();
+        let map: BinaryTreeMap<u32, u32, Avl> = BinaryTreeMap::from(source.clone());
+        assert_eq!(map.height(), 7);
+        assert_eq!(map.get(&42), Ok(&84));
+        assert_eq!(BTreeMap::from(map), source);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_hash_map_round_trip() {
+        let source: HashMap<String, usize> = ["b", "c", "a"].iter().map(|s| (s.to_string(), s.len())).collect();
+        let map: BinaryTreeMap<String, usize> = source.clone().into();
+        assert_eq!(map.nth(0), Some((&"a".to_string(), &1)));
+        let back: HashMap<String, usize> = map.into();
+        assert_eq!(back, source);
+    }
+}
@@ -0,0 +1,166 @@
+use crate::{BalancePolicy, BinaryTreeMap};
+use core::fmt::Display;
+use core::str::FromStr;
+use std::io::{self, Error, ErrorKind, Read, Write};
+
+// Quotes a field when it holds the delimiter, a quote or a line break,
+// doubling any quotes inside, as RFC 4180 does
+fn write_field<W: Write>(w: &mut W, field: &str, delimiter: char) -> io::Result<()> {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        write!(w, "\"{}\"", field.replace('"', "\"\""))
+    } else {
+        w.write_all(field.as_bytes())
+    }
+}
+
+// Splits `text` into records of fields, honouring quotes. Quoted fields may
+// span lines; both `\n` and `\r\n` end a record, and a final line break is
+// optional.
+fn parse_records(text: &str, delimiter: char) -> Result<Vec<Vec<String>>, String> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if field.is_empty() => loop {
+                match chars.next() {
+                    Some('"') if chars.peek() == Some(&'"') => {
+                        chars.next();
+                        field.push('"');
+                    },
+                    Some('"') => break,
+                    Some(c) => {
+                        line += usize::from(c == '\n');
+                        field.push(c);
+                    },
+                    None => return Err(format!("unterminated quote on line {line}")),
+                }
+            },
+            c if c == delimiter => record.push(core::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {},
+            '\n' => {
+                record.push(core::mem::take(&mut field));
+                records.push(core::mem::take(&mut record));
+                line += 1;
+            },
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    Ok(records)
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // One `key,value` row per entry in key order, written with `Display`
+    pub fn to_csv<W: Write>(&self, w: W) -> io::Result<()>
+    where
+        K: Display,
+        V: Display,
+    {
+        self.write_delimited(w, ',')
+    }
+
+    pub fn to_tsv<W: Write>(&self, w: W) -> io::Result<()>
+    where
+        K: Display,
+        V: Display,
+    {
+        self.write_delimited(w, '\t')
+    }
+
+    fn write_delimited<W: Write>(&self, mut w: W, delimiter: char) -> io::Result<()>
+    where
+        K: Display,
+        V: Display,
+    {
+        for (key, value) in self.iter() {
+            write_field(&mut w, &key.to_string(), delimiter)?;
+            write!(w, "{delimiter}")?;
+            write_field(&mut w, &value.to_string(), delimiter)?;
+            w.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, V> + Default> BinaryTreeMap<K, V, P> {
+    // Parses two-field rows with `FromStr`; later rows win over earlier ones
+    // with the same key. Malformed rows fail with `InvalidData` naming the
+    // row.
+    pub fn from_csv<R: Read>(r: R) -> io::Result<Self>
+    where
+        K: FromStr,
+        V: FromStr,
+    {
+        Self::read_delimited(r, ',')
+    }
+
+    pub fn from_tsv<R: Read>(r: R) -> io::Result<Self>
+    where
+        K: FromStr,
+        V: FromStr,
+    {
+        Self::read_delimited(r, '\t')
+    }
+
+    fn read_delimited<R: Read>(mut r: R, delimiter: char) -> io::Result<Self>
+    where
+        K: FromStr,
+        V: FromStr,
+    {
+        let mut text = String::new();
+        r.read_to_string(&mut text)?;
+        let invalid = |message: String| Error::new(ErrorKind::InvalidData, message);
+        let mut map = BinaryTreeMap::default();
+        for (row, record) in parse_records(&text, delimiter).map_err(invalid)?.into_iter().enumerate() {
+            let [key, value] = <[String; 2]>::try_from(record).map_err(|_| invalid(format!("row {} does not have two fields", row + 1)))?;
+            let key = key.parse().map_err(|_| invalid(format!("row {} has an unparsable key", row + 1)))?;
+            let value = value.parse().map_err(|_| invalid(format!("row {} has an unparsable value", row + 1)))?;
+            map.insert(key, value);
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Avl, BinaryTreeMap};
+
+    #[test]
+    fn test_csv_round_trip_with_quoting() {
+        let mut map: BinaryTreeMap<String, i32, Avl> = BinaryTreeMap::default();
+        for (key, value) in [("plain", 1), ("with, comma", 2), ("say \"hi\"", 3), ("two\nlines", -4)] {
+            map.insert(key.to_string(), value);
+        }
+        let mut csv = Vec::new();
+        map.to_csv(&mut csv).unwrap();
+        assert_eq!(
+            String::from_utf8(csv.clone()).unwrap(),
+            "plain,1\n\"say \"\"hi\"\"\",3\n\"two\nlines\",-4\n\"with, comma\",2\n"
+        );
+        let read = BinaryTreeMap::<String, i32, Avl>::from_csv(csv.as_slice()).unwrap();
+        assert!(read.iter().eq(map.iter()));
+
+        let mut tsv = Vec::new();
+        map.to_tsv(&mut tsv).unwrap();
+        let read = BinaryTreeMap::<String, i32, Avl>::from_tsv(tsv.as_slice()).unwrap();
+        assert!(read.iter().eq(map.iter()));
+    }
+
+    #[test]
+    fn test_from_csv_reports_bad_rows() {
+        let map = BinaryTreeMap::<u32, u32>::from_csv("1,10\r\n2,20\r\n1,11".as_bytes()).unwrap();
+        assert!(map.iter().eq([(&1, &11), (&2, &20)]));
+
+        let err = BinaryTreeMap::<u32, u32>::from_csv("1,10\n2,x\n".as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "row 2 has an unparsable value");
+        let err = BinaryTreeMap::<u32, u32>::from_csv("1,10,100\n".as_bytes()).unwrap_err();
+        assert_eq!(err.to_string(), "row 1 does not have two fields");
+        assert!(BinaryTreeMap::<u32, u32>::from_csv("\"1,10\n".as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use crate::{BalancePolicy, BinaryTreeMap, MapError, ZipEntry};
+use alloc::vec::Vec;
+
+// What turns one map into another, each list in key order. Removed and
+// changed entries keep the value they replace, so a patch can tell whether
+// its target still matches the map it was computed from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapDiff<K, V> {
+    pub added: Vec<(K, V)>,
+    pub removed: Vec<(K, V)>,
+    // Key, old value, new value
+    pub changed: Vec<(K, V, V)>,
+}
+
+impl<K, V> Default for MapDiff<K, V> {
+    fn default() -> Self {
+        MapDiff { added: Vec::new(), removed: Vec::new(), changed: Vec::new() }
+    }
+}
+
+impl<K, V> MapDiff<K, V> {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+// What `apply` does with an entry that no longer matches the diff: the map
+// holds a different value than the diff expects. Entries already in the
+// diff's target state are never conflicts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    // Apply nothing and return `MapError::Conflict`
+    Fail,
+    // Keep the map's entry
+    Skip,
+    // Apply the diff's entry regardless
+    Overwrite,
+}
+
+impl<K: Ord, V: PartialEq, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Applies `diff` as computed by `diff`, so that `a.apply(a.diff(&b), _)`
+    // leaves `a` equal to `b`
+    pub fn apply(&mut self, diff: MapDiff<K, V>, policy: ConflictPolicy) -> Result<(), MapError> {
+        if policy == ConflictPolicy::Fail && self.conflicts(&diff) {
+            return Err(MapError::Conflict);
+        }
+        let skip = policy == ConflictPolicy::Skip;
+        for (key, old) in diff.removed {
+            if let Ok(matches) = self.get(&key).map(|current| *current == old) {
+                if matches || !skip {
+                    let _ = self.delete(&key);
+                }
+            }
+        }
+        for (key, value) in diff.added {
+            let matches = self.get(&key).map(|current| *current == value);
+            if !skip || matches != Ok(false) {
+                self.insert(key, value);
+            }
+        }
+        for (key, old, new) in diff.changed {
+            let matches = self.get(&key).map(|current| *current == old || *current == new);
+            if !skip || matches == Ok(true) {
+                self.insert(key, new);
+            }
+        }
+        Ok(())
+    }
+
+    fn conflicts(&self, diff: &MapDiff<K, V>) -> bool {
+        let removed = diff.removed.iter().any(|(key, old)| self.get(key).is_ok_and(|current| current != old));
+        let added = diff.added.iter().any(|(key, value)| self.get(key).is_ok_and(|current| current != value));
+        let changed = diff.changed.iter().any(|(key, old, new)| match self.get(key) {
+            Ok(current) => current != old && current != new,
+            Err(_) => true,
+        });
+        removed || added || changed
+    }
+}
+
+impl<K: Ord + Clone, V: Clone + PartialEq, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // The changes from `self` to `other`, in one merge-join pass
+    pub fn diff<Q: BalancePolicy<K, V>>(&self, other: &BinaryTreeMap<K, V, Q>) -> MapDiff<K, V> {
+        let mut diff = MapDiff::default();
+        for entry in self.iter_joined(other) {
+            match entry {
+                ZipEntry::Left(key, value) => diff.removed.push((key.clone(), value.clone())),
+                ZipEntry::Right(key, value) => diff.added.push((key.clone(), value.clone())),
+                ZipEntry::Both(key, old, new) if old != new => diff.changed.push((key.clone(), old.clone(), new.clone())),
+                ZipEntry::Both(..) => {},
+            }
+        }
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lists_each_kind() {
+        let mut before = BinaryTreeMap::new();
+        let mut after = BinaryTreeMap::new();
+        for (key, value) in [(1, "a"), (2, "b"), (3, "c")] {
+            before.insert(key, value);
+        }
+        for (key, value) in [(2, "b"), (3, "C"), (4, "d")] {
+            after.insert(key, value);
+        }
+        let diff = before.diff(&after);
+        assert_eq!(diff.added, [(4, "d")]);
+        assert_eq!(diff.removed, [(1, "a")]);
+        assert_eq!(diff.changed, [(3, "c", "C")]);
+        assert!(before.diff(&before).is_empty());
+    }
+
+    #[test]
+    fn test_apply_round_trip() {
+        let mut replica = BinaryTreeMap::new();
+        let mut primary = BinaryTreeMap::with_policy(crate::Avl);
+        for key in 0..50 {
+            replica.insert(key, key);
+            primary.insert(key, key);
+        }
+        for key in 0..10 {
+            primary.delete(&key).unwrap();
+            primary.insert(key + 100, key);
+            primary.insert(key + 20, 0);
+        }
+        let diff = replica.diff(&primary);
+        assert_eq!(replica.apply(diff.clone(), ConflictPolicy::Fail), Ok(()));
+        assert!(replica.diff(&primary).is_empty());
+        // Reapplying finds everything already in place
+        assert_eq!(replica.apply(diff, ConflictPolicy::Fail), Ok(()));
+    }
+
+    #[test]
+    fn test_apply_conflict_policies() {
+        let mut base = BinaryTreeMap::new();
+        base.insert("a", 1);
+        base.insert("b", 2);
+        let mut target = BinaryTreeMap::new();
+        target.insert("a", 10);
+        target.insert("c", 3);
+        let diff = base.diff(&target);
+
+        // Someone else changed both `a` and `b` meanwhile
+        let mut drifted = BinaryTreeMap::new();
+        drifted.insert("a", 5);
+        drifted.insert("b", 6);
+        let mut failed = drifted.clone();
+        assert_eq!(failed.apply(diff.clone(), ConflictPolicy::Fail), Err(MapError::Conflict));
+        assert!(failed.diff(&drifted).is_empty());
+
+        let mut skipped = drifted.clone();
+        skipped.apply(diff.clone(), ConflictPolicy::Skip).unwrap();
+        assert_eq!(skipped.iter().collect::<Vec<_>>(), [(&"a", &5), (&"b", &6), (&"c", &3)]);
+
+        drifted.apply(diff, ConflictPolicy::Overwrite).unwrap();
+        assert!(drifted.diff(&target).is_empty());
+    }
+}
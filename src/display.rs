@@ -0,0 +1,123 @@
+use crate::{BalancePolicy, BinaryTreeMap};
+use core::fmt::{Debug, Write};
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use alloc::vec;
+use alloc::format;
+
+impl<K: Ord + Debug, V: Debug, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // One line per node as `key: value`, children indented below their
+    // parent with box-drawing connectors, left child first. A node with only
+    // one child shows the missing side as `·`.
+    //
+    //     5: "e"
+    //     ├── 3: "c"
+    //     └── ·
+    pub fn format_tree(&self) -> String {
+        if self.root.is_none() {
+            return "(empty)\n".to_string();
+        }
+        let mut out = String::new();
+        let mut pending = vec![(self.root.as_deref(), String::new(), "")];
+        while let Some((link, prefix, connector)) = pending.pop() {
+            out.push_str(&prefix);
+            out.push_str(connector);
+            let Some(node) = link else {
+                out.push_str("·\n");
+                continue;
+            };
+            writeln!(out, "{:?}: {:?}", node.key, node.value).unwrap();
+            if node.left.is_some() || node.right.is_some() {
+                let indent = match connector {
+                    "├── " => "│   ",
+                    "└── " => "    ",
+                    _ => "",
+                };
+                let child_prefix = format!("{prefix}{indent}");
+                pending.push((node.right(), child_prefix.clone(), "└── "));
+                pending.push((node.left(), child_prefix, "├── "));
+            }
+        }
+        out
+    }
+
+    #[cfg(feature = "std")]
+    pub fn print_tree(&self) {
+        print!("{}", self.format_tree());
+    }
+
+    // Mermaid flowchart with one box per node, numbered in preorder. Edges
+    // are labelled `L`/`R` so a lone child's side stays visible.
+    pub fn to_mermaid(&self) -> String {
+        let mut out = String::from("flowchart TD\n");
+        let mut next_id = 0;
+        // Each pending node carries its parent's id and the edge label
+        let mut pending = Vec::new();
+        if let Some(root) = self.root.as_deref() {
+            pending.push((root, None));
+        }
+        while let Some((node, parent)) = pending.pop() {
+            let id = next_id;
+            next_id += 1;
+            let label = format!("{:?}: {:?}", node.key, node.value).replace('"', "#quot;");
+            match parent {
+                None => writeln!(out, "    n{id}[\"{label}\"]").unwrap(),
+                Some((parent, side)) => writeln!(out, "    n{parent} -->|{side}| n{id}[\"{label}\"]").unwrap(),
+            }
+            if let Some(right) = node.right() {
+                pending.push((right, Some((id, "R"))));
+            }
+            if let Some(left) = node.left() {
+                pending.push((left, Some((id, "L"))));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BinaryTreeMap;
+
+    #[test]
+    fn test_format_tree() {
+        let mut map = BinaryTreeMap::new();
+        for key in [5, 3, 8, 1, 4, 9] {
+            map.insert(key, key * 10);
+        }
+        let expected = "\
+5: 50
+├── 3: 30
+│   ├── 1: 10
+│   └── 4: 40
+└── 8: 80
+    ├── ·
+    └── 9: 90
+";
+        assert_eq!(map.format_tree(), expected);
+    }
+
+    #[test]
+    fn test_to_mermaid() {
+        let mut map = BinaryTreeMap::new();
+        for (key, value) in [(2, "b"), (1, "a"), (3, "c"), (4, "d")] {
+            map.insert(key, value);
+        }
+        let expected = "\
+flowchart TD
+    n0[\"2: #quot;b#quot;\"]
+    n0 -->|L| n1[\"1: #quot;a#quot;\"]
+    n0 -->|R| n2[\"3: #quot;c#quot;\"]
+    n2 -->|R| n3[\"4: #quot;d#quot;\"]
+";
+        assert_eq!(map.to_mermaid(), expected);
+        assert_eq!(BinaryTreeMap::<u8, u8>::new().to_mermaid(), "flowchart TD\n");
+    }
+
+    #[test]
+    fn test_format_empty_tree() {
+        let map: BinaryTreeMap<i32, ()> = BinaryTreeMap::new();
+        assert_eq!(map.format_tree(), "(empty)\n");
+    }
+}
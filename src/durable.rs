@@ -0,0 +1,247 @@
+use crate::oplog::decode_op;
+use crate::{Avl, BinaryTreeMap, ByteCodec, MapError, Op, OpLog};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const SNAPSHOT: &str = "snapshot";
+const WAL: &str = "wal";
+
+// Log length that triggers a compaction unless set otherwise
+const DEFAULT_COMPACTION_THRESHOLD: usize = 10_000;
+
+// Map persisted in a directory as a snapshot plus a write-ahead log. Every
+// insert and delete is appended to the log and synced before the map
+// changes, so an acknowledged update survives a crash. Once the log holds
+// `compaction_threshold` operations the map is saved as the new snapshot
+// and the log is emptied. An update whose append fails leaves the map and
+// the log as they were. An update whose append succeeds is applied and
+// acknowledged even if the compaction after it fails; the failure is kept
+// for `compaction_failure` and the compaction retried on the next update.
+pub struct DurableTreeMap<K, V> {
+    map: BinaryTreeMap<K, V, Avl>,
+    dir: PathBuf,
+    wal: File,
+    // Bytes of the log known to hold whole, synced records
+    wal_len: u64,
+    // Set when a failed append could not be rolled back, after which the
+    // log's tail is unknown and every update is refused
+    poisoned: bool,
+    logged: usize,
+    compaction_threshold: usize,
+    compaction_failure: Option<io::Error>,
+}
+
+impl<K: Ord + ByteCodec, V: ByteCodec> DurableTreeMap<K, V> {
+    // Creates the directory if needed and recovers its snapshot and log
+    pub fn open<Q: AsRef<Path>>(dir: Q) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+        let mut map = match BinaryTreeMap::load_from_path(dir.join(SNAPSHOT)) {
+            Ok(map) => map,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => BinaryTreeMap::default(),
+            Err(e) => return Err(e),
+        };
+        let bytes = match std::fs::read(dir.join(WAL)) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        // A crash mid-append leaves a torn last record; everything before
+        // it was synced and is replayed, the rest is cut off
+        let mut input = bytes.as_slice();
+        let (mut valid, mut logged) = (0, 0);
+        while !input.is_empty() {
+            match decode_op::<K, V>(&mut input) {
+                Ok(Op::Insert(key, value)) => map.insert(key, value),
+                Ok(Op::Delete(key)) => {
+                    let _ = map.delete(&key);
+                },
+                Err(_) => break,
+            }
+            valid = bytes.len() - input.len();
+            logged += 1;
+        }
+        let wal = OpenOptions::new().create(true).append(true).open(dir.join(WAL))?;
+        wal.set_len(valid as u64)?;
+        Ok(DurableTreeMap {
+            map,
+            dir,
+            wal,
+            wal_len: valid as u64,
+            poisoned: false,
+            logged,
+            compaction_threshold: DEFAULT_COMPACTION_THRESHOLD,
+            compaction_failure: None,
+        })
+    }
+
+    pub fn set_compaction_threshold(&mut self, ops: usize) {
+        self.compaction_threshold = ops;
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn get(&self, key: &K) -> Result<&V, MapError> {
+        self.map.get(key)
+    }
+
+    pub fn map(&self) -> &BinaryTreeMap<K, V, Avl> {
+        &self.map
+    }
+
+    // Operations in the log since the last compaction
+    pub fn logged(&self) -> usize {
+        self.logged
+    }
+
+    // Why the last automatic compaction failed, until one succeeds
+    pub fn compaction_failure(&self) -> Option<&io::Error> {
+        self.compaction_failure.as_ref()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> io::Result<()> {
+        let mut record = OpLog::new();
+        record.record_insert(&key, &value);
+        self.append(record)?;
+        self.map.insert(key, value);
+        self.maybe_compact();
+        Ok(())
+    }
+
+    // Deleting a missing key logs nothing
+    pub fn delete(&mut self, key: &K) -> io::Result<Result<V, MapError>> {
+        if self.map.get(key).is_err() {
+            return Ok(Err(MapError::KeyNotFound));
+        }
+        let mut record = OpLog::<K, V>::new();
+        record.record_delete(key);
+        self.append(record)?;
+        let removed = self.map.delete(key);
+        self.maybe_compact();
+        Ok(removed)
+    }
+
+    // A record that fails to write or sync is cut off again, so a later
+    // append cannot land behind a torn one that recovery would stop at
+    fn append(&mut self, record: OpLog<K, V>) -> io::Result<()> {
+        if self.poisoned {
+            return Err(io::Error::other("log left in an unknown state by a failed append"));
+        }
+        let written = self.wal.write_all(record.as_bytes()).and_then(|()| self.wal.sync_data());
+        if let Err(e) = written {
+            self.poisoned = self.wal.set_len(self.wal_len).and_then(|()| self.wal.sync_data()).is_err();
+            return Err(e);
+        }
+        self.wal_len += record.as_bytes().len() as u64;
+        self.logged += 1;
+        Ok(())
+    }
+
+    // The update is already durable in the log, so a failure here is only
+    // recorded. The log stays over the threshold, so the next update tries
+    // again.
+    fn maybe_compact(&mut self) {
+        if self.logged >= self.compaction_threshold {
+            self.compaction_failure = self.compact().err();
+        }
+    }
+
+    // Saves the map as the snapshot and waits until it is durable, and only
+    // then empties the log. A crash between the two replays the old log
+    // over the new snapshot, which is harmless: each key ends up as the
+    // log's last operation on it left it.
+    pub fn compact(&mut self) -> io::Result<()> {
+        self.map.save_to_path(self.dir.join(SNAPSHOT))?;
+        self.wal.set_len(0)?;
+        self.wal_len = 0;
+        // An empty log has no tail to be unsure of
+        self.poisoned = false;
+        self.wal.sync_data()?;
+        self.logged = 0;
+        self.compaction_failure = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("btm-durable-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_recovers_from_snapshot_and_log() {
+        let dir = scratch("recover");
+        let mut map = DurableTreeMap::open(&dir).unwrap();
+        map.set_compaction_threshold(50);
+        for key in 0..120u32 {
+            map.insert(key, key * 2).unwrap();
+        }
+        assert_eq!(map.delete(&7).unwrap(), Ok(14));
+        assert_eq!(map.delete(&7).unwrap(), Err(MapError::KeyNotFound));
+        // Two compactions ran; the last 21 operations are only in the log
+        assert_eq!(map.logged(), 21);
+        drop(map);
+
+        let reopened = DurableTreeMap::<u32, u32>::open(&dir).unwrap();
+        assert_eq!(reopened.len(), 119);
+        assert_eq!(reopened.get(&119), Ok(&238));
+        assert_eq!(reopened.get(&7), Err(MapError::KeyNotFound));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_failed_compaction_keeps_acknowledged_updates() {
+        let dir = scratch("compaction");
+        let mut map = DurableTreeMap::open(&dir).unwrap();
+        map.set_compaction_threshold(2);
+        // A non-empty directory where the snapshot goes cannot be replaced
+        std::fs::create_dir_all(dir.join(SNAPSHOT).join("blocker")).unwrap();
+        map.insert(1u32, 10u32).unwrap();
+        map.insert(2, 20).unwrap();
+        assert!(map.compaction_failure().is_some());
+        assert_eq!(map.delete(&1).unwrap(), Ok(10));
+        assert_eq!(map.logged(), 3);
+
+        std::fs::remove_dir_all(dir.join(SNAPSHOT)).unwrap();
+        map.insert(3, 30).unwrap();
+        assert!(map.compaction_failure().is_none());
+        assert_eq!(map.logged(), 0);
+        drop(map);
+        let map = DurableTreeMap::<u32, u32>::open(&dir).unwrap();
+        assert!(map.map().iter().map(|(k, _)| *k).eq([2, 3]));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_torn_log_record_is_discarded() {
+        let dir = scratch("torn");
+        let mut map = DurableTreeMap::open(&dir).unwrap();
+        map.insert(1u32, "one".to_string()).unwrap();
+        map.insert(2, "two".to_string()).unwrap();
+        drop(map);
+        let wal = dir.join(WAL);
+        let len = std::fs::metadata(&wal).unwrap().len();
+        OpenOptions::new().write(true).open(&wal).unwrap().set_len(len - 2).unwrap();
+
+        let mut map = DurableTreeMap::<u32, String>::open(&dir).unwrap();
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.logged(), 1);
+        map.insert(3, "three".to_string()).unwrap();
+        drop(map);
+        let map = DurableTreeMap::<u32, String>::open(&dir).unwrap();
+        assert!(map.map().iter().map(|(k, _)| *k).eq([1, 3]));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
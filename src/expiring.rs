@@ -0,0 +1,113 @@
+use crate::{Avl, BinaryTreeMap, MapError};
+use alloc::vec::Vec;
+
+// Map whose entries each carry a deadline, in whatever clock the caller
+// uses. An entry expires once `now` reaches its deadline: reads ignore it
+// from then on, and `purge_expired` removes it. A second tree indexes the
+// entries by deadline, so purging costs O(log n) per expired entry and
+// nothing for the live ones.
+pub struct ExpiringTreeMap<K, V, T> {
+    entries: BinaryTreeMap<K, (V, T), Avl>,
+    deadlines: BinaryTreeMap<(T, K), (), Avl>,
+}
+
+impl<K: Ord + Clone, V, T: Ord + Clone> Default for ExpiringTreeMap<K, V, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone, V, T: Ord + Clone> ExpiringTreeMap<K, V, T> {
+    pub fn new() -> Self {
+        ExpiringTreeMap {
+            entries: BinaryTreeMap::with_policy(Avl),
+            deadlines: BinaryTreeMap::with_policy(Avl),
+        }
+    }
+
+    // Includes expired entries not yet purged
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // Inserting an existing key replaces both its value and its deadline
+    pub fn insert(&mut self, key: K, value: V, deadline: T) {
+        if let Ok((_, old)) = self.entries.get(&key) {
+            let _ = self.deadlines.delete(&(old.clone(), key.clone()));
+        }
+        self.deadlines.insert((deadline.clone(), key.clone()), ());
+        self.entries.insert(key, (value, deadline));
+    }
+
+    pub fn get(&self, key: &K, now: &T) -> Result<&V, MapError> {
+        match self.entries.get(key)? {
+            (value, deadline) if now < deadline => Ok(value),
+            _ => Err(MapError::KeyNotFound),
+        }
+    }
+
+    pub fn deadline(&self, key: &K) -> Result<&T, MapError> {
+        self.entries.get(key).map(|(_, deadline)| deadline)
+    }
+
+    // Removes the entry whether or not it has expired
+    pub fn delete(&mut self, key: &K) -> Result<V, MapError> {
+        let (value, deadline) = self.entries.delete(key)?;
+        let _ = self.deadlines.delete(&(deadline, key.clone()));
+        Ok(value)
+    }
+
+    // Removes every entry expired at `now`, earliest deadline first
+    pub fn purge_expired(&mut self, now: &T) -> Vec<(K, V)> {
+        let mut purged = Vec::new();
+        while let Some(((deadline, _), _)) = self.deadlines.first_key_value() {
+            if deadline > now {
+                break;
+            }
+            let ((_, key), ()) = self.deadlines.pop_first().expect("index is not empty");
+            let (value, _) = self.entries.delete(&key).expect("index matches entries");
+            purged.push((key, value));
+        }
+        purged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_ignores_expired_entries() {
+        let mut sessions = ExpiringTreeMap::new();
+        sessions.insert("alice", 1, 100u64);
+        sessions.insert("bob", 2, 50);
+        assert_eq!(sessions.get(&"bob", &49), Ok(&2));
+        assert_eq!(sessions.get(&"bob", &50), Err(MapError::KeyNotFound));
+        assert_eq!(sessions.get(&"alice", &50), Ok(&1));
+        // Refreshing moves the deadline
+        sessions.insert("bob", 3, 200);
+        assert_eq!(sessions.get(&"bob", &150), Ok(&3));
+        assert_eq!(sessions.deadline(&"bob"), Ok(&200));
+        assert_eq!(sessions.len(), 2);
+    }
+
+    #[test]
+    fn test_purge_removes_only_expired() {
+        let mut map = ExpiringTreeMap::new();
+        for key in 0..100u32 {
+            map.insert(key, key * 10, (key * 37 % 100) as u64);
+        }
+        assert_eq!(map.delete(&0), Ok(0));
+        let purged = map.purge_expired(&10);
+        let mut expired: Vec<_> = (1..100).filter(|k| k * 37 % 100 <= 10).map(|k| (k, k * 10)).collect();
+        expired.sort_by_key(|(k, _)| k * 37 % 100);
+        assert_eq!(purged, expired);
+        assert_eq!(map.len(), 99 - expired.len());
+        assert_eq!(map.purge_expired(&10), []);
+        assert!(map.entries.iter().all(|(_, (_, deadline))| *deadline > 10));
+    }
+}
@@ -0,0 +1,131 @@
+// C interface over an AVL-balanced map from `int64_t` keys to byte strings,
+// declared in `include/binary_tree_map.h`. Maps are opaque heap handles from
+// `btm_new`, released with `btm_free`. Values are copied in on insert; the
+// pointer `btm_get` hands out borrows the map's copy and stays valid until
+// the next mutation or free.
+//
+// Every function taking a handle requires it to come from `btm_new` and not
+// be freed yet; a null handle is ignored. Byte pointers must be valid for
+// reads of `len` bytes (they may be null when `len` is 0), and out-pointers
+// valid for writes.
+#![allow(clippy::missing_safety_doc)]
+
+use crate::{Avl, BinaryTreeMap};
+use std::ffi::c_void;
+use std::slice;
+
+pub struct BtmMap(BinaryTreeMap<i64, Vec<u8>, Avl>);
+
+pub type BtmVisit = extern "C" fn(key: i64, value: *const u8, len: usize, context: *mut c_void) -> bool;
+
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(data, len)
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn btm_new() -> *mut BtmMap {
+    Box::into_raw(Box::new(BtmMap(BinaryTreeMap::default())))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn btm_free(map: *mut BtmMap) {
+    if !map.is_null() {
+        drop(Box::from_raw(map));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn btm_len(map: *const BtmMap) -> usize {
+    map.as_ref().map_or(0, |map| map.0.len())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn btm_insert(map: *mut BtmMap, key: i64, value: *const u8, len: usize) {
+    if let Some(map) = map.as_mut() {
+        map.0.insert(key, bytes(value, len).to_vec());
+    }
+}
+
+// Returns false, leaving the out-pointers untouched, if `key` is absent
+#[no_mangle]
+pub unsafe extern "C" fn btm_get(map: *const BtmMap, key: i64, value: *mut *const u8, len: *mut usize) -> bool {
+    let Some(found) = map.as_ref().and_then(|map| map.0.get(&key).ok()) else {
+        return false;
+    };
+    *value = found.as_ptr();
+    *len = found.len();
+    true
+}
+
+// Returns false if `key` was absent
+#[no_mangle]
+pub unsafe extern "C" fn btm_delete(map: *mut BtmMap, key: i64) -> bool {
+    map.as_mut().is_some_and(|map| map.0.delete(&key).is_ok())
+}
+
+// Calls `visit` for every entry in ascending key order until it returns
+// false. The map must not be modified from inside the callback.
+#[no_mangle]
+pub unsafe extern "C" fn btm_for_each(map: *const BtmMap, visit: BtmVisit, context: *mut c_void) {
+    if let Some(map) = map.as_ref() {
+        for (key, value) in map.0.iter() {
+            if !visit(*key, value.as_ptr(), value.len(), context) {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ptr;
+
+    extern "C" fn collect_keys(key: i64, _value: *const u8, len: usize, context: *mut c_void) -> bool {
+        let keys = unsafe { &mut *(context as *mut Vec<(i64, usize)>) };
+        keys.push((key, len));
+        keys.len() < 3
+    }
+
+    #[test]
+    fn test_round_trip_through_c_interface() {
+        unsafe {
+            let map = btm_new();
+            for key in [5i64, -2, 9, 0] {
+                let value = key.to_string();
+                btm_insert(map, key, value.as_ptr(), value.len());
+            }
+            btm_insert(map, 7, ptr::null(), 0);
+            assert_eq!(btm_len(map), 5);
+
+            let (mut value, mut len) = (ptr::null(), 0);
+            assert!(btm_get(map, -2, &mut value, &mut len));
+            assert_eq!(bytes(value, len), b"-2");
+            assert!(btm_get(map, 7, &mut value, &mut len));
+            assert_eq!(len, 0);
+            assert!(!btm_get(map, 3, &mut value, &mut len));
+
+            assert!(btm_delete(map, 9));
+            assert!(!btm_delete(map, 9));
+
+            let mut keys: Vec<(i64, usize)> = Vec::new();
+            btm_for_each(map, collect_keys, &mut keys as *mut _ as *mut c_void);
+            assert_eq!(keys, vec![(-2, 2), (0, 1), (5, 1)]);
+            btm_free(map);
+        }
+    }
+
+    #[test]
+    fn test_null_handle_is_ignored() {
+        unsafe {
+            btm_insert(ptr::null_mut(), 1, ptr::null(), 0);
+            assert_eq!(btm_len(ptr::null()), 0);
+            assert!(!btm_delete(ptr::null_mut(), 1));
+            btm_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{BalancePolicy, BinaryTreeMap, ByteCodec};
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 4] = b"BTMF";
+// Bumped whenever the body stops being `to_bytes` output of this layout
+const VERSION: u8 = 1;
+
+// The file path with `.tmp` appended, written first and then renamed over
+// the target so a crash mid-save leaves the previous file intact
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".tmp");
+    PathBuf::from(name)
+}
+
+// Makes a rename in `path`'s directory durable. Only Unix can open a
+// directory to sync it; elsewhere the rename is left to the filesystem.
+fn sync_parent(path: &Path) -> std::io::Result<()> {
+    if cfg!(unix) {
+        let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty());
+        File::open(parent.unwrap_or(Path::new(".")))?.sync_all()?;
+    }
+    Ok(())
+}
+
+impl<K: Ord + ByteCodec, V: ByteCodec, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Writes a magic number and format version followed by `to_bytes`.
+    // The temporary file is synced before the rename and the directory
+    // after it, so once this returns the new file survives a crash.
+    pub fn save_to_path<Q: AsRef<Path>>(&self, path: Q) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let mut contents = MAGIC.to_vec();
+        contents.push(VERSION);
+        contents.extend_from_slice(&self.to_bytes());
+        let temp = temp_path(path);
+        let mut file = File::create(&temp)?;
+        file.write_all(&contents)?;
+        file.sync_all()?;
+        std::fs::rename(temp, path)?;
+        sync_parent(path)
+    }
+
+    // Files of another format or version, and corrupt bodies, fail with
+    // `InvalidData`; the decoding error is kept as its source
+    pub fn load_from_path<Q: AsRef<Path>>(path: Q) -> std::io::Result<Self> {
+        let contents = std::fs::read(path)?;
+        let body = contents.strip_prefix(MAGIC.as_slice()).ok_or_else(|| Error::new(ErrorKind::InvalidData, "not a saved map"))?;
+        match body.split_first() {
+            Some((&VERSION, body)) => Self::from_bytes(body).map_err(|e| Error::new(ErrorKind::InvalidData, e)),
+            Some((version, _)) => Err(Error::new(ErrorKind::InvalidData, format!("unsupported format version {version}"))),
+            None => Err(Error::new(ErrorKind::InvalidData, "missing format version")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Avl, BinaryTreeMap, MapError};
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = std::env::temp_dir().join(format!("btm-file-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("map.bin");
+
+        let mut map: BinaryTreeMap<u32, String, Avl> = BinaryTreeMap::default();
+        for key in 0..500 {
+            map.insert(key * 13 % 500, format!("v{key}"));
+        }
+        map.save_to_path(&path).unwrap();
+        let loaded = BinaryTreeMap::<u32, String, Avl>::load_from_path(&path).unwrap();
+        assert!(loaded.iter().eq(map.iter()));
+        assert!(loaded.check_invariants().is_ok());
+
+        // A newer version and a corrupt body are both rejected
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[4] = 2;
+        std::fs::write(&path, &bytes).unwrap();
+        let err = BinaryTreeMap::<u32, String, Avl>::load_from_path(&path).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        bytes[4] = 1;
+        bytes.truncate(bytes.len() - 1);
+        std::fs::write(&path, &bytes).unwrap();
+        let err = BinaryTreeMap::<u32, String, Avl>::load_from_path(&path).unwrap_err();
+        assert_eq!(err.into_inner().unwrap().downcast_ref(), Some(&MapError::InvalidEncoding));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,136 @@
+use crate::linked::{self, LinkedNode, LinkedTree, Slot, NIL};
+use crate::MapError;
+
+// Unbalanced BST holding at most `N` entries in an inline array, linked by
+// index. Nothing is ever allocated: the whole map lives wherever the value
+// does, and inserting a new key into a full map fails with
+// `CapacityExceeded` instead of growing.
+#[derive(Debug)]
+pub struct FixedBinaryTreeMap<K, V, const N: usize> {
+    tree: LinkedTree<[Slot<K, V, ()>; N]>,
+}
+
+impl<K: Ord, V, const N: usize> Default for FixedBinaryTreeMap<K, V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V, const N: usize> FixedBinaryTreeMap<K, V, N> {
+    const FITS_INDEX: () = assert!(N < NIL as usize, "capacity must fit in a u32 index");
+
+    pub fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::FITS_INDEX;
+        // Every slot starts on the free list, in order
+        let slots = core::array::from_fn(|index| {
+            let next = index as u32 + 1;
+            Slot::Vacant { next_free: if next as usize == N { NIL } else { next }, tag: () }
+        });
+        FixedBinaryTreeMap { tree: LinkedTree::new(slots, if N == 0 { NIL } else { 0 }) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.tree.len == N
+    }
+
+    // Updating an existing key always succeeds, even when the map is full
+    pub fn insert(&mut self, key: K, value: V) -> Result<(), MapError> {
+        let edge = self.tree.find_edge(&key);
+        let index = self.tree.target(edge);
+        if index != NIL {
+            self.tree.node_mut(index).value = value; // Update existing value
+            return Ok(());
+        }
+        let node = LinkedNode { key, value, parent: NIL, left: NIL, right: NIL, tag: () };
+        let index = self.tree.take_free(node).map_err(|_| MapError::CapacityExceeded)?;
+        self.tree.attach(edge, index);
+        Ok(())
+    }
+
+    pub fn get(&self, key: &K) -> Result<&V, MapError> {
+        match self.tree.target(self.tree.find_edge(key)) {
+            NIL => Err(MapError::KeyNotFound),
+            index => Ok(&self.tree.node(index).value),
+        }
+    }
+
+    pub fn delete(&mut self, key: &K) -> Result<V, MapError> {
+        let edge = self.tree.find_edge(key);
+        match self.tree.target(edge) {
+            NIL => Err(MapError::KeyNotFound),
+            index => Ok(self.tree.unlink(edge, index, ()).value),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V, N> {
+        Iter(self.tree.iter())
+    }
+}
+
+pub struct Iter<'a, K, V, const N: usize>(linked::Iter<'a, [Slot<K, V, ()>; N]>);
+
+impl<'a, K: Ord, V, const N: usize> Iterator for Iter<'a, K, V, N> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K: Ord, V, const N: usize> ExactSizeIterator for Iter<'_, K, V, N> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fills_to_capacity() {
+        let mut map: FixedBinaryTreeMap<u8, char, 3> = FixedBinaryTreeMap::new();
+        assert_eq!(map.insert(2, 'b'), Ok(()));
+        assert_eq!(map.insert(1, 'a'), Ok(()));
+        assert_eq!(map.insert(3, 'c'), Ok(()));
+        assert!(map.is_full());
+        assert_eq!(map.insert(4, 'd'), Err(MapError::CapacityExceeded));
+        assert_eq!(map.insert(3, 'C'), Ok(()));
+        assert_eq!(map.get(&3), Ok(&'C'));
+        assert_eq!(map.get(&4), Err(MapError::KeyNotFound));
+        assert_eq!(map.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_delete_frees_a_slot() {
+        let mut map: FixedBinaryTreeMap<u32, u32, 4> = FixedBinaryTreeMap::new();
+        for key in [20, 10, 30, 25] {
+            map.insert(key, key).unwrap();
+        }
+        assert_eq!(map.delete(&20), Ok(20));
+        assert_eq!(map.delete(&20), Err(MapError::KeyNotFound));
+        assert_eq!(map.insert(5, 5), Ok(()));
+        assert_eq!(map.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec![5, 10, 25, 30]);
+        assert_eq!(map.iter().len(), map.capacity());
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let mut map: FixedBinaryTreeMap<u8, u8, 0> = FixedBinaryTreeMap::default();
+        assert_eq!(map.insert(1, 1), Err(MapError::CapacityExceeded));
+        assert!(map.is_empty() && map.is_full());
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{BalancePolicy, BinaryTreeMap, MapError};
+use core::ops::{Bound, RangeBounds};
+use alloc::vec::Vec;
+
+// Read-only form of a map for build-once, query-many workloads. Keys and
+// values sit in two parallel sorted arrays, so a lookup is a binary search
+// over contiguous keys and iteration is a linear scan.
+#[derive(Debug, Clone)]
+pub struct FrozenBinaryTreeMap<K, V> {
+    keys: Vec<K>,
+    values: Vec<V>,
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    pub fn freeze(self) -> FrozenBinaryTreeMap<K, V> {
+        let (keys, values) = self.into_sorted_vec().into_iter().unzip();
+        FrozenBinaryTreeMap { keys, values }
+    }
+}
+
+impl<K: Ord, V> FrozenBinaryTreeMap<K, V> {
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn get(&self, key: &K) -> Result<&V, MapError> {
+        match self.keys.binary_search(key) {
+            Ok(index) => Ok(&self.values[index]),
+            Err(_) => Err(MapError::KeyNotFound),
+        }
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (&K, &V)> + ExactSizeIterator {
+        self.keys.iter().zip(self.values.iter())
+    }
+
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> impl DoubleEndedIterator<Item = (&K, &V)> + ExactSizeIterator {
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.keys.partition_point(|key| key < start),
+            Bound::Excluded(start) => self.keys.partition_point(|key| key <= start),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.keys.partition_point(|key| key <= end),
+            Bound::Excluded(end) => self.keys.partition_point(|key| key < end),
+            Bound::Unbounded => self.keys.len(),
+        };
+        let end = end.max(start);
+        self.keys[start..end].iter().zip(self.values[start..end].iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frozen() -> FrozenBinaryTreeMap<i32, String> {
+        let mut map = BinaryTreeMap::new();
+        for key in [50, 30, 70, 20, 40, 60, 80] {
+            map.insert(key, key.to_string());
+        }
+        map.freeze()
+    }
+
+    #[test]
+    fn test_frozen_get_and_iter() {
+        let map = frozen();
+
+        assert_eq!(map.len(), 7);
+        assert_eq!(map.get(&60), Ok(&"60".to_string()));
+        assert_eq!(map.get(&65), Err(MapError::KeyNotFound));
+        let keys: Vec<_> = map.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, vec![20, 30, 40, 50, 60, 70, 80]);
+    }
+
+    #[test]
+    fn test_frozen_range() {
+        let map = frozen();
+        let keys = |iter: &mut dyn Iterator<Item = (&i32, &String)>| iter.map(|(key, _)| *key).collect::<Vec<_>>();
+
+        assert_eq!(keys(&mut map.range(30..60)), vec![30, 40, 50]);
+        assert_eq!(keys(&mut map.range(35..=60)), vec![40, 50, 60]);
+        assert_eq!(keys(&mut map.range(..25)), vec![20]);
+        assert_eq!(keys(&mut map.range((Bound::Excluded(70), Bound::Unbounded))), vec![80]);
+        assert_eq!(keys(&mut map.range((Bound::Included(60), Bound::Excluded(40)))), Vec::<i32>::new());
+        assert_eq!(map.range(..).next_back(), Some((&80, &"80".to_string())));
+    }
+}
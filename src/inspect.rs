@@ -0,0 +1,114 @@
+use crate::node::{Link, Node};
+use crate::{BalancePolicy, BinaryTreeMap};
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Number of levels; 0 for an empty map
+    pub fn height(&self) -> usize {
+        Self::fold_postorder(&self.root, 0, |_, left, right| 1 + left.max(right))
+    }
+
+    // Number of nodes on the shortest path from the root to a leaf
+    pub fn min_depth(&self) -> usize {
+        let mut queue = VecDeque::new();
+        if let Some(root) = self.root.as_deref() {
+            queue.push_back((root, 1));
+        }
+        while let Some((node, depth)) = queue.pop_front() {
+            if node.left.is_none() && node.right.is_none() {
+                return depth;
+            }
+            for child in [node.left(), node.right()].into_iter().flatten() {
+                queue.push_back((child, depth + 1));
+            }
+        }
+        0
+    }
+
+    // True when the heights of every node's two subtrees differ by at most one
+    pub fn is_balanced(&self) -> bool {
+        let height = Self::fold_postorder(&self.root, Some(0usize), |_, left, right| {
+            let (left, right) = (left?, right?);
+            (left.abs_diff(right) <= 1).then_some(1 + left.max(right))
+        });
+        height.is_some()
+    }
+
+    // Combines per-subtree results bottom-up with an explicit stack, so
+    // degenerate trees can be inspected without exhausting the call stack
+    pub(crate) fn fold_postorder<R: Clone>(
+        root: &Link<K, V, P::Meta>,
+        empty: R,
+        mut combine: impl FnMut(&Node<K, V, P::Meta>, R, R) -> R,
+    ) -> R {
+        let mut pending = Vec::new();
+        let mut results = Vec::new();
+        if let Some(node) = root.as_deref() {
+            pending.push((node, false));
+        } else {
+            return empty;
+        }
+        while let Some((node, children_done)) = pending.pop() {
+            if children_done {
+                let right = if node.right.is_some() { results.pop().unwrap() } else { empty.clone() };
+                let left = if node.left.is_some() { results.pop().unwrap() } else { empty.clone() };
+                results.push(combine(node, left, right));
+            } else {
+                pending.push((node, true));
+                if let Some(right) = node.right() {
+                    pending.push((right, false));
+                }
+                if let Some(left) = node.left() {
+                    pending.push((left, false));
+                }
+            }
+        }
+        results.pop().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BinaryTreeMap;
+
+    #[test]
+    fn test_empty_map_shape() {
+        let map: BinaryTreeMap<i32, ()> = BinaryTreeMap::new();
+        assert_eq!(map.height(), 0);
+        assert_eq!(map.min_depth(), 0);
+        assert!(map.is_balanced());
+    }
+
+    #[test]
+    fn test_degenerate_shape_is_detected() {
+        let mut map = BinaryTreeMap::new();
+        for key in 0..2_000 {
+            map.insert(key, ());
+            if key < 2 {
+                assert!(map.is_balanced());
+            }
+        }
+
+        assert_eq!(map.height(), 2_000);
+        assert_eq!(map.min_depth(), 2_000);
+        assert!(!map.is_balanced());
+    }
+
+    #[test]
+    fn test_shape_of_balanced_insert_order() {
+        let mut map = BinaryTreeMap::new();
+        for key in [50, 30, 70, 20, 40, 60] {
+            map.insert(key, ());
+        }
+
+        assert_eq!(map.height(), 3);
+        assert_eq!(map.min_depth(), 3);
+        assert!(map.is_balanced());
+
+        map.insert(10, ());
+        map.insert(5, ());
+        assert_eq!(map.height(), 5);
+        assert!(!map.is_balanced());
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{Avl, BalancePolicy, BinaryTreeMap, MapError};
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// Shared pool of strings, each stored once behind an `Rc<str>`. Cloning an
+// `Interner` gives another handle to the same pool, so maps built with it
+// share one copy of every key they have in common. Single-threaded, like
+// the `Rc`s it hands out.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    // Sorted, for binary search by `&str`; vocabularies are small
+    strings: Rc<RefCell<Vec<Rc<str>>>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // The pooled copy of `s`, added if it is new
+    pub fn intern(&self, s: &str) -> Rc<str> {
+        let mut strings = self.strings.borrow_mut();
+        match strings.binary_search_by(|pooled| (**pooled).cmp(s)) {
+            Ok(index) => strings[index].clone(),
+            Err(index) => {
+                let pooled: Rc<str> = Rc::from(s);
+                strings.insert(index, pooled.clone());
+                pooled
+            },
+        }
+    }
+
+    // The pooled copy of `s` if there is one, without adding it
+    pub fn lookup(&self, s: &str) -> Option<Rc<str>> {
+        let strings = self.strings.borrow();
+        let index = strings.binary_search_by(|pooled| (**pooled).cmp(s)).ok()?;
+        Some(strings[index].clone())
+    }
+
+    // Drops the strings nothing outside the pool refers to any more
+    pub fn purge_unused(&self) {
+        self.strings.borrow_mut().retain(|pooled| Rc::strong_count(pooled) > 1);
+    }
+}
+
+// Map keyed by strings drawn from an `Interner`. Lookups by `&str` find
+// the pooled key first, so they never allocate.
+pub struct InternedTreeMap<V, P: BalancePolicy<Rc<str>, V> = Avl> {
+    map: BinaryTreeMap<Rc<str>, V, P>,
+    interner: Interner,
+}
+
+impl<V> Default for InternedTreeMap<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> InternedTreeMap<V> {
+    pub fn new() -> Self {
+        Self::with_interner(&Interner::new())
+    }
+
+    pub fn with_interner(interner: &Interner) -> Self {
+        InternedTreeMap { map: BinaryTreeMap::default(), interner: interner.clone() }
+    }
+}
+
+impl<V, P: BalancePolicy<Rc<str>, V>> InternedTreeMap<V, P> {
+    pub fn interner(&self) -> &Interner {
+        &self.interner
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn insert(&mut self, key: &str, value: V) {
+        let key = self.interner.intern(key);
+        self.map.insert(key, value);
+    }
+
+    pub fn get(&self, key: &str) -> Result<&V, MapError> {
+        let key = self.interner.lookup(key).ok_or(MapError::KeyNotFound)?;
+        self.map.get(&key)
+    }
+
+    // The key stays pooled until `Interner::purge_unused`
+    pub fn delete(&mut self, key: &str) -> Result<V, MapError> {
+        let key = self.interner.lookup(key).ok_or(MapError::KeyNotFound)?;
+        self.map.delete(&key)
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&str, &V)> {
+        self.map.iter().map(|(key, value)| (&**key, value))
+    }
+
+    pub fn map(&self) -> &BinaryTreeMap<Rc<str>, V, P> {
+        &self.map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maps_share_one_copy_of_each_key() {
+        let words = Interner::new();
+        let mut counts = InternedTreeMap::with_interner(&words);
+        let mut firsts = InternedTreeMap::with_interner(&words);
+        for (line, word) in ["the", "cat", "the", "hat", "cat"].into_iter().enumerate() {
+            if counts.get(word).is_err() {
+                firsts.insert(word, line);
+            }
+            let count = counts.get(word).copied().unwrap_or(0);
+            counts.insert(word, count + 1);
+        }
+        assert_eq!(words.len(), 3);
+        assert!(counts.iter().eq([("cat", &2), ("hat", &1), ("the", &2)]));
+        assert_eq!(firsts.get("hat"), Ok(&3));
+        let (a, _) = counts.map().iter().next().unwrap();
+        let (b, _) = firsts.map().iter().next().unwrap();
+        assert!(Rc::ptr_eq(a, b));
+        assert_eq!(counts.get("dog"), Err(MapError::KeyNotFound));
+    }
+
+    #[test]
+    fn test_purge_drops_unreferenced_strings() {
+        let mut map = InternedTreeMap::new();
+        map.insert("kept", 1);
+        map.insert("gone", 2);
+        assert_eq!(map.delete("gone"), Ok(2));
+        assert_eq!(map.interner().len(), 2);
+        map.interner().purge_unused();
+        assert_eq!(map.interner().len(), 1);
+        assert_eq!(map.interner().lookup("gone"), None);
+        assert_eq!(map.get("kept"), Ok(&1));
+    }
+}
@@ -0,0 +1,190 @@
+use crate::node::{Link, Node};
+use crate::{BalancePolicy, BinaryTreeMap, ValueIndependent, WeightBalanced};
+use core::ops::Bound;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+// Half-open interval `[start, end)`, ordered by start and then end so
+// intervals sharing a start can coexist as distinct keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval<T> {
+    pub start: T,
+    pub end: T,
+}
+
+impl<T: Ord> Interval<T> {
+    pub fn new(start: T, end: T) -> Self {
+        assert!(start <= end, "interval start must not be after its end");
+        Interval { start, end }
+    }
+
+    pub fn contains(&self, point: &T) -> bool {
+        self.start <= *point && *point < self.end
+    }
+
+    pub fn overlaps(&self, other: &Interval<T>) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+}
+
+// Weight-balanced like `WeightBalanced`, with each node also recording the
+// largest `end` in its subtree so queries can skip subtrees that finish
+// before the query starts
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MaxEnd;
+
+pub type IntervalTreeMap<T, V> = BinaryTreeMap<Interval<T>, V, MaxEnd>;
+
+fn max_end<T, V>(link: &Link<Interval<T>, V, Option<T>>) -> Option<&T> {
+    link.as_ref().and_then(|n| n.meta.as_ref())
+}
+
+impl<T: Ord + Clone, V> ValueIndependent<Interval<T>, V> for MaxEnd {}
+
+impl<T: Ord + Clone, V> BalancePolicy<Interval<T>, V> for MaxEnd {
+    type Meta = Option<T>;
+
+    fn update(node: &mut Node<Interval<T>, V, Option<T>>) {
+        let end = [Some(&node.key.end), max_end(&node.left), max_end(&node.right)].into_iter().flatten().max();
+        node.meta = end.cloned();
+    }
+
+    fn after_insert(node: Box<Node<Interval<T>, V, Option<T>>>) -> Box<Node<Interval<T>, V, Option<T>>> {
+        WeightBalanced::balance::<_, _, Self>(node)
+    }
+
+    fn after_delete(node: Box<Node<Interval<T>, V, Option<T>>>) -> Box<Node<Interval<T>, V, Option<T>>> {
+        WeightBalanced::balance::<_, _, Self>(node)
+    }
+
+    fn check(node: &Node<Interval<T>, V, Option<T>>) -> Result<(), &'static str> {
+        let end = [Some(&node.key.end), max_end(&node.left), max_end(&node.right)].into_iter().flatten().max();
+        if node.meta.as_ref() != end {
+            return Err("stored max end does not match the subtree");
+        }
+        WeightBalanced::check_weights(node)
+    }
+
+    fn join_direction(
+        left: Option<&Node<Interval<T>, V, Option<T>>>,
+        right: Option<&Node<Interval<T>, V, Option<T>>>,
+    ) -> core::cmp::Ordering {
+        WeightBalanced::join_by_weight(left, right)
+    }
+}
+
+impl<T: Ord + Clone, V> BinaryTreeMap<Interval<T>, V, MaxEnd> {
+    // Intervals containing `point`, in key order
+    pub fn query_point<'a>(&'a self, point: &'a T) -> Overlapping<'a, T, V> {
+        Overlapping::new(&self.root, point, Bound::Included(point))
+    }
+
+    // Intervals sharing at least one point with `range`, in key order
+    pub fn query_overlapping<'a>(&'a self, range: &'a Interval<T>) -> Overlapping<'a, T, V> {
+        Overlapping::new(&self.root, &range.start, Bound::Excluded(&range.end))
+    }
+}
+
+// In-order walk over the intervals that end after `after` and start before
+// `before`. Subtrees whose max end is too small are never entered, and the
+// walk stops at the first start past `before`, so a query costs
+// O(log n + matches) on a balanced tree.
+pub struct Overlapping<'a, T, V> {
+    stack: Vec<&'a Node<Interval<T>, V, Option<T>>>,
+    after: &'a T,
+    before: Bound<&'a T>,
+}
+
+impl<'a, T: Ord, V> Overlapping<'a, T, V> {
+    fn new(root: &'a Link<Interval<T>, V, Option<T>>, after: &'a T, before: Bound<&'a T>) -> Self {
+        let mut iter = Overlapping { stack: Vec::new(), after, before };
+        iter.descend(root);
+        iter
+    }
+
+    // Ends are exclusive: an interval reaches past `after` only if its end
+    // is strictly greater
+    fn descend(&mut self, mut link: &'a Link<Interval<T>, V, Option<T>>) {
+        while let Some(node) = link {
+            if node.meta.as_ref().is_none_or(|end| end <= self.after) {
+                return;
+            }
+            self.stack.push(node);
+            link = &node.left;
+        }
+    }
+
+    fn starts_in_time(&self, start: &T) -> bool {
+        match self.before {
+            Bound::Included(before) => start <= before,
+            Bound::Excluded(before) => start < before,
+            Bound::Unbounded => true,
+        }
+    }
+}
+
+impl<'a, T: Ord, V> Iterator for Overlapping<'a, T, V> {
+    type Item = (&'a Interval<T>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            // Everything left on the stack or to the right starts later
+            if !self.starts_in_time(&node.key.start) {
+                self.stack.clear();
+                return None;
+            }
+            self.descend(&node.right);
+            if node.key.end > *self.after {
+                return Some((&node.key, &node.value));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meetings() -> IntervalTreeMap<u32, &'static str> {
+        let mut map = IntervalTreeMap::default();
+        for (start, end, name) in [(9, 10, "standup"), (10, 12, "review"), (11, 13, "lunch"), (14, 15, "1:1"), (8, 17, "on call")] {
+            map.insert(Interval::new(start, end), name);
+        }
+        map
+    }
+
+    fn names<'a>(iter: impl Iterator<Item = (&'a Interval<u32>, &'a &'static str)>) -> Vec<&'static str> {
+        iter.map(|(_, name)| *name).collect()
+    }
+
+    #[test]
+    fn test_point_and_range_queries() {
+        let map = meetings();
+        assert_eq!(names(map.query_point(&11)), vec!["on call", "review", "lunch"]);
+        assert_eq!(names(map.query_point(&10)), vec!["on call", "review"]);
+        assert_eq!(names(map.query_point(&17)), Vec::<&str>::new());
+        assert_eq!(names(map.query_overlapping(&Interval::new(12, 14))), vec!["on call", "lunch"]);
+        assert_eq!(names(map.query_overlapping(&Interval::new(0, 8))), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_matches_brute_force_after_churn() {
+        let mut map = IntervalTreeMap::default();
+        let intervals: Vec<Interval<u32>> = (0..300).map(|i| Interval::new(i * 7 % 200, i * 7 % 200 + i % 13)).collect();
+        for (i, interval) in intervals.iter().enumerate() {
+            map.insert(*interval, i);
+        }
+        for interval in intervals.iter().step_by(4) {
+            let _ = map.delete(interval);
+        }
+        assert_eq!(map.check_invariants(), Ok(()));
+        for point in 0..215 {
+            let expected: Vec<_> = map.iter().filter(|(key, _)| key.contains(&point)).collect();
+            assert_eq!(map.query_point(&point).collect::<Vec<_>>(), expected);
+        }
+        let window = Interval::new(50, 60);
+        let expected: Vec<_> = map.iter().filter(|(key, _)| key.overlaps(&window)).collect();
+        assert_eq!(map.query_overlapping(&window).collect::<Vec<_>>(), expected);
+    }
+}
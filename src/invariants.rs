@@ -0,0 +1,114 @@
+use crate::node::Node;
+use crate::{BalancePolicy, BinaryTreeMap};
+use alloc::vec::Vec;
+
+// What `check_invariants` found wrong, and at which node. Nodes are named by
+// their in-order position, since a broken tree may not support lookups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvariantViolation {
+    pub position: usize,
+    pub kind: ViolationKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViolationKind {
+    // The key is not greater than the key before it
+    OutOfOrder,
+    // The stored subtree size disagrees with the children
+    WrongSize { stored: usize, expected: usize },
+    // The balance policy rejected the node's metadata or shape
+    Policy(&'static str),
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Walks the whole tree in order, checking that keys strictly ascend and
+    // that every node's size and policy metadata agree with its children.
+    // Each check is local, so together they cover the whole tree. Returns the
+    // first violation found.
+    pub fn check_invariants(&self) -> Result<(), InvariantViolation> {
+        let mut stack: Vec<&Node<K, V, P::Meta>> = Vec::new();
+        let mut current = self.root.as_deref();
+        let mut previous: Option<&K> = None;
+        let mut position = 0;
+        loop {
+            while let Some(node) = current {
+                stack.push(node);
+                current = node.left();
+            }
+            let Some(node) = stack.pop() else {
+                return Ok(());
+            };
+            let violation = |kind| InvariantViolation { position, kind };
+            if previous.is_some_and(|previous| previous >= &node.key) {
+                return Err(violation(ViolationKind::OutOfOrder));
+            }
+            let expected = 1 + Node::size_of(&node.left) + Node::size_of(&node.right);
+            if node.size != expected {
+                return Err(violation(ViolationKind::WrongSize { stored: node.size, expected }));
+            }
+            P::check(node).map_err(|reason| violation(ViolationKind::Policy(reason)))?;
+            previous = Some(&node.key);
+            position += 1;
+            current = node.right();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Avl, WeightBalancedTreeMap};
+
+    #[test]
+    fn test_valid_trees_pass() {
+        let mut plain = BinaryTreeMap::new();
+        let mut avl = BinaryTreeMap::with_policy(Avl);
+        let mut weighted = WeightBalancedTreeMap::default();
+        for key in (0..500).map(|i| i * 7 % 500) {
+            plain.insert(key, ());
+            avl.insert(key, ());
+            weighted.insert(key, ());
+        }
+        for key in (0..500).step_by(3) {
+            plain.delete(&key).unwrap();
+            avl.delete(&key).unwrap();
+            weighted.delete(&key).unwrap();
+        }
+        assert_eq!(plain.check_invariants(), Ok(()));
+        assert_eq!(avl.check_invariants(), Ok(()));
+        assert_eq!(weighted.check_invariants(), Ok(()));
+        assert_eq!(BinaryTreeMap::<u8, ()>::new().check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_reports_the_broken_node() {
+        let mut map = BinaryTreeMap::new();
+        for key in [4, 2, 6, 1, 3] {
+            map.insert(key, ());
+        }
+        // Key 3 becomes 5, leaving the root 4 (position 3) out of order
+        map.root.as_mut().unwrap().left.as_mut().unwrap().right.as_mut().unwrap().key = 5;
+        assert_eq!(
+            map.check_invariants(),
+            Err(InvariantViolation { position: 3, kind: ViolationKind::OutOfOrder })
+        );
+        map.root.as_mut().unwrap().left.as_mut().unwrap().right.as_mut().unwrap().key = 3;
+        map.root.as_mut().unwrap().size = 9;
+        assert_eq!(
+            map.check_invariants(),
+            Err(InvariantViolation { position: 3, kind: ViolationKind::WrongSize { stored: 9, expected: 5 } })
+        );
+    }
+
+    #[test]
+    fn test_policy_violations() {
+        let mut avl = BinaryTreeMap::with_policy(Avl);
+        for key in 0..3 {
+            avl.insert(key, ());
+        }
+        avl.root.as_mut().unwrap().meta = 7;
+        let violation = avl.check_invariants().unwrap_err();
+        assert_eq!(violation.position, 1);
+        assert!(matches!(violation.kind, ViolationKind::Policy(_)));
+    }
+}
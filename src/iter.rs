@@ -0,0 +1,290 @@
+use crate::node::{Link, Node};
+use crate::{BalancePolicy, BinaryTreeMap};
+use core::ops::{Bound, RangeBounds};
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+// In-order iterator over `(&K, &V)`. Only the unvisited ancestors are kept
+// on the stack, so it needs O(height) space.
+pub struct Iter<'a, K, V, M = ()> {
+    stack: Vec<&'a Node<K, V, M>>,
+    remaining: usize,
+}
+
+impl<'a, K, V, M> Iter<'a, K, V, M> {
+    fn push_left(&mut self, mut link: &'a Link<K, V, M>) {
+        while let Some(node) = link {
+            self.stack.push(node);
+            link = &node.left;
+        }
+    }
+}
+
+impl<'a, K, V, M> Iterator for Iter<'a, K, V, M> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left(&node.right);
+        self.remaining -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V, M> ExactSizeIterator for Iter<'_, K, V, M> {}
+
+// In-order iterator over the entries whose keys fall in a range. Starts
+// with the path to the first key in range and stops at the first key past
+// the end, so it costs O(height + entries visited).
+pub struct Range<'a, K, V, R, M = ()> {
+    stack: Vec<&'a Node<K, V, M>>,
+    range: R,
+}
+
+impl<'a, K: Ord, V, R: RangeBounds<K>, M> Range<'a, K, V, R, M> {
+    fn seek(&mut self, mut link: &'a Link<K, V, M>) {
+        while let Some(node) = link {
+            let above_start = match self.range.start_bound() {
+                Bound::Included(start) => node.key >= *start,
+                Bound::Excluded(start) => node.key > *start,
+                Bound::Unbounded => true,
+            };
+            link = if above_start {
+                self.stack.push(node);
+                &node.left
+            } else {
+                &node.right
+            };
+        }
+    }
+}
+
+impl<'a, K: Ord, V, R: RangeBounds<K>, M> Iterator for Range<'a, K, V, R, M> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        if !self.range.contains(&node.key) {
+            self.stack.clear();
+            return None;
+        }
+        self.seek(&node.right);
+        Some((&node.key, &node.value))
+    }
+}
+
+// In-order iterator that also yields each node's depth, the root being at
+// depth 0
+pub struct WithDepth<'a, K, V, M = ()> {
+    stack: Vec<(&'a Node<K, V, M>, usize)>,
+    remaining: usize,
+}
+
+impl<'a, K, V, M> WithDepth<'a, K, V, M> {
+    fn push_left(&mut self, mut link: &'a Link<K, V, M>, mut depth: usize) {
+        while let Some(node) = link {
+            self.stack.push((node, depth));
+            link = &node.left;
+            depth += 1;
+        }
+    }
+}
+
+impl<'a, K, V, M> Iterator for WithDepth<'a, K, V, M> {
+    type Item = (&'a K, &'a V, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, depth) = self.stack.pop()?;
+        self.push_left(&node.right, depth + 1);
+        self.remaining -= 1;
+        Some((&node.key, &node.value, depth))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V, M> ExactSizeIterator for WithDepth<'_, K, V, M> {}
+
+// Breadth-first iterator: the root, then each level left to right. The
+// queue holds at most one level plus its children, O(width) space.
+pub struct LevelOrder<'a, K, V, M = ()> {
+    queue: VecDeque<&'a Node<K, V, M>>,
+    remaining: usize,
+}
+
+impl<'a, K, V, M> Iterator for LevelOrder<'a, K, V, M> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.queue.pop_front()?;
+        self.queue.extend(node.left());
+        self.queue.extend(node.right());
+        self.remaining -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V, M> ExactSizeIterator for LevelOrder<'_, K, V, M> {}
+
+// Breadth-first iterator yielding one level at a time, left to right
+pub struct Levels<'a, K, V, M = ()> {
+    level: Vec<&'a Node<K, V, M>>,
+}
+
+impl<'a, K, V, M> Iterator for Levels<'a, K, V, M> {
+    type Item = Vec<(&'a K, &'a V)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.level.is_empty() {
+            return None;
+        }
+        let next_level = self.level.iter().flat_map(|node| node.left().into_iter().chain(node.right())).collect();
+        let level = core::mem::replace(&mut self.level, next_level);
+        Some(level.into_iter().map(|node| (&node.key, &node.value)).collect())
+    }
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    pub fn iter(&self) -> Iter<'_, K, V, P::Meta> {
+        let mut iter = Iter { stack: Vec::new(), remaining: self.len() };
+        iter.push_left(&self.root);
+        iter
+    }
+
+    // Entries `offset..offset + limit` in key order. Subtree sizes lead
+    // straight to the first one in O(log n), so earlier pages are never
+    // iterated.
+    pub fn page(&self, offset: usize, limit: usize) -> core::iter::Take<Iter<'_, K, V, P::Meta>> {
+        let mut iter = Iter { stack: Vec::new(), remaining: self.len().saturating_sub(offset) };
+        let mut index = offset;
+        let mut link = &self.root;
+        while let Some(node) = link {
+            let left_size = Node::size_of(&node.left);
+            if index < left_size {
+                // The entry is in the left subtree, to be followed by this node
+                iter.stack.push(node);
+                link = &node.left;
+            } else if index == left_size {
+                iter.stack.push(node);
+                break;
+            } else {
+                index -= left_size + 1;
+                link = &node.right;
+            }
+        }
+        iter.take(limit)
+    }
+
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Range<'_, K, V, R, P::Meta> {
+        let mut iter = Range { stack: Vec::new(), range };
+        iter.seek(&self.root);
+        iter
+    }
+
+    pub fn iter_with_depth(&self) -> WithDepth<'_, K, V, P::Meta> {
+        let mut iter = WithDepth { stack: Vec::new(), remaining: self.len() };
+        iter.push_left(&self.root, 0);
+        iter
+    }
+
+    pub fn iter_level_order(&self) -> LevelOrder<'_, K, V, P::Meta> {
+        LevelOrder { queue: self.root.as_deref().into_iter().collect(), remaining: self.len() }
+    }
+
+    // Same order as `iter_level_order`, grouped into one `Vec` per level
+    pub fn iter_levels(&self) -> Levels<'_, K, V, P::Meta> {
+        Levels { level: self.root.as_deref().into_iter().collect() }
+    }
+}
+
+impl<'a, K: Ord, V, P: BalancePolicy<K, V>> IntoIterator for &'a BinaryTreeMap<K, V, P> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, P::Meta>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BinaryTreeMap;
+
+    #[test]
+    fn test_iter_in_key_order() {
+        let mut map = BinaryTreeMap::new();
+        for key in [5, 3, 8, 1, 4, 9] {
+            map.insert(key, key * 2);
+        }
+        let iter = map.iter();
+        assert_eq!(iter.len(), 6);
+        assert_eq!(iter.map(|(k, _)| *k).collect::<Vec<_>>(), vec![1, 3, 4, 5, 8, 9]);
+        assert_eq!((&map).into_iter().last(), Some((&9, &18)));
+    }
+
+    #[test]
+    fn test_range_bounds() {
+        let map: BinaryTreeMap<u32, ()> = BinaryTreeMap::from_sorted_iter((0..100).map(|key| (key * 2, ())));
+        fn keys<'a>(iter: impl Iterator<Item = (&'a u32, &'a ())>) -> Vec<u32> {
+            iter.map(|(k, _)| *k).collect()
+        }
+        assert_eq!(keys(map.range(9..15)), vec![10, 12, 14]);
+        assert_eq!(keys(map.range(10..=14)), vec![10, 12, 14]);
+        assert_eq!(keys(map.range(195..)), vec![196, 198]);
+        assert_eq!(keys(map.range(..3)), vec![0, 2]);
+        assert_eq!(map.range(..).count(), 100);
+        assert_eq!(map.range(50..50).count(), 0);
+    }
+
+    #[test]
+    fn test_page_skips_to_offset() {
+        let mut map = BinaryTreeMap::new();
+        for key in (0..100).map(|i| i * 37 % 100) {
+            map.insert(key, ());
+        }
+        let keys = |offset, limit| map.page(offset, limit).map(|(k, _)| *k).collect::<Vec<_>>();
+        assert_eq!(keys(0, 3), [0, 1, 2]);
+        assert_eq!(keys(40, 5), [40, 41, 42, 43, 44]);
+        assert_eq!(keys(98, 10), [98, 99]);
+        assert!(keys(100, 10).is_empty());
+        assert_eq!(map.page(95, 10).len(), 5);
+    }
+
+    #[test]
+    fn test_iter_with_depth() {
+        let mut map = BinaryTreeMap::new();
+        for key in [4, 2, 6, 3, 5, 7, 8] {
+            map.insert(key, ());
+        }
+        let depths: Vec<(i32, usize)> = map.iter_with_depth().map(|(k, _, depth)| (*k, depth)).collect();
+        assert_eq!(depths, vec![(2, 1), (3, 2), (4, 0), (5, 2), (6, 1), (7, 2), (8, 3)]);
+        assert_eq!(map.iter_with_depth().len(), 7);
+    }
+
+    #[test]
+    fn test_level_order() {
+        //       4
+        //     2   6
+        //    1   5  7
+        let mut map = BinaryTreeMap::new();
+        for key in [4, 2, 6, 1, 5, 7] {
+            map.insert(key, ());
+        }
+        let iter = map.iter_level_order();
+        assert_eq!(iter.len(), 6);
+        assert_eq!(iter.map(|(k, _)| *k).collect::<Vec<_>>(), vec![4, 2, 6, 1, 5, 7]);
+        let levels: Vec<Vec<i32>> = map.iter_levels().map(|level| level.into_iter().map(|(k, _)| *k).collect()).collect();
+        assert_eq!(levels, vec![vec![4], vec![2, 6], vec![1, 5, 7]]);
+        assert_eq!(BinaryTreeMap::<i32, ()>::new().iter_levels().count(), 0);
+    }
+}
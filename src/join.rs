@@ -0,0 +1,196 @@
+use crate::node::{Link, Node};
+use crate::observer::Observer;
+use crate::{BalancePolicy, BinaryTreeMap};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+// Split and join are the two primitives the bulk set operations are built
+// from. Both work along a single root-to-leaf path, keeping the detached
+// nodes in a `Vec` rather than on the call stack, so degenerate trees of
+// any depth are fine.
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Divides the map into the entries below `key`, the value stored at
+    // `key` (if any), and the entries above it, in O(log n) for balanced maps
+    pub fn split(mut self, key: &K) -> (Self, Option<V>, Self) {
+        let (below, found, above) = Self::split_link(self.root.take(), key);
+        (
+            BinaryTreeMap::from_root(below),
+            found.map(|node| node.value),
+            BinaryTreeMap::from_root(above),
+        )
+    }
+
+    // Inverse of `split`. Every key in `left` must be less than `key` and
+    // every key in `right` greater; this is only checked in debug builds.
+    pub fn join(mut left: Self, key: K, value: V, mut right: Self) -> Self {
+        debug_assert!(Self::max_key(&left.root).is_none_or(|max| *max < key));
+        debug_assert!(Self::min_key(&right.root).is_none_or(|min| *min > key));
+        let mid = Node::leaf::<P>(key, value);
+        let root = Self::join_links(left.root.take(), mid, right.root.take());
+        BinaryTreeMap::from_root(Some(root))
+    }
+
+    pub(crate) fn min_key(link: &Link<K, V, P::Meta>) -> Option<&K> {
+        let mut node = link.as_deref()?;
+        while let Some(left) = node.left() {
+            node = left;
+        }
+        Some(&node.key)
+    }
+
+    pub(crate) fn max_key(link: &Link<K, V, P::Meta>) -> Option<&K> {
+        let mut node = link.as_deref()?;
+        while let Some(right) = node.right() {
+            node = right;
+        }
+        Some(&node.key)
+    }
+
+    // Joins `left < mid < right` into one tree. Descends the heavier side
+    // until the policy accepts both halves under `mid`, then rebalances on
+    // the way back up exactly as an insert would.
+    pub(crate) fn join_links(mut left: Link<K, V, P::Meta>, mut mid: Box<Node<K, V, P::Meta>>, mut right: Link<K, V, P::Meta>) -> Box<Node<K, V, P::Meta>> {
+        let mut path = Vec::new();
+        loop {
+            match P::join_direction(left.as_deref(), right.as_deref()) {
+                core::cmp::Ordering::Greater => {
+                    let mut node = left.unwrap();
+                    left = node.right.take();
+                    path.push((node, core::cmp::Ordering::Greater));
+                },
+                core::cmp::Ordering::Less => {
+                    let mut node = right.unwrap();
+                    right = node.left.take();
+                    path.push((node, core::cmp::Ordering::Less));
+                },
+                core::cmp::Ordering::Equal => break,
+            }
+        }
+        mid.left = left;
+        mid.right = right;
+        mid.refresh::<P>();
+        Self::reattach(path, Some(mid), P::after_insert).expect("join produces a node")
+    }
+
+    // Joins `left < right` without a middle entry
+    pub(crate) fn join_pair(left: Link<K, V, P::Meta>, right: Link<K, V, P::Meta>) -> Link<K, V, P::Meta> {
+        match right {
+            None => left,
+            Some(right) => {
+                // The restructuring inside bulk operations is not reported
+                let (min, rest) = Self::extract_min(right, &mut Observer::default());
+                Some(Self::join_links(left, min, rest))
+            }
+        }
+    }
+
+    // Splits into the entries below `key`, the detached node holding `key`
+    // (if any), and the entries above it
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn split_link(mut link: Link<K, V, P::Meta>, key: &K) -> (Link<K, V, P::Meta>, Option<Box<Node<K, V, P::Meta>>>, Link<K, V, P::Meta>) {
+        // Nodes passed on the way down, each with the subtree on the side
+        // the search did not take. Unwinding joins them, deepest first,
+        // onto the half they belong to.
+        let mut passed = Vec::new();
+        let (mut below, found, mut above) = loop {
+            let Some(mut node) = link else {
+                break (None, None, None);
+            };
+            let left = node.left.take();
+            let right = node.right.take();
+            match key.cmp(&node.key) {
+                core::cmp::Ordering::Less => {
+                    passed.push((node, right, core::cmp::Ordering::Less));
+                    link = left;
+                },
+                core::cmp::Ordering::Greater => {
+                    passed.push((node, left, core::cmp::Ordering::Greater));
+                    link = right;
+                },
+                core::cmp::Ordering::Equal => break (left, Some(node), right),
+            }
+        };
+        while let Some((node, other, side)) = passed.pop() {
+            match side {
+                core::cmp::Ordering::Less => above = Some(Self::join_links(above, node, other)),
+                _ => below = Some(Self::join_links(other, node, below)),
+            }
+        }
+        (below, found, above)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Avl, BinaryTreeMap};
+
+    #[test]
+    fn test_split_at_present_key() {
+        let mut map = BinaryTreeMap::with_policy(Avl);
+        for key in 0..100 {
+            map.insert(key, key * 10);
+        }
+
+        let (below, found, above) = map.split(&40);
+
+        assert_eq!(found, Some(400));
+        assert_eq!(below.len(), 40);
+        assert_eq!(above.len(), 59);
+        assert!(below.is_balanced() && above.is_balanced());
+        assert_eq!(below.nth(39), Some((&39, &390)));
+        assert_eq!(above.nth(0), Some((&41, &410)));
+    }
+
+    #[test]
+    fn test_split_at_absent_key() {
+        let mut map = BinaryTreeMap::new();
+        for key in [10, 20, 30] {
+            map.insert(key, ());
+        }
+
+        let (below, found, above) = map.split(&25);
+
+        assert_eq!(found, None);
+        assert_eq!(below.len(), 2);
+        assert_eq!(above.len(), 1);
+    }
+
+    #[test]
+    fn test_join_reassembles_split() {
+        let mut map = BinaryTreeMap::with_policy(Avl);
+        for key in 0..500 {
+            map.insert(key, key);
+        }
+        let (below, found, above) = map.split(&123);
+
+        let map = BinaryTreeMap::join(below, 123, found.unwrap(), above);
+
+        assert_eq!(map.len(), 500);
+        assert!(map.is_balanced());
+        assert_eq!(map.rank(&123), 123);
+        assert_eq!(map.get(&499), Ok(&499));
+    }
+
+    #[test]
+    fn test_split_deep_spine() {
+        let map = crate::right_spine(100_000);
+        let (below, found, above) = map.split(&99_998);
+        assert_eq!((below.len(), found, above.len()), (99_998, Some(99_998), 1));
+
+        let map = BinaryTreeMap::join(below, 99_998, 99_998, above);
+        assert_eq!(map.len(), 100_000);
+        assert_eq!(map.nth(99_999), Some((&99_999, &99_999)));
+    }
+
+    #[test]
+    fn test_join_lopsided_maps() {
+        let small = BinaryTreeMap::from_sorted_iter([(0, ())]);
+        let big: BinaryTreeMap<_, _, Avl> = BinaryTreeMap::from_sorted_iter((2..1000).map(|key| (key, ())));
+
+        let map = BinaryTreeMap::join(small, 1, (), big);
+
+        assert_eq!(map.len(), 1000);
+        assert!(map.is_balanced());
+        assert_eq!(map.nth(1), Some((&1, &())));
+    }
+}
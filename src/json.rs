@@ -0,0 +1,74 @@
+use crate::{shape, BalancePolicy, BinaryTreeMap};
+use serde::{de::DeserializeOwned, Serialize};
+
+// Which serde representation the JSON helpers use
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonFormat {
+    // An object of entries in key order; keys must serialize as strings or
+    // numbers
+    #[default]
+    Entries,
+    // Flat preorder `[key, value]` nodes and nulls, see `shape`
+    Shape,
+}
+
+impl<K, V, P> BinaryTreeMap<K, V, P>
+where
+    K: Ord + Serialize + DeserializeOwned,
+    V: Serialize + DeserializeOwned,
+    P: BalancePolicy<K, V> + Default,
+{
+    pub fn to_json_string(&self, format: JsonFormat) -> Result<String, serde_json::Error> {
+        match format {
+            JsonFormat::Entries => serde_json::to_string(self),
+            JsonFormat::Shape => {
+                let mut out = Vec::new();
+                shape::serialize(self, &mut serde_json::Serializer::new(&mut out))?;
+                Ok(String::from_utf8(out).expect("serde_json writes UTF-8"))
+            },
+        }
+    }
+
+    pub fn from_json_str(json: &str, format: JsonFormat) -> Result<Self, serde_json::Error> {
+        match format {
+            JsonFormat::Entries => serde_json::from_str(json),
+            JsonFormat::Shape => {
+                let mut deserializer = serde_json::Deserializer::from_str(json);
+                let map = shape::deserialize(&mut deserializer)?;
+                deserializer.end()?;
+                Ok(map)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsonFormat;
+    use crate::{BinaryTreeMap, WeightBalancedTreeMap};
+
+    #[test]
+    fn test_round_trip_both_formats() {
+        let mut map = BinaryTreeMap::new();
+        for key in [1u32, 3, 2] {
+            map.insert(key, vec![key; 2]);
+        }
+        let entries = map.to_json_string(JsonFormat::Entries).unwrap();
+        assert_eq!(entries, r#"{"1":[1,1],"2":[2,2],"3":[3,3]}"#);
+        let balanced: BinaryTreeMap<u32, Vec<u32>> = BinaryTreeMap::from_json_str(&entries, JsonFormat::Entries).unwrap();
+        assert_eq!(balanced.height(), 2);
+
+        let shape = map.to_json_string(JsonFormat::Shape).unwrap();
+        let copy: BinaryTreeMap<u32, Vec<u32>> = BinaryTreeMap::from_json_str(&shape, JsonFormat::Shape).unwrap();
+        assert_eq!(copy.height(), 3);
+        assert_eq!(copy.to_json_string(JsonFormat::Shape).unwrap(), shape);
+    }
+
+    #[test]
+    fn test_rejects_bad_json() {
+        let parse = |json, format| WeightBalancedTreeMap::<u32, u32>::from_json_str(json, format);
+        assert!(parse("{\"1\": 2", JsonFormat::Entries).is_err());
+        assert!(parse("[[1, 2], null, null] 7", JsonFormat::Shape).is_err());
+        assert!(parse("[null]", JsonFormat::Shape).unwrap().is_empty());
+    }
+}
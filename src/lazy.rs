@@ -0,0 +1,234 @@
+use crate::augment::{after_start, before_end};
+use crate::node::{Link, Node};
+use crate::{BalancePolicy, BinaryTreeMap, MapError, WeightBalanced};
+use core::ops::{AddAssign, RangeBounds};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+// Weight-balanced policy whose metadata is a pending delta for the node's
+// value and every value below it. The map pushes deltas down as it
+// descends and before it rotates, so a delta never moves to a node it was
+// not meant for.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LazyAdd;
+
+impl<K, V: AddAssign + Clone> BalancePolicy<K, V> for LazyAdd {
+    type Meta = Option<V>;
+
+    fn after_insert(node: Box<Node<K, V, Option<V>>>) -> Box<Node<K, V, Option<V>>> {
+        WeightBalanced::balance::<_, _, Self>(node)
+    }
+
+    fn after_delete(node: Box<Node<K, V, Option<V>>>) -> Box<Node<K, V, Option<V>>> {
+        WeightBalanced::balance::<_, _, Self>(node)
+    }
+
+    fn push_down(node: &mut Node<K, V, Option<V>>) {
+        if let Some(delta) = node.meta.take() {
+            node.value += delta.clone();
+            for child in [&mut node.left, &mut node.right].into_iter().flatten() {
+                add_pending(&mut child.meta, delta.clone());
+            }
+        }
+    }
+
+    fn check(node: &Node<K, V, Option<V>>) -> Result<(), &'static str> {
+        WeightBalanced::check_weights(node)
+    }
+
+    fn join_direction(left: Option<&Node<K, V, Option<V>>>, right: Option<&Node<K, V, Option<V>>>) -> core::cmp::Ordering {
+        WeightBalanced::join_by_weight(left, right)
+    }
+}
+
+fn add_pending<V: AddAssign>(pending: &mut Option<V>, delta: V) {
+    match pending {
+        Some(pending) => *pending += delta,
+        None => *pending = Some(delta),
+    }
+}
+
+// Map supporting `range_update`, which adds a delta to every value in a key
+// range in O(log n) by leaving it pending at the roots of the subtrees it
+// covers. Values are only settled on the way to them, so reads return owned
+// values and the inner map is never handed out. Deltas must commute.
+#[derive(Debug)]
+pub struct LazyTreeMap<K, V: AddAssign + Clone> {
+    map: BinaryTreeMap<K, V, LazyAdd>,
+}
+
+impl<K: Ord, V: AddAssign + Clone> Default for LazyTreeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V: AddAssign + Clone> LazyTreeMap<K, V> {
+    pub fn new() -> Self {
+        LazyTreeMap { map: BinaryTreeMap::default() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        self.map.insert(key, value);
+    }
+
+    pub fn delete(&mut self, key: &K) -> Result<V, MapError> {
+        self.map.delete(key)
+    }
+
+    // Adds up the deltas pending on the search path
+    pub fn get(&self, key: &K) -> Result<V, MapError> {
+        let mut pending = None;
+        let mut current = &self.map.root;
+        while let Some(n) = current {
+            if let Some(delta) = &n.meta {
+                add_pending(&mut pending, delta.clone());
+            }
+            current = match key.cmp(&n.key) {
+                core::cmp::Ordering::Equal => return Ok(settled(&n.value, pending)),
+                core::cmp::Ordering::Less => &n.left,
+                core::cmp::Ordering::Greater => &n.right,
+            };
+        }
+        Err(MapError::KeyNotFound)
+    }
+
+    // Adds `delta` to the value of every key in `range`. Below the node
+    // where the bounds' search paths split, each path leaves the delta on
+    // the subtrees hanging off its inner side.
+    pub fn range_update<R: RangeBounds<K>>(&mut self, range: R, delta: V) {
+        let mut current = &mut self.map.root;
+        while let Some(n) = current {
+            if !after_start(&n.key, range.start_bound()) {
+                current = &mut n.right;
+            } else if !before_end(&n.key, range.end_bound()) {
+                current = &mut n.left;
+            } else {
+                n.value += delta.clone();
+                let mut link = &mut n.left;
+                while let Some(n) = link {
+                    if after_start(&n.key, range.start_bound()) {
+                        n.value += delta.clone();
+                        if let Some(right) = &mut n.right {
+                            add_pending(&mut right.meta, delta.clone());
+                        }
+                        link = &mut n.left;
+                    } else {
+                        link = &mut n.right;
+                    }
+                }
+                let mut link = &mut n.right;
+                while let Some(n) = link {
+                    if before_end(&n.key, range.end_bound()) {
+                        n.value += delta.clone();
+                        if let Some(left) = &mut n.left {
+                            add_pending(&mut left.meta, delta.clone());
+                        }
+                        link = &mut n.right;
+                    } else {
+                        link = &mut n.left;
+                    }
+                }
+                return;
+            }
+        }
+    }
+
+    // In-order iterator carrying each ancestor's pending total on the stack
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter { stack: Vec::new() };
+        iter.push_left(&self.map.root, None);
+        iter
+    }
+}
+
+// A node's value, with the deltas pending above and on it applied
+fn settled<V: AddAssign + Clone>(value: &V, pending: Option<V>) -> V {
+    let mut value = value.clone();
+    if let Some(delta) = pending {
+        value += delta;
+    }
+    value
+}
+
+#[allow(clippy::type_complexity)]
+pub struct Iter<'a, K, V> {
+    stack: Vec<(&'a Node<K, V, Option<V>>, Option<V>)>,
+}
+
+impl<'a, K, V: AddAssign + Clone> Iter<'a, K, V> {
+    fn push_left(&mut self, mut link: &'a Link<K, V, Option<V>>, mut pending: Option<V>) {
+        while let Some(n) = link {
+            if let Some(delta) = &n.meta {
+                add_pending(&mut pending, delta.clone());
+            }
+            self.stack.push((n, pending.clone()));
+            link = &n.left;
+        }
+    }
+}
+
+impl<'a, K, V: AddAssign + Clone> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (n, pending) = self.stack.pop()?;
+        self.push_left(&n.right, pending.clone());
+        Some((&n.key, settled(&n.value, pending)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_update_applies_to_range_only() {
+        let mut map = LazyTreeMap::new();
+        for key in 0..100 {
+            map.insert(key, 0i64);
+        }
+        map.range_update(10..20, 5);
+        map.range_update(15..=30, 1);
+        map.range_update(..3, -2);
+        for key in 0..100 {
+            let expected = (if (10..20).contains(&key) { 5 } else { 0 })
+                + (if (15..=30).contains(&key) { 1 } else { 0 })
+                + (if key < 3 { -2 } else { 0 });
+            assert_eq!(map.get(&key), Ok(expected));
+        }
+        assert_eq!(map.iter().map(|(_, v)| v).sum::<i64>(), 5 * 10 + 16 - 6);
+        assert_eq!(map.get(&100), Err(MapError::KeyNotFound));
+    }
+
+    #[test]
+    fn test_pending_deltas_survive_restructuring() {
+        let mut map = LazyTreeMap::new();
+        let mut expected = alloc::collections::BTreeMap::new();
+        for round in 0..20u64 {
+            for key in (0..50).map(|i| (i * 17 + round * 50) % 1000) {
+                map.insert(key, key);
+                expected.insert(key, key);
+            }
+            let (lo, hi) = (round * 31 % 700, round * 31 % 700 + 250);
+            map.range_update(lo..hi, round);
+            for value in expected.range_mut(lo..hi).map(|(_, v)| v) {
+                *value += round;
+            }
+            for key in (0..1000).step_by(7 + round as usize) {
+                assert_eq!(map.delete(&key).ok(), expected.remove(&key));
+            }
+        }
+        assert_eq!(map.len(), expected.len());
+        assert!(map.iter().eq(expected.iter().map(|(k, v)| (k, *v))));
+        assert_eq!(map.map.check_invariants(), Ok(()));
+    }
+}
@@ -0,0 +1,180 @@
+use crate::{BalancePolicy, BinaryTreeMap};
+use core::fmt::Write;
+use alloc::string::String;
+use alloc::vec;
+
+// Keys and values that can be written as Lean literals, along with the Lean
+// type they belong to
+pub trait LeanTerm {
+    const LEAN_TYPE: &'static str;
+
+    fn write_lean(&self, out: &mut String);
+}
+
+macro_rules! lean_nat {
+    ($($t:ty),*) => {$(
+        impl LeanTerm for $t {
+            const LEAN_TYPE: &'static str = "Nat";
+
+            fn write_lean(&self, out: &mut String) {
+                write!(out, "{self}").unwrap();
+            }
+        }
+    )*};
+}
+
+macro_rules! lean_int {
+    ($($t:ty),*) => {$(
+        impl LeanTerm for $t {
+            const LEAN_TYPE: &'static str = "Int";
+
+            // Negative literals need parentheses to be a single argument
+            fn write_lean(&self, out: &mut String) {
+                if *self < 0 {
+                    write!(out, "({self})").unwrap();
+                } else {
+                    write!(out, "{self}").unwrap();
+                }
+            }
+        }
+    )*};
+}
+
+// Lean source is UTF-8, so only quotes, backslashes and control characters
+// need escaping. Lean's `\u` takes exactly four hex digits rather than Rust's
+// braces.
+fn escape(out: &mut String, c: char, quote: char) {
+    match c {
+        '\\' => out.push_str("\\\\"),
+        '\n' => out.push_str("\\n"),
+        '\t' => out.push_str("\\t"),
+        '\r' => out.push_str("\\r"),
+        c if c == quote => {
+            out.push('\\');
+            out.push(c);
+        },
+        c if c.is_control() => write!(out, "\\u{:04x}", c as u32).unwrap(),
+        c => out.push(c),
+    }
+}
+
+lean_nat!(u8, u16, u32, u64, u128, usize);
+lean_int!(i8, i16, i32, i64, i128, isize);
+
+impl LeanTerm for bool {
+    const LEAN_TYPE: &'static str = "Bool";
+
+    fn write_lean(&self, out: &mut String) {
+        out.push_str(if *self { "true" } else { "false" });
+    }
+}
+
+impl LeanTerm for char {
+    const LEAN_TYPE: &'static str = "Char";
+
+    fn write_lean(&self, out: &mut String) {
+        out.push('\'');
+        escape(out, *self, '\'');
+        out.push('\'');
+    }
+}
+
+impl LeanTerm for str {
+    const LEAN_TYPE: &'static str = "String";
+
+    fn write_lean(&self, out: &mut String) {
+        out.push('"');
+        for c in self.chars() {
+            escape(out, c, '"');
+        }
+        out.push('"');
+    }
+}
+
+impl LeanTerm for String {
+    const LEAN_TYPE: &'static str = "String";
+
+    fn write_lean(&self, out: &mut String) {
+        self.as_str().write_lean(out);
+    }
+}
+
+impl LeanTerm for () {
+    const LEAN_TYPE: &'static str = "Unit";
+
+    fn write_lean(&self, out: &mut String) {
+        out.push_str("()");
+    }
+}
+
+impl<K: Ord + LeanTerm, V: LeanTerm, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // The tree as a term of the `BinaryTreeMap` structure in
+    // BinaryTreeMap.lean, node for node, so a tree produced here can be fed
+    // to the Lean definitions and theorems. The type ascription pins `K` and
+    // `V`, which Lean cannot infer from an empty map.
+    //
+    //     ({ root := some (BTreeNode.node 2 "b" (some (BTreeNode.node 1 "a" none none)) none) }
+    //       : BinaryTreeMap Nat String)
+    pub fn to_lean(&self) -> String {
+        enum Part<'a, N> {
+            Link(Option<&'a N>),
+            Text(&'static str),
+        }
+        let mut out = String::from("({ root := ");
+        let mut pending = vec![Part::Link(self.root.as_deref())];
+        while let Some(part) = pending.pop() {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Link(None) => out.push_str("none"),
+                Part::Link(Some(node)) => {
+                    out.push_str("some (BTreeNode.node ");
+                    node.key.write_lean(&mut out);
+                    out.push(' ');
+                    node.value.write_lean(&mut out);
+                    // Children are wrapped in parentheses unless they are `none`
+                    pending.push(Part::Text(")"));
+                    for child in [node.right(), node.left()] {
+                        if child.is_some() {
+                            pending.push(Part::Text(")"));
+                        }
+                        pending.push(Part::Link(child));
+                        pending.push(Part::Text(if child.is_some() { " (" } else { " " }));
+                    }
+                },
+            }
+        }
+        write!(out, " }} : BinaryTreeMap {} {})", K::LEAN_TYPE, V::LEAN_TYPE).unwrap();
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BinaryTreeMap;
+
+    #[test]
+    fn test_renders_nodes_in_place() {
+        let mut map = BinaryTreeMap::new();
+        for (key, value) in [(2u32, "b"), (1, "a"), (3, "c\"d\u{1}é")] {
+            map.insert(key, value.to_string());
+        }
+        assert_eq!(
+            map.to_lean(),
+            "({ root := some (BTreeNode.node 2 \"b\" (some (BTreeNode.node 1 \"a\" none none)) \
+             (some (BTreeNode.node 3 \"c\\\"d\\u0001é\" none none))) } : BinaryTreeMap Nat String)"
+        );
+    }
+
+    #[test]
+    fn test_empty_and_signed() {
+        let mut map: BinaryTreeMap<i64, bool> = BinaryTreeMap::new();
+        assert_eq!(map.to_lean(), "({ root := none } : BinaryTreeMap Int Bool)");
+        map.insert(-1, true);
+        map.insert(5, false);
+        assert_eq!(
+            map.to_lean(),
+            "({ root := some (BTreeNode.node (-1) true none (some (BTreeNode.node 5 false none none))) } \
+             : BinaryTreeMap Int Bool)"
+        );
+    }
+}
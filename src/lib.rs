@@ -1,134 +1,664 @@
-#[derive(Debug, PartialEq)]
+// Without the default `std` feature the crate is `no_std` and needs only
+// `alloc`; the features that rely on threads, I/O or foreign runtimes
+// enable `std` themselves
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MapError {
     KeyNotFound,
+    // Returned by `try_insert` when the key is already present
+    DuplicateKey,
+    // Encoded input was truncated, malformed or out of order
+    InvalidEncoding,
+    // A fixed-capacity map is full
+    CapacityExceeded,
+    // A patch was computed from a map that differs from its target
+    Conflict,
 }
 
-#[derive(Debug)]
-struct Node<K, V> {
-    key: K,
-    value: V,
-    left: Option<Box<Node<K, V>>>,
-    right: Option<Box<Node<K, V>>>,
+impl core::fmt::Display for MapError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str(match self {
+            MapError::KeyNotFound => "key not found",
+            MapError::DuplicateKey => "key already present",
+            MapError::InvalidEncoding => "invalid encoding",
+            MapError::CapacityExceeded => "capacity exceeded",
+            MapError::Conflict => "patch conflicts with map",
+        })
+    }
 }
 
+// `core::error::Error` is `std::error::Error`, so this also works without std
+impl core::error::Error for MapError {}
+
+#[cfg(feature = "arbitrary")]
+mod arbitrary_impl;
+pub mod arena;
+mod augment;
+mod balance;
+mod bounded;
+mod build;
+mod builder;
+mod bytes;
+#[cfg(feature = "concurrent")]
+pub mod concurrent;
+mod convert;
+#[cfg(feature = "std")]
+mod csv;
+mod diff;
+mod display;
+#[cfg(feature = "std")]
+mod durable;
+mod expiring;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+mod file;
+mod fixed;
+mod frozen;
+mod inspect;
+#[cfg(feature = "intern")]
+mod intern;
+mod interval;
+mod invariants;
+mod iter;
+mod join;
+#[cfg(feature = "json")]
+mod json;
+mod lazy;
+mod lean;
+mod linked;
+#[cfg(feature = "lock_free")]
+pub mod lock_free;
+mod memory;
+mod merkle;
+#[cfg(all(feature = "mmap", unix))]
+mod mmap;
+mod nearest;
+mod node;
+mod observer;
+mod oplog;
+#[cfg(feature = "rayon")]
+mod parallel;
+pub mod persistent;
+mod pool;
+mod prefix;
+mod proof;
+#[cfg(feature = "python")]
+mod python;
+mod queue;
+#[cfg(feature = "quickcheck")]
+mod quickcheck_impl;
+mod rebalance;
+#[cfg(feature = "rand")]
+mod sample;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod set_ops;
+#[cfg(debug_assertions)]
+mod shadow;
+#[cfg(feature = "serde")]
+pub mod shape;
+mod small;
+mod stats;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+mod sync;
+#[cfg(feature = "teaching")]
+mod teaching;
+mod time_travel;
+mod to_persistent;
+mod tombstone;
+mod transform;
+mod tree_stats;
+mod undo;
+mod value_ref;
+#[cfg(all(kani, feature = "verification"))]
+mod verification;
+mod versioned;
+mod visit;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod weight_balanced;
+mod weighted;
+mod zip;
+
+pub use allocator_api2::alloc::AllocError;
+pub use arena::{ArenaTreeMap, EntryId};
+pub use augment::{Augment, Augmented, AugmentedTreeMap};
+pub use balance::{Avl, BalancePolicy, Unbalanced, ValueIndependent};
+pub use bounded::{BoundedTreeMap, EvictionPolicy};
+pub use builder::MapBuilder;
+pub use bytes::ByteCodec;
+pub use diff::{ConflictPolicy, MapDiff};
+#[cfg(feature = "std")]
+pub use durable::DurableTreeMap;
+pub use expiring::ExpiringTreeMap;
+pub use fixed::FixedBinaryTreeMap;
+pub use frozen::FrozenBinaryTreeMap;
+#[cfg(feature = "intern")]
+pub use intern::{InternedTreeMap, Interner};
+pub use interval::{Interval, IntervalTreeMap, MaxEnd, Overlapping};
+pub use invariants::{InvariantViolation, ViolationKind};
+pub use iter::{Iter, LevelOrder, Levels, Range, WithDepth};
+#[cfg(feature = "json")]
+pub use json::JsonFormat;
+pub use lazy::{LazyAdd, LazyTreeMap};
+pub use lean::LeanTerm;
+pub use memory::MemoryReport;
+pub use merkle::{MerkleHash, MerkleTreeMap};
+#[cfg(all(feature = "mmap", unix))]
+pub use mmap::MmapTreeMap;
+pub use nearest::{KeyDistance, NearestN};
+pub use node::Node;
+pub use observer::MapObserver;
+pub use oplog::{LoggedMap, Op, OpLog};
+pub use prefix::PrefixKey;
+pub use proof::SearchProof;
+#[cfg(feature = "python")]
+pub use python::PyBinaryTreeMap;
+#[cfg(feature = "rayon")]
+pub use parallel::{ParIter, ParIterMut};
+pub use queue::{MaxQueue, MinQueue};
+#[cfg(debug_assertions)]
+pub use shadow::ShadowedMap;
+pub use small::SmallTreeMap;
+pub use stats::OpStats;
+pub use sync::{RangeEntries, RangeHash, SyncMessage};
+pub use time_travel::TimeTravel;
+pub use tombstone::TombstoneTreeMap;
+pub use tree_stats::TreeStats;
+pub use undo::UndoableTreeMap;
+pub use value_ref::{Pinned, StableTreeMap, ValueRef};
+pub use versioned::VersionedTreeMap;
+pub use visit::TreeVisitor;
+#[cfg(feature = "wasm")]
+pub use wasm::JsBinaryTreeMap;
+pub use weight_balanced::{WeightBalanced, WeightBalancedTreeMap};
+pub use weighted::{HasWeight, TotalWeight, WeightedTreeMap};
+pub use zip::{Joined, ZipEntry};
+
+use node::{Link, Path};
+use observer::Observer;
+use pool::NodePool;
+use stats::{Probe, StatsCell};
+use core::marker::PhantomData;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use alloc::vec;
+
 #[derive(Debug)]
-pub struct BinaryTreeMap<K, V> {
-    root: Option<Box<Node<K, V>>>,
+pub struct BinaryTreeMap<K, V, P: BalancePolicy<K, V> = Unbalanced> {
+    root: Link<K, V, P::Meta>,
+    pool: NodePool<K, V, P::Meta>,
+    stats: StatsCell,
+    observer: Observer<K, V>,
+    policy: PhantomData<P>,
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, V> + Default> Default for BinaryTreeMap<K, V, P> {
+    fn default() -> Self {
+        Self::with_policy(P::default())
+    }
+}
+
+// Dropping the `Box` chain directly recurses once per level, so unlink the
+// nodes iteratively: rotate left children up until the root has none, then
+// free the root and continue with its right subtree.
+impl<K, V, P: BalancePolicy<K, V>> Drop for BinaryTreeMap<K, V, P> {
+    fn drop(&mut self) {
+        node::drop_link(self.root.take());
+    }
+}
+
+// Copies node for node, so the clone keeps the original's shape and policy
+// metadata. Built bottom-up from an explicit stack like `Drop` above.
+impl<K: Ord + Clone, V: Clone, P: BalancePolicy<K, V>> Clone for BinaryTreeMap<K, V, P>
+where
+    P::Meta: Clone,
+{
+    fn clone(&self) -> Self {
+        enum Visit<'a, K, V, M> {
+            Enter(&'a Link<K, V, M>),
+            Exit(&'a node::Node<K, V, M>),
+        }
+        let mut pending = vec![Visit::Enter(&self.root)];
+        let mut built = Vec::new();
+        while let Some(visit) = pending.pop() {
+            match visit {
+                Visit::Enter(None) => built.push(None),
+                Visit::Enter(Some(node)) => {
+                    pending.push(Visit::Exit(node));
+                    pending.push(Visit::Enter(&node.right));
+                    pending.push(Visit::Enter(&node.left));
+                },
+                Visit::Exit(node) => {
+                    let right = built.pop().expect("right subtree was built");
+                    let left = built.pop().expect("left subtree was built");
+                    stats::count_allocation();
+                    built.push(Some(Box::new(node::Node {
+                        key: node.key.clone(),
+                        value: node.value.clone(),
+                        size: node.size,
+                        meta: node.meta.clone(),
+                        left,
+                        right,
+                    })));
+                },
+            }
+        }
+        Self::from_root(built.pop().expect("root was built"))
+    }
 }
 
 impl<K: Ord, V> BinaryTreeMap<K, V> {
     pub fn new() -> Self {
-        BinaryTreeMap { root: None }
+        Self::with_policy(Unbalanced)
+    }
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    pub fn with_policy(_policy: P) -> Self {
+        Self::from_root(None)
+    }
+
+    pub(crate) fn from_root(root: Link<K, V, P::Meta>) -> Self {
+        BinaryTreeMap {
+            root,
+            pool: NodePool::new(),
+            stats: StatsCell::default(),
+            observer: Observer::default(),
+            policy: PhantomData,
+        }
     }
 
     pub fn insert(&mut self, key: K, value: V) {
-        self.root = Self::insert_recursive(self.root.take(), key, value);
-    }
-
-    fn insert_recursive(node: Option<Box<Node<K, V>>>, key: K, value: V) -> Option<Box<Node<K, V>>> {
-        match node {
-            None => Some(Box::new(Node {
-                key,
-                value,
-                left: None,
-                right: None,
-            })),
-            Some(mut n) => {
-                match key.cmp(&n.key) {
-                    std::cmp::Ordering::Equal => {
-                        n.value = value; // Update existing value
-                        Some(n)
-                    },
-                    std::cmp::Ordering::Less => {
-                        n.left = Self::insert_recursive(n.left, key, value);
-                        Some(n)
-                    },
-                    std::cmp::Ordering::Greater => {
-                        n.right = Self::insert_recursive(n.right, key, value);
-                        Some(n)
-                    },
+        let Ok(()) = self.insert_with(key, value, Vec::new(), Self::replace_value, |pool, key, value| {
+            Ok::<_, core::convert::Infallible>(pool.leaf::<P>(key, value))
+        });
+    }
+
+    fn replace_value(observer: &mut Observer<K, V>, key: &mut K, value: &mut V, _: K, new: V) {
+        let old = core::mem::replace(value, new);
+        observer.notify(|o| o.on_update(key, &old, value));
+    }
+
+    // Inserts `value` if `key` is absent, otherwise folds it into the
+    // existing value with `combine(existing, value)`, in one traversal.
+    // A merge is reported like `update`'s change, with the merged value.
+    pub fn upsert(&mut self, key: K, value: V, combine: impl FnOnce(&mut V, V)) {
+        let merge = |observer: &mut Observer<K, V>, key: &mut K, old: &mut V, _, new| {
+            combine(old, new);
+            observer.notify(|o| o.on_modify(key, old));
+        };
+        let Ok(()) = self.insert_with(key, value, Vec::new(), merge, |pool, key, value| {
+            Ok::<_, core::convert::Infallible>(pool.leaf::<P>(key, value))
+        });
+    }
+
+    // Like `insert`, but also swaps in the new key object, which may differ
+    // from the old in ways its ordering ignores. Returns the previous key
+    // and value, as `BTreeSet::replace` does.
+    pub fn replace(&mut self, key: K, value: V) -> Option<(K, V)> {
+        let mut previous = None;
+        let merge = |observer: &mut Observer<K, V>, old_key: &mut K, old_value: &mut V, key, value| {
+            let old_key = core::mem::replace(old_key, key);
+            let old = core::mem::replace(old_value, value);
+            observer.notify(|o| o.on_update(&old_key, &old, old_value));
+            previous = Some((old_key, old));
+        };
+        let Ok(()) = self.insert_with(key, value, Vec::new(), merge, |pool, key, value| {
+            Ok::<_, core::convert::Infallible>(pool.leaf::<P>(key, value))
+        });
+        previous
+    }
+
+    // Like `insert`, but reports running out of memory instead of aborting.
+    // The search path is reserved before the tree is taken apart, so the
+    // new node is the only allocation left once the descent begins.
+    pub fn try_insert_alloc(&mut self, key: K, value: V) -> Result<(), AllocError> {
+        let mut path = Vec::new();
+        path.try_reserve_exact(self.search_depth(&key)).map_err(|_| AllocError)?;
+        self.insert_with(key, value, path, Self::replace_value, |pool, key, value| pool.try_leaf::<P>(key, value))
+    }
+
+    // Nodes a search for `key` passes before reaching it or an empty link
+    fn search_depth(&self, key: &K) -> usize {
+        let mut depth = 0;
+        let mut current = &self.root;
+        while let Some(n) = current {
+            current = match key.cmp(&n.key) {
+                core::cmp::Ordering::Equal => break,
+                core::cmp::Ordering::Less => &n.left,
+                core::cmp::Ordering::Greater => &n.right,
+            };
+            depth += 1;
+        }
+        depth
+    }
+
+    // Shared body of the insert variants. `merge` stores the new key and
+    // value into an existing entry and tells the observer. `leaf` makes the
+    // new node; if it fails the tree is put back unchanged.
+    fn insert_with<E>(
+        &mut self,
+        key: K,
+        value: V,
+        mut path: Path<K, V, P::Meta>,
+        merge: impl FnOnce(&mut Observer<K, V>, &mut K, &mut V, K, V),
+        leaf: impl FnOnce(&mut NodePool<K, V, P::Meta>, K, V) -> Result<Box<Node<K, V, P::Meta>>, E>,
+    ) -> Result<(), E> {
+        let mut probe = Probe::start(&self.stats);
+        let mut current = self.root.take();
+        loop {
+            match current {
+                None => {
+                    let leaf = match leaf(&mut self.pool, key, value) {
+                        Ok(leaf) => leaf,
+                        Err(error) => {
+                            self.root = Self::reattach(path, None, |n| n);
+                            return Err(error);
+                        },
+                    };
+                    self.observer.notify(|o| o.on_insert(&leaf.key, &leaf.value));
+                    self.root = Self::reattach(path, Some(leaf), self.observer.watch(P::after_insert));
+                    return Ok(());
+                },
+                Some(mut n) => {
+                    probe.step();
+                    P::push_down(&mut n);
+                    match key.cmp(&n.key) {
+                        core::cmp::Ordering::Equal => {
+                            // Update existing value
+                            merge(&mut self.observer, &mut n.key, &mut n.value, key, value);
+                            // Policy metadata may summarize values
+                            n.refresh::<P>();
+                            self.root = Self::reattach(path, Some(n), |n| n);
+                            return Ok(());
+                        },
+                        core::cmp::Ordering::Less => {
+                            current = n.left.take();
+                            path.push((n, core::cmp::Ordering::Less));
+                        },
+                        core::cmp::Ordering::Greater => {
+                            current = n.right.take();
+                            path.push((n, core::cmp::Ordering::Greater));
+                        },
+                    }
                 }
             }
         }
     }
 
+    // Inserts only if `key` is absent, leaving an existing value untouched
+    pub fn try_insert(&mut self, key: K, value: V) -> Result<(), MapError> {
+        if self.get(&key).is_ok() {
+            return Err(MapError::DuplicateKey);
+        }
+        self.insert(key, value);
+        Ok(())
+    }
+
+    // Modifies the value for `key` in place, returning whether it was there.
+    // The path is detached like `insert`'s, so metadata that summarizes
+    // values is refreshed. Observers get `on_modify`, as the old value is
+    // gone by then.
+    pub fn update(&mut self, key: &K, f: impl FnOnce(&mut V)) -> bool {
+        let mut probe = Probe::start(&self.stats);
+        let mut path = Vec::new();
+        let mut current = self.root.take();
+        while let Some(mut n) = current {
+            probe.step();
+            P::push_down(&mut n);
+            match key.cmp(&n.key) {
+                core::cmp::Ordering::Less => {
+                    current = n.left.take();
+                    path.push((n, core::cmp::Ordering::Less));
+                },
+                core::cmp::Ordering::Greater => {
+                    current = n.right.take();
+                    path.push((n, core::cmp::Ordering::Greater));
+                },
+                core::cmp::Ordering::Equal => {
+                    f(&mut n.value);
+                    n.refresh::<P>();
+                    self.observer.notify(|o| o.on_modify(&n.key, &n.value));
+                    self.root = Self::reattach(path, Some(n), |n| n);
+                    return true;
+                },
+            }
+        }
+        self.root = Self::reattach(path, None, |n| n);
+        false
+    }
+
+    // The mutating operations detach each node from its parent on the way
+    // down, remembering which side they came from. This puts the subtree
+    // back together bottom-up, refreshing sizes and running `fix` (the
+    // policy hook) at every ancestor, without recursion.
+    fn reattach<F>(mut path: Path<K, V, P::Meta>, mut subtree: Link<K, V, P::Meta>, mut fix: F) -> Link<K, V, P::Meta>
+    where
+        F: FnMut(Box<Node<K, V, P::Meta>>) -> Box<Node<K, V, P::Meta>>,
+    {
+        while let Some((mut parent, side)) = path.pop() {
+            match side {
+                core::cmp::Ordering::Less => parent.left = subtree,
+                _ => parent.right = subtree,
+            }
+            parent.refresh::<P>();
+            subtree = Some(fix(parent));
+        }
+        subtree
+    }
+
     pub fn delete(&mut self, key: &K) -> Result<V, MapError> {
-        let mut deleted_value = None;
-        self.root = Self::delete_recursive(self.root.take(), key, &mut deleted_value);
-        deleted_value.ok_or(MapError::KeyNotFound)
-    }
-
-    fn delete_recursive(node: Option<Box<Node<K, V>>>, key: &K, deleted_value: &mut Option<V>) -> Option<Box<Node<K, V>>> {
-        match node {
-            None => None,
-            Some(mut n) => {
-                match key.cmp(&n.key) {
-                    std::cmp::Ordering::Less => {
-                        n.left = Self::delete_recursive(n.left, key, deleted_value);
-                        Some(n)
-                    },
-                    std::cmp::Ordering::Greater => {
-                        n.right = Self::delete_recursive(n.right, key, deleted_value);
-                        Some(n)
-                    },
-                    std::cmp::Ordering::Equal => {
-                        *deleted_value = Some(n.value);
-                        match (n.left, n.right) {
-                            // Case 1: No children - just remove the node
-                            (None, None) => None,
-                            // Case 2: One child - replace with the child
-                            (Some(left), None) => Some(left),
-                            (None, Some(right)) => Some(right),
-                            // Case 3: Two children - replace with in-order successor
-                            (Some(left), Some(right)) => {
-                                let (successor_value, successor_key, new_right) = Self::extract_min(right);
-                                Some(Box::new(Node {
-                                    key: successor_key,
-                                    value: successor_value,
-                                    left: Some(left),
-                                    right: new_right,
-                                }))
-                            }
+        let mut probe = Probe::start(&self.stats);
+        let mut path = Vec::new();
+        let mut current = self.root.take();
+        while let Some(mut n) = current {
+            probe.step();
+            P::push_down(&mut n);
+            match key.cmp(&n.key) {
+                core::cmp::Ordering::Less => {
+                    current = n.left.take();
+                    path.push((n, core::cmp::Ordering::Less));
+                },
+                core::cmp::Ordering::Greater => {
+                    current = n.right.take();
+                    path.push((n, core::cmp::Ordering::Greater));
+                },
+                core::cmp::Ordering::Equal => {
+                    let Node { key, value, left, right, .. } = self.pool.recycle(n);
+                    self.observer.notify(|o| o.on_remove(&key, &value));
+                    let replacement = match (left, right) {
+                        // Case 1: No children - just remove the node
+                        (None, None) => None,
+                        // Case 2: One child - replace with the child
+                        (Some(left), None) => Some(left),
+                        (None, Some(right)) => Some(right),
+                        // Case 3: Two children - replace with in-order successor
+                        (Some(left), Some(right)) => {
+                            let (mut successor, new_right) = Self::extract_min(right, &mut self.observer);
+                            successor.left = Some(left);
+                            successor.right = new_right;
+                            successor.refresh::<P>();
+                            Some(self.observer.watch(P::after_delete)(successor))
                         }
-                    }
+                    };
+                    self.root = Self::reattach(path, replacement, self.observer.watch(P::after_delete));
+                    return Ok(value);
                 }
             }
         }
+        // Key not found: put the tree back together unchanged
+        self.root = Self::reattach(path, None, |n| n);
+        Err(MapError::KeyNotFound)
     }
 
-    fn extract_min(mut node: Box<Node<K, V>>) -> (V, K, Option<Box<Node<K, V>>>) {
-        match node.left.take() {
-            None => {
-                // This node is the minimum
-                (node.value, node.key, node.right)
-            },
-            Some(left) => {
-                // Recurse to find minimum in left subtree
-                let (min_value, min_key, new_left) = Self::extract_min(left);
-                node.left = new_left;
-                (min_value, min_key, Some(node))
-            }
+    // Detaches the minimum node, returning it (without children) along with
+    // the rest of the tree
+    #[allow(clippy::type_complexity)]
+    fn extract_min(
+        node: Box<Node<K, V, P::Meta>>,
+        observer: &mut Observer<K, V>,
+    ) -> (Box<Node<K, V, P::Meta>>, Link<K, V, P::Meta>) {
+        let mut path = Vec::new();
+        let mut current = node;
+        P::push_down(&mut current);
+        while let Some(mut left) = current.left.take() {
+            P::push_down(&mut left);
+            path.push((current, core::cmp::Ordering::Less));
+            current = left;
         }
+        // `current` is the minimum; its right subtree takes its place
+        let right = current.right.take();
+        (current, Self::reattach(path, right, observer.watch(P::after_delete)))
     }
 
-    pub fn get(&self, key: &K) -> Result<&V, MapError> {
-        self.get_recursive(&self.root, key)
+    #[allow(clippy::type_complexity)]
+    fn extract_max(
+        node: Box<Node<K, V, P::Meta>>,
+        observer: &mut Observer<K, V>,
+    ) -> (Box<Node<K, V, P::Meta>>, Link<K, V, P::Meta>) {
+        let mut path = Vec::new();
+        let mut current = node;
+        P::push_down(&mut current);
+        while let Some(mut right) = current.right.take() {
+            P::push_down(&mut right);
+            path.push((current, core::cmp::Ordering::Greater));
+            current = right;
+        }
+        let left = current.left.take();
+        (current, Self::reattach(path, left, observer.watch(P::after_delete)))
     }
 
-    fn get_recursive<'a>(&self, node: &'a Option<Box<Node<K, V>>>, key: &K) -> Result<&'a V, MapError> {
-        match node {
-            None => Err(MapError::KeyNotFound),
-            Some(n) => {
-                match key.cmp(&n.key) {
-                    std::cmp::Ordering::Equal => Ok(&n.value),
-                    std::cmp::Ordering::Less => self.get_recursive(&n.left, key),
-                    std::cmp::Ordering::Greater => self.get_recursive(&n.right, key),
-                }
+    pub fn get(&self, key: &K) -> Result<&V, MapError> {
+        let mut probe = Probe::start(&self.stats);
+        let mut current = &self.root;
+        while let Some(n) = current {
+            probe.step();
+            match key.cmp(&n.key) {
+                core::cmp::Ordering::Equal => return Ok(&n.value),
+                core::cmp::Ordering::Less => current = &n.left,
+                core::cmp::Ordering::Greater => current = &n.right,
             }
         }
+        Err(MapError::KeyNotFound)
+    }
+
+    // `Option`-returning forms of `get` and `delete`, matching `BTreeMap`
+    pub fn get_opt(&self, key: &K) -> Option<&V> {
+        self.get(key).ok()
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.delete(key).ok()
+    }
+
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(left) = node.left.as_deref() {
+            node = left;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let mut node = self.root.as_deref()?;
+        while let Some(right) = node.right.as_deref() {
+            node = right;
+        }
+        Some((&node.key, &node.value))
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        let (first, rest) = Self::extract_min(self.root.take()?, &mut self.observer);
+        self.root = rest;
+        let Node { key, value, .. } = self.pool.recycle(first);
+        self.observer.notify(|o| o.on_remove(&key, &value));
+        Some((key, value))
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        let (last, rest) = Self::extract_max(self.root.take()?, &mut self.observer);
+        self.root = rest;
+        let Node { key, value, .. } = self.pool.recycle(last);
+        self.observer.notify(|o| o.on_remove(&key, &value));
+        Some((key, value))
+    }
+
+    pub fn len(&self) -> usize {
+        Node::size_of(&self.root)
+    }
+
+    // Keeps up to `limit` deleted nodes around for reuse by later inserts
+    pub fn set_node_pool_limit(&mut self, limit: usize) {
+        self.pool.set_limit(limit);
+    }
+
+    pub fn pooled_nodes(&self) -> usize {
+        self.pool.len()
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn op_stats(&self) -> OpStats {
+        self.stats.get()
+    }
+
+    #[cfg(feature = "stats")]
+    pub fn reset_op_stats(&self) {
+        self.stats.reset();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    // Entry at position `index` in key order
+    pub fn nth(&self, index: usize) -> Option<(&K, &V)> {
+        Node::nth(&self.root, index)
+    }
+
+    // Number of keys strictly less than `key`
+    pub fn rank(&self, key: &K) -> usize {
+        Node::rank(&self.root, key)
+    }
+
+    // Entry at quantile `q` in key order by the nearest-rank method: the
+    // smallest key with at least `q` of the entries at or below it. `None`
+    // for an empty map or `q` outside 0..=1.
+    pub fn quantile(&self, q: f64) -> Option<(&K, &V)> {
+        if !(0.0..=1.0).contains(&q) {
+            return None;
+        }
+        // `ceil` is not in `core`
+        let scaled = q * self.len() as f64;
+        let mut rank = scaled as usize;
+        if (rank as f64) < scaled {
+            rank += 1;
+        }
+        self.nth(rank.saturating_sub(1))
     }
 }
 
+// Map with keys `0..len` as one right spine, the shape ascending inserts
+// give an unbalanced map, built directly since those inserts are quadratic
+#[cfg(test)]
+pub(crate) fn right_spine(len: u32) -> BinaryTreeMap<u32, u32> {
+    let mut map = BinaryTreeMap::new();
+    for key in (0..len).rev() {
+        let mut node = Node::leaf::<Unbalanced>(key, key);
+        node.right = map.root.take();
+        node.refresh::<Unbalanced>();
+        map.root = Some(node);
+    }
+    map
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -239,4 +769,209 @@ mod tests {
         // Verify original data is untouched
         assert_eq!(map.get(&50), Ok(&"root".to_string()));
     }
+
+    #[test]
+    fn test_len_tracks_inserts_and_deletes() {
+        let mut map = BinaryTreeMap::new();
+        assert!(map.is_empty());
+        map.insert(50, "a");
+        map.insert(30, "b");
+        map.insert(70, "c");
+        map.insert(30, "d");
+        assert_eq!(map.len(), 3);
+
+        map.delete(&50).unwrap();
+        assert_eq!(map.len(), 2);
+    }
+
+    #[test]
+    fn test_nth_and_rank() {
+        let mut map = BinaryTreeMap::new();
+        for key in [50, 30, 70, 20, 40, 60, 80] {
+            map.insert(key, key * 10);
+        }
+
+        assert_eq!(map.nth(0), Some((&20, &200)));
+        assert_eq!(map.nth(3), Some((&50, &500)));
+        assert_eq!(map.nth(6), Some((&80, &800)));
+        assert_eq!(map.nth(7), None);
+
+        assert_eq!(map.rank(&20), 0);
+        assert_eq!(map.rank(&55), 4);
+        assert_eq!(map.rank(&99), 7);
+    }
+
+    #[test]
+    fn test_degenerate_tree_does_not_overflow_stack() {
+        let mut map = crate::right_spine(100_000);
+        map.insert(100_000, 100_000);
+
+        assert_eq!(map.get(&100_000), Ok(&100_000));
+        assert_eq!(map.delete(&50_000), Ok(50_000));
+        assert_eq!(map.delete(&50_000), Err(MapError::KeyNotFound));
+        assert_eq!(map.delete(&0), Ok(0));
+        assert_eq!(map.len(), 99_999);
+    }
+
+    #[test]
+    fn test_drop_deep_tree() {
+        let mut map = BinaryTreeMap::new();
+        for key in 0..1_000_000 {
+            let mut node = Node::leaf::<Unbalanced>(key, key.to_string());
+            node.left = map.root.take();
+            node.refresh::<Unbalanced>();
+            map.root = Some(node);
+        }
+
+        drop(map);
+    }
+
+    #[test]
+    fn test_clone_keeps_shape() {
+        let mut map = BinaryTreeMap::new();
+        for key in 0..50_000 {
+            let mut node = Node::leaf::<Unbalanced>(key, key);
+            node.left = map.root.take();
+            node.refresh::<Unbalanced>();
+            map.root = Some(node);
+        }
+        let mut copy = map.clone();
+        copy.insert(-1, -1);
+        assert_eq!(copy.height(), 50_001);
+        assert_eq!(map.len(), 50_000);
+        assert_eq!(map.get(&-1), Err(MapError::KeyNotFound));
+
+        let mut avl = BinaryTreeMap::with_policy(Avl);
+        for key in 0..100 {
+            avl.insert(key, ());
+        }
+        assert!(avl.clone().iter().eq(avl.iter()));
+        assert_eq!(avl.clone().height(), avl.height());
+    }
+
+    #[test]
+    fn test_try_insert_and_error_display() {
+        let mut map = BinaryTreeMap::new();
+        assert_eq!(map.try_insert(1, "one"), Ok(()));
+        assert_eq!(map.try_insert(1, "uno"), Err(MapError::DuplicateKey));
+        assert_eq!(map.get(&1), Ok(&"one"));
+
+        let error: Box<dyn std::error::Error> = Box::new(map.get(&2).unwrap_err());
+        assert_eq!(error.to_string(), "key not found");
+        assert_eq!(MapError::CapacityExceeded.to_string(), "capacity exceeded");
+    }
+
+    #[test]
+    fn test_option_forms() {
+        let mut map = BinaryTreeMap::new();
+        map.insert("a", 1);
+        assert_eq!(map.get_opt(&"a"), Some(&1));
+        assert_eq!(map.get_opt(&"b"), None);
+        assert_eq!(map.remove(&"a"), Some(1));
+        assert_eq!(map.remove(&"a"), None);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn test_failed_allocation_leaves_tree_intact() {
+        let mut map = BinaryTreeMap::with_policy(Avl);
+        for key in 0..10 {
+            assert_eq!(map.try_insert_alloc(key, key), Ok(()));
+        }
+        assert_eq!(map.try_insert_alloc(3, 30), Ok(()));
+        let before = map.format_tree();
+        let failed = map.insert_with(20, 20, Vec::new(), |_, _, _, _, _| (), |_, _, _| Err(AllocError));
+        assert_eq!(failed, Err(AllocError));
+        assert_eq!(map.format_tree(), before);
+        assert_eq!(map.len(), 10);
+        assert_eq!(map.get(&3), Ok(&30));
+    }
+
+    #[test]
+    fn test_pop_first_and_last() {
+        let mut map = BinaryTreeMap::with_policy(Avl);
+        assert_eq!(map.pop_first(), None);
+        for key in [5, 2, 8, 1, 9, 3] {
+            map.insert(key, key * 10);
+        }
+        assert_eq!(map.first_key_value(), Some((&1, &10)));
+        assert_eq!(map.last_key_value(), Some((&9, &90)));
+        assert_eq!(map.pop_first(), Some((1, 10)));
+        assert_eq!(map.pop_last(), Some((9, 90)));
+        assert_eq!(map.pop_last(), Some((8, 80)));
+        assert_eq!(map.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec![2, 3, 5]);
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_update_in_place() {
+        let mut map = BinaryTreeMap::new();
+        for key in [4, 2, 6] {
+            map.insert(key, vec![key]);
+        }
+        assert!(map.update(&2, |v| v.push(20)));
+        assert!(!map.update(&3, |v| v.push(30)));
+        assert_eq!(map.get(&2), Ok(&vec![2, 20]));
+        assert_eq!(map.len(), 3);
+    }
+
+    #[test]
+    fn test_upsert_builds_frequency_table() {
+        let mut counts = BinaryTreeMap::with_policy(Avl);
+        for word in "the cat and the hat and the bat".split(' ') {
+            counts.upsert(word, 1, |count, one| *count += one);
+        }
+        let table: Vec<_> = counts.iter().map(|(w, c)| (*w, *c)).collect();
+        assert_eq!(table, [("and", 2), ("bat", 1), ("cat", 1), ("hat", 1), ("the", 3)]);
+    }
+
+    #[test]
+    fn test_replace_swaps_key_object() {
+        // Ordered by the name only; the id rides along
+        #[derive(Debug)]
+        struct Tagged(&'static str, u32);
+
+        impl PartialEq for Tagged {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        impl Eq for Tagged {}
+
+        impl PartialOrd for Tagged {
+            fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for Tagged {
+            fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+                self.0.cmp(other.0)
+            }
+        }
+
+        let mut map = BinaryTreeMap::new();
+        assert_eq!(map.replace(Tagged("a", 1), 10), None);
+        let (old, value) = map.replace(Tagged("a", 2), 20).unwrap();
+        assert_eq!((old.1, value), (1, 10));
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.first_key_value().map(|(k, v)| (k.1, *v)), Some((2, 20)));
+    }
+
+    #[test]
+    fn test_quantile_nearest_rank() {
+        let mut latencies = BinaryTreeMap::with_policy(Avl);
+        assert_eq!(latencies.quantile(0.5), None);
+        for ms in 1..=200 {
+            latencies.insert(ms, ());
+        }
+        assert_eq!(latencies.quantile(0.99).map(|(k, _)| *k), Some(198));
+        assert_eq!(latencies.quantile(0.5).map(|(k, _)| *k), Some(100));
+        assert_eq!(latencies.quantile(0.0).map(|(k, _)| *k), Some(1));
+        assert_eq!(latencies.quantile(1.0).map(|(k, _)| *k), Some(200));
+        assert_eq!(latencies.quantile(0.001).map(|(k, _)| *k), Some(1));
+        assert_eq!(latencies.quantile(f64::NAN), None);
+        assert_eq!(latencies.quantile(1.5), None);
+    }
 }
@@ -0,0 +1,282 @@
+use allocator_api2::alloc::Allocator;
+use allocator_api2::vec::Vec;
+
+// Index meaning "no node", in place of `None`
+pub(crate) const NIL: u32 = u32::MAX;
+
+#[derive(Debug)]
+pub(crate) struct LinkedNode<K, V, T> {
+    pub(crate) key: K,
+    pub(crate) value: V,
+    // Parent links cost one index per node and let iteration step to the
+    // successor without keeping a stack
+    pub(crate) parent: u32,
+    pub(crate) left: u32,
+    pub(crate) right: u32,
+    // Whatever else the map keeps per slot
+    pub(crate) tag: T,
+}
+
+#[derive(Debug)]
+pub(crate) enum Slot<K, V, T> {
+    Occupied(LinkedNode<K, V, T>),
+    // Deleted slots form a linked free list reused by later inserts. The
+    // tag stays with the slot and passes to the next node stored in it.
+    Vacant { next_free: u32, tag: T },
+}
+
+// Where the slots of a `LinkedTree` live
+pub(crate) trait SlotStorage {
+    type Key;
+    type Value;
+    type Tag;
+
+    fn slots(&self) -> &[SlotOf<Self>];
+    fn slots_mut(&mut self) -> &mut [SlotOf<Self>];
+}
+
+pub(crate) type SlotOf<S> = Slot<<S as SlotStorage>::Key, <S as SlotStorage>::Value, <S as SlotStorage>::Tag>;
+pub(crate) type NodeOf<S> = LinkedNode<<S as SlotStorage>::Key, <S as SlotStorage>::Value, <S as SlotStorage>::Tag>;
+
+impl<K, V, T, A: Allocator> SlotStorage for Vec<Slot<K, V, T>, A> {
+    type Key = K;
+    type Value = V;
+    type Tag = T;
+
+    fn slots(&self) -> &[Slot<K, V, T>] {
+        self
+    }
+
+    fn slots_mut(&mut self) -> &mut [Slot<K, V, T>] {
+        self
+    }
+}
+
+impl<K, V, T, const N: usize> SlotStorage for [Slot<K, V, T>; N] {
+    type Key = K;
+    type Value = V;
+    type Tag = T;
+
+    fn slots(&self) -> &[Slot<K, V, T>] {
+        self
+    }
+
+    fn slots_mut(&mut self) -> &mut [Slot<K, V, T>] {
+        self
+    }
+}
+
+// Where a node hangs from: the root pointer or a child link of another node
+#[derive(Clone, Copy)]
+pub(crate) enum Edge {
+    Root,
+    Left(u32),
+    Right(u32),
+}
+
+impl Edge {
+    fn owner(self) -> u32 {
+        match self {
+            Edge::Root => NIL,
+            Edge::Left(parent) | Edge::Right(parent) => parent,
+        }
+    }
+}
+
+// Unbalanced BST whose nodes link to each other by `u32` index, shared by
+// `ArenaTreeMap` and `FixedBinaryTreeMap`. They differ in where the slots
+// live and in what happens when the free list runs out, which is left to
+// them.
+#[derive(Debug)]
+pub(crate) struct LinkedTree<S> {
+    pub(crate) slots: S,
+    pub(crate) root: u32,
+    pub(crate) free_head: u32,
+    pub(crate) len: usize,
+}
+
+impl<S: SlotStorage> LinkedTree<S>
+where
+    S::Key: Ord,
+{
+    pub(crate) fn new(slots: S, free_head: u32) -> Self {
+        LinkedTree { slots, root: NIL, free_head, len: 0 }
+    }
+
+    pub(crate) fn node(&self, index: u32) -> &NodeOf<S> {
+        match &self.slots.slots()[index as usize] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant { .. } => unreachable!("link to a vacant slot"),
+        }
+    }
+
+    pub(crate) fn node_mut(&mut self, index: u32) -> &mut NodeOf<S> {
+        match &mut self.slots.slots_mut()[index as usize] {
+            Slot::Occupied(node) => node,
+            Slot::Vacant { .. } => unreachable!("link to a vacant slot"),
+        }
+    }
+
+    pub(crate) fn target(&self, edge: Edge) -> u32 {
+        match edge {
+            Edge::Root => self.root,
+            Edge::Left(parent) => self.node(parent).left,
+            Edge::Right(parent) => self.node(parent).right,
+        }
+    }
+
+    // Links `index` in at `edge`, keeping its parent pointer in sync
+    pub(crate) fn set_target(&mut self, edge: Edge, index: u32) {
+        match edge {
+            Edge::Root => self.root = index,
+            Edge::Left(parent) => self.node_mut(parent).left = index,
+            Edge::Right(parent) => self.node_mut(parent).right = index,
+        }
+        if index != NIL {
+            self.node_mut(index).parent = edge.owner();
+        }
+    }
+
+    // Follows the search path for `key`, returning the edge where it is or
+    // would be attached
+    pub(crate) fn find_edge(&self, key: &S::Key) -> Edge {
+        let mut edge = Edge::Root;
+        loop {
+            let index = self.target(edge);
+            if index == NIL {
+                return edge;
+            }
+            edge = match key.cmp(&self.node(index).key) {
+                core::cmp::Ordering::Equal => return edge,
+                core::cmp::Ordering::Less => Edge::Left(index),
+                core::cmp::Ordering::Greater => Edge::Right(index),
+            };
+        }
+    }
+
+    // The edge a live node hangs from, found through its parent link
+    pub(crate) fn edge_of(&self, index: u32) -> Edge {
+        match self.node(index).parent {
+            NIL => Edge::Root,
+            parent if self.node(parent).left == index => Edge::Left(parent),
+            parent => Edge::Right(parent),
+        }
+    }
+
+    // Stores `node` in the first free slot and returns its index, or hands
+    // the node back when the free list is empty
+    pub(crate) fn take_free(&mut self, node: NodeOf<S>) -> Result<u32, NodeOf<S>> {
+        let index = self.free_head;
+        if index == NIL {
+            return Err(node);
+        }
+        let slot = &mut self.slots.slots_mut()[index as usize];
+        match core::mem::replace(slot, Slot::Occupied(node)) {
+            Slot::Vacant { next_free, tag } => {
+                self.free_head = next_free;
+                self.node_mut(index).tag = tag;
+            },
+            Slot::Occupied(_) => unreachable!("free list points at a live node"),
+        }
+        Ok(index)
+    }
+
+    // Links an allocated node in where `find_edge` left off
+    pub(crate) fn attach(&mut self, edge: Edge, index: u32) {
+        self.set_target(edge, index);
+        self.len += 1;
+    }
+
+    // Removes the node at `index`, which hangs from `edge`, and puts its
+    // slot on the free list under `tag`
+    pub(crate) fn unlink(&mut self, edge: Edge, index: u32, tag: S::Tag) -> NodeOf<S> {
+        let (left, right) = {
+            let node = self.node(index);
+            (node.left, node.right)
+        };
+        let replacement = if left == NIL {
+            right
+        } else if right == NIL {
+            left
+        } else {
+            // Two children: relink the in-order successor into this position
+            // so that no entry moves between slots
+            let mut successor_edge = Edge::Right(index);
+            let mut successor = right;
+            while self.node(successor).left != NIL {
+                successor_edge = Edge::Left(successor);
+                successor = self.node(successor).left;
+            }
+            if successor != right {
+                let successor_right = self.node(successor).right;
+                self.set_target(successor_edge, successor_right);
+                self.set_target(Edge::Right(successor), right);
+            }
+            self.set_target(Edge::Left(successor), left);
+            successor
+        };
+        self.set_target(edge, replacement);
+        self.len -= 1;
+        let vacant = Slot::Vacant { next_free: self.free_head, tag };
+        self.free_head = index;
+        match core::mem::replace(&mut self.slots.slots_mut()[index as usize], vacant) {
+            Slot::Occupied(node) => node,
+            Slot::Vacant { .. } => unreachable!("released a vacant slot"),
+        }
+    }
+
+    fn leftmost(&self, mut index: u32) -> u32 {
+        while index != NIL && self.node(index).left != NIL {
+            index = self.node(index).left;
+        }
+        index
+    }
+
+    // In-order successor found through the right subtree or, failing that,
+    // the first ancestor reached from a left child
+    fn successor(&self, index: u32) -> u32 {
+        let right = self.node(index).right;
+        if right != NIL {
+            return self.leftmost(right);
+        }
+        let mut child = index;
+        let mut parent = self.node(index).parent;
+        while parent != NIL && self.node(parent).right == child {
+            child = parent;
+            parent = self.node(parent).parent;
+        }
+        parent
+    }
+
+    // Iterates in key order using O(1) extra space
+    pub(crate) fn iter(&self) -> Iter<'_, S> {
+        Iter { tree: self, next: self.leftmost(self.root), remaining: self.len }
+    }
+}
+
+pub(crate) struct Iter<'a, S> {
+    tree: &'a LinkedTree<S>,
+    next: u32,
+    remaining: usize,
+}
+
+impl<'a, S: SlotStorage> Iterator for Iter<'a, S>
+where
+    S::Key: Ord,
+{
+    type Item = (&'a S::Key, &'a S::Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == NIL {
+            return None;
+        }
+        let node = self.tree.node(self.next);
+        self.next = self.tree.successor(self.next);
+        self.remaining -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
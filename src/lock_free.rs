@@ -0,0 +1,362 @@
+use crate::MapError;
+use crossbeam_epoch::{self as epoch, Atomic, Owned, Shared};
+use std::cmp::Ordering;
+use std::ops::{Bound, RangeBounds};
+use std::sync::atomic::{self, AtomicUsize};
+use std::sync::{Mutex, PoisonError};
+
+pub use crossbeam_epoch::Guard;
+
+// Nodes are immutable once published. A write copies the path from the root
+// to the changed link, swaps the root pointer, and hands the replaced nodes
+// to the epoch collector, which frees them once no pinned reader can still
+// be looking at them.
+#[derive(Debug)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+    left: Atomic<Node<K, V>>,
+    right: Atomic<Node<K, V>>,
+}
+
+impl<K: Clone, V: Clone> Node<K, V> {
+    fn copy_with<'g>(&self, left: Shared<'g, Self>, right: Shared<'g, Self>, guard: &'g Guard) -> Shared<'g, Self> {
+        let node = Node {
+            key: self.key.clone(),
+            value: self.value.clone(),
+            left: Atomic::from(left),
+            right: Atomic::from(right),
+        };
+        Owned::new(node).into_shared(guard)
+    }
+}
+
+type Path<'g, K, V> = Vec<(Shared<'g, Node<K, V>>, Ordering)>;
+
+// Unbalanced map whose readers never lock: `get` and `range` only pin the
+// current epoch. Writers are serialized by a mutex, so there is a single
+// writer at a time.
+#[derive(Debug)]
+pub struct BinaryTreeMap<K, V> {
+    root: Atomic<Node<K, V>>,
+    len: AtomicUsize,
+    writer: Mutex<()>,
+}
+
+impl<K: Ord, V> Default for BinaryTreeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Frees the nodes of the current version iteratively; replaced nodes were
+// already handed to the collector
+impl<K, V> Drop for BinaryTreeMap<K, V> {
+    fn drop(&mut self) {
+        // SAFETY: `&mut self` means no reader holds a reference into the
+        // current version, and each of its nodes is reachable exactly once
+        unsafe {
+            let guard = epoch::unprotected();
+            let mut pending = vec![self.root.load(atomic::Ordering::Relaxed, guard)];
+            while let Some(link) = pending.pop() {
+                if !link.is_null() {
+                    let node = link.into_owned();
+                    pending.push(node.left.load(atomic::Ordering::Relaxed, guard));
+                    pending.push(node.right.load(atomic::Ordering::Relaxed, guard));
+                }
+            }
+        }
+    }
+}
+
+impl<K: Ord, V> BinaryTreeMap<K, V> {
+    pub fn new() -> Self {
+        BinaryTreeMap {
+            root: Atomic::null(),
+            len: AtomicUsize::new(0),
+            writer: Mutex::new(()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.load(atomic::Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Readers borrow entries for as long as they keep both the guard and the
+    // map borrowed
+    pub fn pin(&self) -> Guard {
+        epoch::pin()
+    }
+
+    pub fn get<'g>(&'g self, key: &K, guard: &'g Guard) -> Result<&'g V, MapError> {
+        let mut current = self.root.load(atomic::Ordering::Acquire, guard);
+        // SAFETY: nodes reachable from a root loaded under `guard` are not
+        // freed before the guard is dropped
+        while let Some(node) = unsafe { current.as_ref() } {
+            current = match key.cmp(&node.key) {
+                Ordering::Equal => return Ok(&node.value),
+                Ordering::Less => node.left.load(atomic::Ordering::Acquire, guard),
+                Ordering::Greater => node.right.load(atomic::Ordering::Acquire, guard),
+            };
+        }
+        Err(MapError::KeyNotFound)
+    }
+
+    // Iterates the version that was current when the call was made, however
+    // many writes land while the iterator is alive
+    pub fn range<'g, R: RangeBounds<K>>(&'g self, range: R, guard: &'g Guard) -> Range<'g, K, V, R> {
+        let mut iter = Range { stack: Vec::new(), range, guard };
+        iter.seek(self.root.load(atomic::Ordering::Acquire, guard));
+        iter
+    }
+}
+
+impl<K, V> BinaryTreeMap<K, V>
+where
+    K: Ord + Clone + Send + 'static,
+    V: Clone + Send + 'static,
+{
+    pub fn insert(&self, key: K, value: V) {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let guard = &epoch::pin();
+        let mut path = Vec::new();
+        let mut retired = Vec::new();
+        let mut current = self.root.load(atomic::Ordering::Acquire, guard);
+        // SAFETY: as in `get`; the writer lock keeps the version stable
+        let subtree = loop {
+            match unsafe { current.as_ref() } {
+                None => {
+                    self.len.fetch_add(1, atomic::Ordering::Relaxed);
+                    let leaf = Node { key, value, left: Atomic::null(), right: Atomic::null() };
+                    break Owned::new(leaf).into_shared(guard);
+                },
+                Some(node) => match key.cmp(&node.key) {
+                    Ordering::Equal => {
+                        // Update existing value in a fresh copy of the node
+                        retired.push(current);
+                        let updated = Node {
+                            key,
+                            value,
+                            left: Atomic::from(node.left.load(atomic::Ordering::Acquire, guard)),
+                            right: Atomic::from(node.right.load(atomic::Ordering::Acquire, guard)),
+                        };
+                        break Owned::new(updated).into_shared(guard);
+                    },
+                    Ordering::Less => {
+                        path.push((current, Ordering::Less));
+                        current = node.left.load(atomic::Ordering::Acquire, guard);
+                    },
+                    Ordering::Greater => {
+                        path.push((current, Ordering::Greater));
+                        current = node.right.load(atomic::Ordering::Acquire, guard);
+                    },
+                },
+            }
+        };
+        self.publish(path, subtree, retired, guard);
+    }
+
+    // Returns a clone: readers may still be looking at the removed entry
+    pub fn delete(&self, key: &K) -> Result<V, MapError> {
+        let _writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let guard = &epoch::pin();
+        let mut path = Vec::new();
+        let mut retired = Vec::new();
+        let mut current = self.root.load(atomic::Ordering::Acquire, guard);
+        // SAFETY: as in `insert`
+        while let Some(node) = unsafe { current.as_ref() } {
+            match key.cmp(&node.key) {
+                Ordering::Less => {
+                    path.push((current, Ordering::Less));
+                    current = node.left.load(atomic::Ordering::Acquire, guard);
+                },
+                Ordering::Greater => {
+                    path.push((current, Ordering::Greater));
+                    current = node.right.load(atomic::Ordering::Acquire, guard);
+                },
+                Ordering::Equal => {
+                    retired.push(current);
+                    let left = node.left.load(atomic::Ordering::Acquire, guard);
+                    let right = node.right.load(atomic::Ordering::Acquire, guard);
+                    let replacement = if left.is_null() {
+                        right
+                    } else if right.is_null() {
+                        left
+                    } else {
+                        // Two children: copy the left spine of the right
+                        // subtree down to the in-order successor, which
+                        // takes the deleted node's place
+                        let mut spine = Vec::new();
+                        let mut successor = right;
+                        loop {
+                            let next = unsafe { successor.deref() }.left.load(atomic::Ordering::Acquire, guard);
+                            if next.is_null() {
+                                break;
+                            }
+                            spine.push((successor, Ordering::Less));
+                            successor = next;
+                        }
+                        retired.push(successor);
+                        let successor = unsafe { successor.deref() };
+                        let rest = successor.right.load(atomic::Ordering::Acquire, guard);
+                        let right = Self::copy_path(spine, rest, &mut retired, guard);
+                        successor.copy_with(left, right, guard)
+                    };
+                    let value = node.value.clone();
+                    self.len.fetch_sub(1, atomic::Ordering::Relaxed);
+                    self.publish(path, replacement, retired, guard);
+                    return Ok(value);
+                },
+            }
+        }
+        Err(MapError::KeyNotFound)
+    }
+
+    // Copies each node on `path` bottom-up with the matching child replaced,
+    // collecting the originals for reclamation
+    fn copy_path<'g>(
+        mut path: Path<'g, K, V>,
+        mut subtree: Shared<'g, Node<K, V>>,
+        retired: &mut Vec<Shared<'g, Node<K, V>>>,
+        guard: &'g Guard,
+    ) -> Shared<'g, Node<K, V>> {
+        while let Some((parent, side)) = path.pop() {
+            retired.push(parent);
+            // SAFETY: path nodes were reached from the root under `guard`
+            let node = unsafe { parent.deref() };
+            subtree = match side {
+                Ordering::Less => node.copy_with(subtree, node.right.load(atomic::Ordering::Acquire, guard), guard),
+                _ => node.copy_with(node.left.load(atomic::Ordering::Acquire, guard), subtree, guard),
+            };
+        }
+        subtree
+    }
+
+    fn publish<'g>(
+        &self,
+        path: Path<'g, K, V>,
+        subtree: Shared<'g, Node<K, V>>,
+        mut retired: Vec<Shared<'g, Node<K, V>>>,
+        guard: &'g Guard,
+    ) {
+        let root = Self::copy_path(path, subtree, &mut retired, guard);
+        self.root.store(root, atomic::Ordering::Release);
+        for node in retired {
+            // SAFETY: the new root no longer reaches `node`, and `K`/`V` are
+            // `Send + 'static` so any thread may free it later
+            unsafe { guard.defer_destroy(node) };
+        }
+    }
+}
+
+pub struct Range<'g, K, V, R> {
+    stack: Vec<&'g Node<K, V>>,
+    range: R,
+    guard: &'g Guard,
+}
+
+impl<'g, K: Ord, V, R: RangeBounds<K>> Range<'g, K, V, R> {
+    // Pushes the path to the smallest key at or above the lower bound
+    fn seek(&mut self, mut current: Shared<'g, Node<K, V>>) {
+        // SAFETY: `current` was loaded under `self.guard`
+        while let Some(node) = unsafe { current.as_ref() } {
+            let above_start = match self.range.start_bound() {
+                Bound::Included(start) => node.key >= *start,
+                Bound::Excluded(start) => node.key > *start,
+                Bound::Unbounded => true,
+            };
+            current = if above_start {
+                self.stack.push(node);
+                node.left.load(atomic::Ordering::Acquire, self.guard)
+            } else {
+                node.right.load(atomic::Ordering::Acquire, self.guard)
+            };
+        }
+    }
+}
+
+impl<'g, K: Ord, V, R: RangeBounds<K>> Iterator for Range<'g, K, V, R> {
+    type Item = (&'g K, &'g V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        if !self.range.contains(&node.key) {
+            self.stack.clear();
+            return None;
+        }
+        self.seek(node.right.load(atomic::Ordering::Acquire, self.guard));
+        Some((&node.key, &node.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    fn scattered(n: u64) -> impl Iterator<Item = u64> {
+        (0..n).map(move |i| i * 7919 % n)
+    }
+
+    #[test]
+    fn test_insert_get_delete() {
+        let map = BinaryTreeMap::new();
+        for key in scattered(100) {
+            map.insert(key, key.to_string());
+        }
+        map.insert(7, "seven".to_string());
+        assert_eq!(map.len(), 100);
+        assert_eq!(map.delete(&50), Ok("50".to_string()));
+        assert_eq!(map.delete(&50), Err(MapError::KeyNotFound));
+        let guard = map.pin();
+        assert_eq!(map.get(&7, &guard).map(String::as_str), Ok("seven"));
+        assert_eq!(map.get(&50, &guard), Err(MapError::KeyNotFound));
+        let keys: Vec<u64> = map.range(45..55, &guard).map(|(k, _)| *k).collect();
+        assert_eq!(keys, vec![45, 46, 47, 48, 49, 51, 52, 53, 54]);
+        assert_eq!(map.range(.., &guard).count(), 99);
+    }
+
+    #[test]
+    fn test_range_reads_a_stable_version() {
+        let map = BinaryTreeMap::new();
+        for key in scattered(10) {
+            map.insert(key, key);
+        }
+        let guard = map.pin();
+        let iter = map.range(.., &guard);
+        for key in 0..10 {
+            map.delete(&key).unwrap();
+        }
+        assert!(map.is_empty());
+        assert_eq!(iter.map(|(k, _)| *k).collect::<Vec<_>>(), (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_readers_alongside_writer() {
+        let map = BinaryTreeMap::new();
+        for key in scattered(1000) {
+            map.insert(key * 2, key);
+        }
+        thread::scope(|s| {
+            s.spawn(|| {
+                for key in scattered(1000) {
+                    map.insert(key * 2 + 1, key);
+                    map.delete(&(key * 2 + 1)).unwrap();
+                }
+            });
+            for _ in 0..3 {
+                s.spawn(|| {
+                    for key in 0..1000 {
+                        let guard = map.pin();
+                        assert_eq!(map.get(&(key * 2), &guard), Ok(&key));
+                    }
+                });
+            }
+        });
+        assert_eq!(map.len(), 1000);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::node::Node;
+use crate::{BalancePolicy, BinaryTreeMap};
+
+// Heap used for node storage. Counts only the nodes themselves, not memory
+// that keys or values own indirectly (such as a `String`'s buffer).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryReport {
+    // Live entries
+    pub nodes: usize,
+    // Bytes occupied by live entries
+    pub node_bytes: usize,
+    // Node slots allocated, including pooled or vacant ones awaiting reuse
+    pub capacity: usize,
+    // Bytes allocated for all node slots
+    pub allocated_bytes: usize,
+}
+
+impl MemoryReport {
+    pub(crate) fn for_slots<T>(nodes: usize, capacity: usize) -> Self {
+        let slot = core::mem::size_of::<T>();
+        MemoryReport {
+            nodes,
+            node_bytes: nodes * slot,
+            capacity,
+            allocated_bytes: capacity * slot,
+        }
+    }
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    pub fn memory_usage(&self) -> MemoryReport {
+        MemoryReport::for_slots::<Node<K, V, P::Meta>>(self.len(), self.len() + self.pooled_nodes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ArenaTreeMap;
+
+    #[test]
+    fn test_memory_usage_counts_pooled_nodes() {
+        let mut map = BinaryTreeMap::new();
+        map.set_node_pool_limit(4);
+        for key in 0..10u64 {
+            map.insert(key, key);
+        }
+        map.delete(&3).unwrap();
+
+        let report = map.memory_usage();
+        let node = std::mem::size_of::<Node<u64, u64, ()>>();
+        assert_eq!(report.nodes, 9);
+        assert_eq!(report.node_bytes, 9 * node);
+        assert_eq!(report.capacity, 10);
+        assert_eq!(report.allocated_bytes, 10 * node);
+    }
+
+    #[test]
+    fn test_arena_memory_usage_reports_capacity() {
+        let mut map = ArenaTreeMap::with_capacity(32);
+        for key in 0..8 {
+            map.insert(key, ());
+        }
+
+        let report = map.memory_usage();
+        assert_eq!(report.nodes, 8);
+        assert!(report.capacity >= 32);
+        assert_eq!(report.allocated_bytes / report.capacity, report.node_bytes / report.nodes);
+    }
+}
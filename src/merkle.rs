@@ -0,0 +1,131 @@
+use crate::{Augment, Augmented, AugmentedTreeMap, BinaryTreeMap, ByteCodec};
+use alloc::vec::Vec;
+use core::ops::RangeBounds;
+
+// FNV-1a with a final avalanche step. It screens for changes; it is not
+// collision-resistant against an adversary.
+struct EntryHasher(u64);
+
+impl EntryHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        // splitmix64's finalizer
+        let mut h = self.0;
+        h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        h ^ (h >> 31)
+    }
+}
+
+// Hashes the entry's `ByteCodec` encoding rather than feeding it through
+// `Hash`, whose output may vary with the platform and the compiler, so
+// replicas on different machines agree
+pub(crate) fn entry_hash<K: ByteCodec, V: ByteCodec>(key: &K, value: &V) -> u64 {
+    let mut bytes = Vec::new();
+    key.encode(&mut bytes);
+    value.encode(&mut bytes);
+    let mut hasher = EntryHasher(0xcbf2_9ce4_8422_2325);
+    hasher.write(&bytes);
+    hasher.finish()
+}
+
+// Each node's summary is the wrapping sum of its subtree's entry hashes.
+// Summing rather than hashing the children's hashes together makes the
+// hash depend only on the entries, not on the tree's shape, so replicas
+// built in different orders or under different policies agree.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MerkleHash;
+
+impl<K: ByteCodec, V: ByteCodec> Augment<K, V> for MerkleHash {
+    type Summary = u64;
+
+    fn summarize(key: &K, value: &V) -> u64 {
+        entry_hash(key, value)
+    }
+
+    fn combine(left: &u64, right: &u64) -> u64 {
+        left.wrapping_add(*right)
+    }
+}
+
+pub type MerkleTreeMap<K, V> = AugmentedTreeMap<K, V, MerkleHash>;
+
+impl<K: Ord + ByteCodec, V: ByteCodec> BinaryTreeMap<K, V, Augmented<MerkleHash>> {
+    // Maps with equal root hashes almost surely hold the same entries;
+    // unequal hashes prove they differ. O(1).
+    pub fn root_hash(&self) -> u64 {
+        self.summary()
+    }
+
+    // Hash of the subtree rooted at `key`'s node, which covers whatever
+    // entries this map's shape puts below it
+    pub fn subtree_hash(&self, key: &K) -> Option<u64> {
+        let mut current = &self.root;
+        while let Some(n) = current {
+            current = match key.cmp(&n.key) {
+                core::cmp::Ordering::Equal => return Some(n.meta),
+                core::cmp::Ordering::Less => &n.left,
+                core::cmp::Ordering::Greater => &n.right,
+            };
+        }
+        None
+    }
+
+    // Hash of the entries in `range` in O(log n). Unlike subtree hashes,
+    // range hashes are comparable between maps of different shapes.
+    pub fn range_hash<R: RangeBounds<K>>(&self, range: R) -> u64 {
+        self.range_fold(range)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_root_hash_ignores_shape() {
+        let mut ascending = MerkleTreeMap::default();
+        let mut scattered = MerkleTreeMap::default();
+        for key in 0..100u32 {
+            ascending.insert(key, key * 2);
+            scattered.insert(key * 37 % 100, key * 37 % 100 * 2);
+        }
+        assert_eq!(ascending.root_hash(), scattered.root_hash());
+        assert_eq!(ascending.range_hash(10..20), scattered.range_hash(10..20));
+
+        scattered.insert(50, 0);
+        assert_ne!(ascending.root_hash(), scattered.root_hash());
+        assert_eq!(ascending.range_hash(..50), scattered.range_hash(..50));
+        assert_ne!(ascending.range_hash(50..=50), scattered.range_hash(50..=50));
+        assert_eq!(MerkleTreeMap::<u32, u32>::default().root_hash(), 0);
+    }
+
+    #[test]
+    fn test_entry_hash_is_fixed() {
+        // Pinned so that a change to the hash, which would split replicas
+        // running different versions, is noticed
+        assert_eq!(entry_hash(&1u32, &"one".to_string()), 0xc490_ddd7_a69b_626e);
+    }
+
+    #[test]
+    fn test_subtree_hash_matches_contents() {
+        let mut map = MerkleTreeMap::default();
+        for key in ["m", "f", "t", "a", "h"] {
+            map.insert(key.to_string(), key.len());
+        }
+        let root = map.root.as_ref().unwrap().key().clone();
+        assert_eq!(map.subtree_hash(&root), Some(map.root_hash()));
+        // A leaf's subtree is the leaf alone
+        let mut leaf = map.root.as_deref().unwrap();
+        while let Some(next) = leaf.left() {
+            leaf = next;
+        }
+        assert_eq!(map.subtree_hash(leaf.key()), Some(entry_hash(leaf.key(), leaf.value())));
+        assert_eq!(map.subtree_hash(&"z".to_string()), None);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::{BalancePolicy, BinaryTreeMap, ByteCodec, MapError};
+use core::cmp::Ordering;
+use core::marker::PhantomData;
+use core::ops::{Bound, RangeBounds};
+use std::fs::File;
+use std::io::{self, Error, ErrorKind, Write};
+use std::os::fd::AsRawFd;
+use std::path::Path;
+
+// File layout, all integers little-endian:
+//
+//     magic "BTMM" | version u8 | 3 zero bytes | count u64
+//     count u64 offsets, each the start of an entry relative to the file
+//     entries in ascending key order, each `ByteCodec` key then value
+//
+// The offsets form an implicit perfectly balanced tree over the sorted
+// entries, searched by bisection, so a lookup decodes O(log n) keys and
+// reads only the pages they sit on.
+const MAGIC: &[u8; 4] = b"BTMM";
+const VERSION: u8 = 1;
+const HEADER: usize = 16;
+
+// Read-only map that queries a file mapped into memory in place. Opening it
+// costs no heap and reads only the header and the offset table, one 8-byte
+// offset per entry, never the entries themselves. Entries are decoded on
+// access and returned by value.
+pub struct MmapTreeMap<K, V> {
+    ptr: *mut libc::c_void,
+    mapped_len: usize,
+    count: usize,
+    entries: PhantomData<fn() -> (K, V)>,
+}
+
+// The mapping is read-only and not tied to the thread that made it
+unsafe impl<K, V> Send for MmapTreeMap<K, V> {}
+unsafe impl<K, V> Sync for MmapTreeMap<K, V> {}
+
+impl<K, V> Drop for MmapTreeMap<K, V> {
+    fn drop(&mut self) {
+        if self.mapped_len > 0 {
+            // SAFETY: `ptr` and `mapped_len` describe the mapping made in `open`
+            unsafe { libc::munmap(self.ptr, self.mapped_len) };
+        }
+    }
+}
+
+fn invalid(message: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+impl<K: Ord + ByteCodec, V: ByteCodec> MmapTreeMap<K, V> {
+    // Writes `map`'s entries in the layout `open` reads
+    pub fn write<P: BalancePolicy<K, V>, Q: AsRef<Path>>(path: Q, map: &BinaryTreeMap<K, V, P>) -> io::Result<()> {
+        let mut data = Vec::new();
+        let mut offsets = Vec::with_capacity(map.len());
+        let start = HEADER + 8 * map.len();
+        for (key, value) in map.iter() {
+            offsets.push((start + data.len()) as u64);
+            key.encode(&mut data);
+            value.encode(&mut data);
+        }
+        let mut out = io::BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        out.write_all(&[VERSION, 0, 0, 0])?;
+        out.write_all(&(map.len() as u64).to_le_bytes())?;
+        for offset in offsets {
+            out.write_all(&offset.to_le_bytes())?;
+        }
+        out.write_all(&data)?;
+        out.flush()
+    }
+
+    /// Checks the header and that the offset table fits and ascends, which
+    /// takes O(n); entries themselves are only validated as they are decoded.
+    ///
+    /// # Safety
+    ///
+    /// The map reads the file through a mapping for as long as it lives. The
+    /// caller must ensure that no one, in this process or another, modifies
+    /// or truncates the file until the map is dropped: changed bytes break
+    /// the immutability of the slices handed to the decoders, and reading
+    /// past a truncated end raises SIGBUS.
+    pub unsafe fn open<Q: AsRef<Path>>(path: Q) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = usize::try_from(file.metadata()?.len()).map_err(|_| invalid("file too large to map"))?;
+        if len < HEADER {
+            return Err(invalid("not a mapped tree file"));
+        }
+        // SAFETY: a fresh private read-only mapping of the whole file; the
+        // descriptor may be closed once it exists
+        let ptr = unsafe { libc::mmap(core::ptr::null_mut(), len, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0) };
+        if ptr == libc::MAP_FAILED {
+            return Err(Error::last_os_error());
+        }
+        let mut map = MmapTreeMap { ptr, mapped_len: len, count: 0, entries: PhantomData };
+        let bytes = map.bytes();
+        if &bytes[..4] != MAGIC {
+            return Err(invalid("not a mapped tree file"));
+        }
+        if bytes[4] != VERSION {
+            return Err(invalid("unsupported format version"));
+        }
+        let count = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        let table_end = usize::try_from(count).ok().and_then(|n| n.checked_mul(8)).and_then(|n| n.checked_add(HEADER));
+        let table_end = table_end.filter(|end| *end <= len).ok_or_else(|| invalid("offset table out of bounds"))?;
+        map.count = count as usize;
+        let mut previous = table_end;
+        for index in 0..map.count {
+            let offset = map.offset(index);
+            if offset < previous || offset >= len {
+                return Err(invalid("offset table out of order"));
+            }
+            previous = offset + 1;
+        }
+        Ok(map)
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: the mapping is `mapped_len` readable bytes and lives as long as self
+        unsafe { core::slice::from_raw_parts(self.ptr as *const u8, self.mapped_len) }
+    }
+
+    fn offset(&self, index: usize) -> usize {
+        let at = HEADER + 8 * index;
+        u64::from_le_bytes(self.bytes()[at..at + 8].try_into().unwrap()) as usize
+    }
+
+    fn key_at(&self, index: usize) -> Result<K, MapError> {
+        K::decode(&mut &self.bytes()[self.offset(index)..])
+    }
+
+    fn entry_at(&self, index: usize) -> Result<(K, V), MapError> {
+        let mut input = &self.bytes()[self.offset(index)..];
+        Ok((K::decode(&mut input)?, V::decode(&mut input)?))
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    // Index of the first entry whose key is not below `key`, and whether
+    // that entry holds `key` itself
+    fn seek(&self, key: &K) -> Result<(usize, bool), MapError> {
+        let (mut low, mut high) = (0, self.count);
+        while low < high {
+            let mid = low + (high - low) / 2;
+            match self.key_at(mid)?.cmp(key) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Equal => return Ok((mid, true)),
+                Ordering::Greater => high = mid,
+            }
+        }
+        Ok((low, false))
+    }
+
+    pub fn get(&self, key: &K) -> Result<V, MapError> {
+        match self.seek(key)? {
+            (index, true) => self.entry_at(index).map(|(_, value)| value),
+            _ => Err(MapError::KeyNotFound),
+        }
+    }
+
+    // Entries come back decoded, so a corrupt one surfaces as an error in
+    // its place instead of ending the iteration early
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = Result<(K, V), MapError>> + '_ {
+        (0..self.count).map(|index| self.entry_at(index))
+    }
+
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> Result<impl ExactSizeIterator<Item = Result<(K, V), MapError>> + '_, MapError> {
+        let start = match range.start_bound() {
+            Bound::Included(key) => self.seek(key)?.0,
+            Bound::Excluded(key) => match self.seek(key)? {
+                (index, true) => index + 1,
+                (index, false) => index,
+            },
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(key) => match self.seek(key)? {
+                (index, true) => index + 1,
+                (index, false) => index,
+            },
+            Bound::Excluded(key) => self.seek(key)?.0,
+            Bound::Unbounded => self.count,
+        };
+        Ok((start..end.max(start)).map(|index| self.entry_at(index)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Avl;
+
+    #[test]
+    fn test_queries_a_written_file() {
+        let path = std::env::temp_dir().join(format!("btm-mmap-{}", std::process::id()));
+        let mut map: BinaryTreeMap<u32, String, Avl> = BinaryTreeMap::default();
+        for key in 0..1000 {
+            map.insert(key * 7 % 1000 * 2, format!("v{key}"));
+        }
+        MmapTreeMap::write(&path, &map).unwrap();
+
+        // SAFETY: the file is private to this test and unchanged while mapped
+        let mapped = unsafe { MmapTreeMap::<u32, String>::open(&path) }.unwrap();
+        assert_eq!(mapped.len(), 1000);
+        assert_eq!(mapped.get(&14), map.get(&14).cloned());
+        assert_eq!(mapped.get(&15), Err(MapError::KeyNotFound));
+        let expected: Vec<_> = map.range(10..=20).map(|(k, v)| (*k, v.clone())).collect();
+        let range: Result<Vec<_>, _> = mapped.range(9..=20).unwrap().collect();
+        assert_eq!(range.unwrap(), expected);
+        assert!(mapped.iter().map(Result::unwrap).eq(map.iter().map(|(k, v)| (*k, v.clone()))));
+        drop(mapped);
+
+        // Offsets running past the end are caught when opening
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[16..24].copy_from_slice(&u64::MAX.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        assert!(unsafe { MmapTreeMap::<u32, String>::open(&path) }.is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
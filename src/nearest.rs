@@ -0,0 +1,209 @@
+use crate::node::{Link, Node};
+use crate::{BalancePolicy, BinaryTreeMap};
+use alloc::vec::Vec;
+
+// Distance between keys, which must grow as keys move apart in key order
+pub trait KeyDistance {
+    type Distance: Ord;
+
+    fn distance(&self, other: &Self) -> Self::Distance;
+}
+
+type DistanceFn<K> = fn(&K, &K) -> <K as KeyDistance>::Distance;
+
+macro_rules! integer_distance {
+    ($($t:ty => $d:ty),*) => {$(
+        impl KeyDistance for $t {
+            type Distance = $d;
+
+            fn distance(&self, other: &Self) -> $d {
+                self.abs_diff(*other)
+            }
+        }
+    )*};
+}
+
+integer_distance!(
+    u8 => u8, u16 => u16, u32 => u32, u64 => u64, u128 => u128, usize => usize,
+    i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize
+);
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    pub fn nearest(&self, key: &K) -> Option<(&K, &V)>
+    where
+        K: KeyDistance,
+    {
+        self.nearest_by(key, K::distance)
+    }
+
+    // The closest entry is the query's floor or its ceiling, both found on
+    // one search path. Ties go to the smaller key.
+    pub fn nearest_by<D: Ord>(&self, key: &K, distance: impl Fn(&K, &K) -> D) -> Option<(&K, &V)> {
+        let (below, above) = self.neighbours(key);
+        let nearest = match (below, above) {
+            (Some(below), Some(above)) if distance(key, &above.key) < distance(key, &below.key) => above,
+            (Some(below), _) => below,
+            (None, above) => above?,
+        };
+        Some((&nearest.key, &nearest.value))
+    }
+
+    pub fn nearest_n<'a>(&'a self, key: &'a K, k: usize) -> NearestN<'a, K, V, P::Meta, DistanceFn<K>>
+    where
+        K: KeyDistance,
+    {
+        self.nearest_n_by(key, k, K::distance)
+    }
+
+    // The `k` closest entries, closest first, with the same tie-breaking as
+    // `nearest_by`. Walks down from the query's floor and up from its
+    // ceiling, each side keeping its own stack, and takes the closer head.
+    pub fn nearest_n_by<'a, D: Ord, F: Fn(&K, &K) -> D>(&'a self, key: &'a K, k: usize, distance: F) -> NearestN<'a, K, V, P::Meta, F> {
+        let mut nearest = NearestN {
+            below: Vec::new(),
+            above: Vec::new(),
+            key,
+            distance,
+            remaining: k.min(self.len()),
+        };
+        let mut current = &self.root;
+        while let Some(n) = current {
+            current = if n.key <= *key {
+                nearest.below.push(n);
+                &n.right
+            } else {
+                nearest.above.push(n);
+                &n.left
+            };
+        }
+        nearest
+    }
+
+    // Greatest entry at or below `key` and least entry above it
+    #[allow(clippy::type_complexity)]
+    fn neighbours(&self, key: &K) -> (Option<&Node<K, V, P::Meta>>, Option<&Node<K, V, P::Meta>>) {
+        let (mut below, mut above) = (None, None);
+        let mut current = &self.root;
+        while let Some(n) = current {
+            match key.cmp(&n.key) {
+                core::cmp::Ordering::Equal => return (Some(n), above),
+                core::cmp::Ordering::Less => {
+                    above = Some(&**n);
+                    current = &n.left;
+                },
+                core::cmp::Ordering::Greater => {
+                    below = Some(&**n);
+                    current = &n.right;
+                },
+            }
+        }
+        (below, above)
+    }
+}
+
+// Iterator returned by `nearest_n`. `below` holds entries at or below the
+// query, largest on top; `above` the ones past it, smallest on top.
+pub struct NearestN<'a, K, V, M, F> {
+    below: Vec<&'a Node<K, V, M>>,
+    above: Vec<&'a Node<K, V, M>>,
+    key: &'a K,
+    distance: F,
+    remaining: usize,
+}
+
+impl<'a, K, V, M, F> NearestN<'a, K, V, M, F> {
+    fn push_right(stack: &mut Vec<&'a Node<K, V, M>>, mut link: &'a Link<K, V, M>) {
+        while let Some(n) = link {
+            stack.push(n);
+            link = &n.right;
+        }
+    }
+
+    fn push_left(stack: &mut Vec<&'a Node<K, V, M>>, mut link: &'a Link<K, V, M>) {
+        while let Some(n) = link {
+            stack.push(n);
+            link = &n.left;
+        }
+    }
+}
+
+impl<'a, K, V, M, D: Ord, F: Fn(&K, &K) -> D> Iterator for NearestN<'a, K, V, M, F> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let take_above = match (self.below.last(), self.above.last()) {
+            (Some(below), Some(above)) => (self.distance)(self.key, &above.key) < (self.distance)(self.key, &below.key),
+            (below, _) => below.is_none(),
+        };
+        let n = if take_above {
+            let n = self.above.pop()?;
+            Self::push_left(&mut self.above, &n.right);
+            n
+        } else {
+            let n = self.below.pop()?;
+            Self::push_right(&mut self.below, &n.left);
+            n
+        };
+        self.remaining -= 1;
+        Some((&n.key, &n.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V, M, D: Ord, F: Fn(&K, &K) -> D> ExactSizeIterator for NearestN<'_, K, V, M, F> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nearest_snaps_to_closest_sample() {
+        let mut samples = BinaryTreeMap::new();
+        assert_eq!(samples.nearest(&5), None);
+        for t in [100u64, 200, 300] {
+            samples.insert(t, t / 100);
+        }
+        assert_eq!(samples.nearest(&0), Some((&100, &1)));
+        assert_eq!(samples.nearest(&149), Some((&100, &1)));
+        assert_eq!(samples.nearest(&151), Some((&200, &2)));
+        // Ties go to the smaller key
+        assert_eq!(samples.nearest(&250), Some((&200, &2)));
+        assert_eq!(samples.nearest(&300), Some((&300, &3)));
+        assert_eq!(samples.nearest(&u64::MAX), Some((&300, &3)));
+    }
+
+    #[test]
+    fn test_nearest_by_custom_distance() {
+        let mut map = BinaryTreeMap::new();
+        for key in [-10i32, 0, 7] {
+            map.insert(key, ());
+        }
+        // Distances above count double, so the floor wins more often
+        let skewed = |query: &i32, key: &i32| if key > query { 2 * (key - query) } else { query - key };
+        assert_eq!(map.nearest_by(&4, skewed), Some((&0, &())));
+        assert_eq!(map.nearest(&4), Some((&7, &())));
+        assert_eq!(map.nearest_by(&-8, skewed), Some((&-10, &())));
+    }
+
+    #[test]
+    fn test_nearest_n_in_distance_order() {
+        let mut map = BinaryTreeMap::with_policy(crate::Avl);
+        for key in (0..100u32).map(|i| i * 10) {
+            map.insert(key, ());
+        }
+        let keys = |key, k| map.nearest_n(key, k).map(|(k, _)| *k).collect::<Vec<_>>();
+        assert_eq!(keys(&503, 5), [500, 510, 490, 520, 480]);
+        // Ties go to the smaller key, and an exact match comes first
+        assert_eq!(keys(&500, 3), [500, 490, 510]);
+        assert_eq!(keys(&5, 3), [0, 10, 20]);
+        assert_eq!(keys(&2000, 2), [990, 980]);
+        assert_eq!(map.nearest_n(&0, 1000).len(), 100);
+        assert!(keys(&0, 0).is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+use crate::balance::BalancePolicy;
+use crate::stats;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+pub(crate) type Link<K, V, M> = Option<Box<Node<K, V, M>>>;
+
+// Nodes detached on the way down a search, each with the side the search
+// continued on, so the tree can be stitched back together bottom-up
+pub(crate) type Path<K, V, M> = Vec<(Box<Node<K, V, M>>, core::cmp::Ordering)>;
+
+// Frees a subtree without recursing: left children are rotated up onto
+// the right spine until there are none, so each node is freed on its own
+pub(crate) fn drop_link<K, V, M>(mut current: Link<K, V, M>) {
+    while let Some(mut node) = current {
+        match node.left.take() {
+            Some(mut left) => {
+                node.left = left.right.take();
+                left.right = Some(node);
+                current = Some(left);
+            },
+            None => current = node.right.take(),
+        }
+    }
+}
+
+// A tree node. Fields stay crate-private so the ordering and size invariants
+// can only be changed through the rotation helpers below.
+#[derive(Debug)]
+pub struct Node<K, V, M = ()> {
+    pub(crate) key: K,
+    pub(crate) value: V,
+    // Number of nodes in the subtree rooted here, including this one
+    pub(crate) size: usize,
+    // Per-node data owned by the tree's balance policy
+    pub(crate) meta: M,
+    pub(crate) left: Link<K, V, M>,
+    pub(crate) right: Link<K, V, M>,
+}
+
+impl<K, V, M: Default> Node<K, V, M> {
+    pub(crate) fn leaf<P: BalancePolicy<K, V, Meta = M>>(key: K, value: V) -> Box<Self> {
+        stats::count_allocation();
+        let mut node = Box::new(Node {
+            key,
+            value,
+            size: 1,
+            meta: M::default(),
+            left: None,
+            right: None,
+        });
+        node.refresh::<P>();
+        node
+    }
+}
+
+impl<K, V, M> Node<K, V, M> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn meta(&self) -> &M {
+        &self.meta
+    }
+
+    pub fn meta_mut(&mut self) -> &mut M {
+        &mut self.meta
+    }
+
+    pub fn left(&self) -> Option<&Self> {
+        self.left.as_deref()
+    }
+
+    pub fn right(&self) -> Option<&Self> {
+        self.right.as_deref()
+    }
+
+    pub(crate) fn size_of(node: &Link<K, V, M>) -> usize {
+        node.as_ref().map_or(0, |n| n.size)
+    }
+
+    // Recomputes size and policy metadata from the (already correct) children
+    pub(crate) fn refresh<P: BalancePolicy<K, V, Meta = M>>(&mut self) {
+        self.size = 1 + Self::size_of(&self.left) + Self::size_of(&self.right);
+        P::update(self);
+    }
+
+    pub fn rotate_left<P: BalancePolicy<K, V, Meta = M>>(mut node: Box<Self>) -> Box<Self> {
+        stats::count_rotation();
+        P::push_down(&mut node);
+        let mut pivot = node.right.take().expect("rotate_left requires a right child");
+        P::push_down(&mut pivot);
+        node.right = pivot.left.take();
+        node.refresh::<P>();
+        pivot.left = Some(node);
+        pivot.refresh::<P>();
+        pivot
+    }
+
+    pub fn rotate_right<P: BalancePolicy<K, V, Meta = M>>(mut node: Box<Self>) -> Box<Self> {
+        stats::count_rotation();
+        P::push_down(&mut node);
+        let mut pivot = node.left.take().expect("rotate_right requires a left child");
+        P::push_down(&mut pivot);
+        node.left = pivot.right.take();
+        node.refresh::<P>();
+        pivot.right = Some(node);
+        pivot.refresh::<P>();
+        pivot
+    }
+
+    // Replaces the left child with `f(child)`, e.g. to rotate it as the first
+    // half of a double rotation
+    pub fn map_left<P, F>(&mut self, f: F)
+    where
+        P: BalancePolicy<K, V, Meta = M>,
+        F: FnOnce(Box<Self>) -> Box<Self>,
+    {
+        self.left = self.left.take().map(f);
+        self.refresh::<P>();
+    }
+
+    pub fn map_right<P, F>(&mut self, f: F)
+    where
+        P: BalancePolicy<K, V, Meta = M>,
+        F: FnOnce(Box<Self>) -> Box<Self>,
+    {
+        self.right = self.right.take().map(f);
+        self.refresh::<P>();
+    }
+
+    pub(crate) fn nth(node: &Link<K, V, M>, mut index: usize) -> Option<(&K, &V)> {
+        let mut current = node;
+        while let Some(n) = current {
+            let left_size = Self::size_of(&n.left);
+            match index.cmp(&left_size) {
+                core::cmp::Ordering::Less => current = &n.left,
+                core::cmp::Ordering::Equal => return Some((&n.key, &n.value)),
+                core::cmp::Ordering::Greater => {
+                    index -= left_size + 1;
+                    current = &n.right;
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<K: Ord, V, M> Node<K, V, M> {
+    pub(crate) fn rank(node: &Link<K, V, M>, key: &K) -> usize {
+        let mut rank = 0;
+        let mut current = node;
+        while let Some(n) = current {
+            match key.cmp(&n.key) {
+                core::cmp::Ordering::Less => current = &n.left,
+                core::cmp::Ordering::Equal => return rank + Self::size_of(&n.left),
+                core::cmp::Ordering::Greater => {
+                    rank += Self::size_of(&n.left) + 1;
+                    current = &n.right;
+                }
+            }
+        }
+        rank
+    }
+}
@@ -0,0 +1,155 @@
+use crate::node::Node;
+use crate::{BalancePolicy, BinaryTreeMap};
+use alloc::boxed::Box;
+
+// Callbacks fired as entries of the map are inserted, changed or removed,
+// including by `delete_many` and what is built on it. Every method
+// defaults to doing nothing, so an observer only implements the events it
+// cares about. Operations that build a whole new tree (joins, set
+// operations, sorted builds) are not reported.
+pub trait MapObserver<K, V>: Send + Sync {
+    fn on_insert(&mut self, _key: &K, _value: &V) {}
+
+    // An insert replaced the value of a key already present
+    fn on_update(&mut self, _key: &K, _old: &V, _new: &V) {}
+
+    // `update` or `upsert` changed a value in place, so only the result is
+    // left to report
+    fn on_modify(&mut self, _key: &K, _value: &V) {}
+
+    fn on_remove(&mut self, _key: &K, _value: &V) {}
+
+    // The balance policy rotated a subtree, bringing `new_root` up to its
+    // root. A double rotation is reported once, for the grandchild that
+    // ends up on top.
+    fn on_rotate(&mut self, _new_root: &K) {}
+}
+
+// The map's registered observer, if any
+pub(crate) struct Observer<K, V>(Option<Box<dyn MapObserver<K, V>>>);
+
+impl<K, V> Default for Observer<K, V> {
+    fn default() -> Self {
+        Observer(None)
+    }
+}
+
+impl<K, V> core::fmt::Debug for Observer<K, V> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_tuple("Observer").field(&self.0.is_some()).finish()
+    }
+}
+
+impl<K, V> Observer<K, V> {
+    pub(crate) fn notify(&mut self, event: impl FnOnce(&mut dyn MapObserver<K, V>)) {
+        if let Some(observer) = self.0.as_deref_mut() {
+            event(observer);
+        }
+    }
+
+    // Wraps a policy hook so that a change of subtree root, which only a
+    // rotation can cause, is reported
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn watch<'a, M, F>(&'a mut self, fix: F) -> impl FnMut(Box<Node<K, V, M>>) -> Box<Node<K, V, M>> + 'a
+    where
+        F: Fn(Box<Node<K, V, M>>) -> Box<Node<K, V, M>> + 'a,
+    {
+        move |node| {
+            let Some(observer) = self.0.as_deref_mut() else {
+                return fix(node);
+            };
+            let before: *const Node<K, V, M> = &*node;
+            let node = fix(node);
+            if !core::ptr::eq(before, &*node) {
+                observer.on_rotate(&node.key);
+            }
+            node
+        }
+    }
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Replaces any observer already registered
+    pub fn set_observer(&mut self, observer: impl MapObserver<K, V> + 'static) {
+        self.observer.0 = Some(Box::new(observer));
+    }
+
+    pub fn take_observer(&mut self) -> Option<Box<dyn MapObserver<K, V>>> {
+        self.observer.0.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Avl;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl MapObserver<i32, &'static str> for Recorder {
+        fn on_insert(&mut self, key: &i32, value: &&'static str) {
+            self.0.lock().unwrap().push(format!("insert {key} {value}"));
+        }
+
+        fn on_update(&mut self, key: &i32, old: &&'static str, new: &&'static str) {
+            self.0.lock().unwrap().push(format!("update {key} {old}->{new}"));
+        }
+
+        fn on_modify(&mut self, key: &i32, value: &&'static str) {
+            self.0.lock().unwrap().push(format!("modify {key} {value}"));
+        }
+
+        fn on_remove(&mut self, key: &i32, value: &&'static str) {
+            self.0.lock().unwrap().push(format!("remove {key} {value}"));
+        }
+
+        fn on_rotate(&mut self, new_root: &i32) {
+            self.0.lock().unwrap().push(format!("rotate {new_root}"));
+        }
+    }
+
+    #[test]
+    fn test_reports_changes_in_order() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut map = BinaryTreeMap::with_policy(Avl);
+        map.set_observer(Recorder(Arc::clone(&events)));
+        map.insert(1, "a");
+        map.insert(2, "b");
+        map.insert(3, "c");
+        map.insert(2, "B");
+        map.delete(&1).unwrap();
+        assert!(map.delete(&1).is_err());
+        assert_eq!(
+            *events.lock().unwrap(),
+            ["insert 1 a", "insert 2 b", "insert 3 c", "rotate 2", "update 2 b->B", "remove 1 a"]
+        );
+    }
+
+    #[test]
+    fn test_reports_in_place_changes_and_bulk_removals() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut map = BinaryTreeMap::new();
+        map.insert(1, "a");
+        map.insert(2, "b");
+        map.insert(3, "c");
+        map.set_observer(Recorder(Arc::clone(&events)));
+        assert!(map.update(&2, |value| *value = "B"));
+        map.upsert(3, "C", |old, new| *old = new);
+        map.upsert(4, "d", |old, new| *old = new);
+        map.delete_many([3, 1, 9]);
+        assert_eq!(*events.lock().unwrap(), ["modify 2 B", "modify 3 C", "insert 4 d", "remove 1 a", "remove 3 C"]);
+    }
+
+    #[test]
+    fn test_take_observer_stops_events() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let mut map = BinaryTreeMap::new();
+        map.set_observer(Recorder(Arc::clone(&events)));
+        map.insert(1, "a");
+        assert!(map.take_observer().is_some());
+        map.insert(2, "b");
+        assert_eq!(events.lock().unwrap().len(), 1);
+    }
+}
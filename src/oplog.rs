@@ -0,0 +1,238 @@
+use crate::{BalancePolicy, BinaryTreeMap, ByteCodec, LeanTerm, MapError, Unbalanced};
+use core::fmt::Write;
+use core::marker::PhantomData;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// Tags in front of each recorded operation
+const INSERT: u8 = 1;
+const DELETE: u8 = 2;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Op<K, V> {
+    Insert(K, V),
+    Delete(K),
+}
+
+// Trace of inserts and deletes, kept in the `ByteCodec` encoding as they
+// happen so the log can be saved or attached to a bug report as-is, then
+// decoded and replayed onto a fresh map. Deletes of missing keys are
+// recorded too; replaying reproduces every call, not just the net effect.
+#[derive(Debug, Clone)]
+pub struct OpLog<K, V> {
+    bytes: Vec<u8>,
+    len: usize,
+    entries: PhantomData<fn() -> (K, V)>,
+}
+
+impl<K: ByteCodec, V: ByteCodec> Default for OpLog<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: ByteCodec, V: ByteCodec> OpLog<K, V> {
+    pub fn new() -> Self {
+        OpLog { bytes: Vec::new(), len: 0, entries: PhantomData }
+    }
+
+    // Checks that `bytes` decodes into a whole number of operations
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, MapError> {
+        let mut input = bytes.as_slice();
+        let mut len = 0;
+        while !input.is_empty() {
+            decode_op::<K, V>(&mut input)?;
+            len += 1;
+        }
+        Ok(OpLog { bytes, len, entries: PhantomData })
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    // Number of operations recorded
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn record_insert(&mut self, key: &K, value: &V) {
+        self.bytes.push(INSERT);
+        key.encode(&mut self.bytes);
+        value.encode(&mut self.bytes);
+        self.len += 1;
+    }
+
+    pub fn record_delete(&mut self, key: &K) {
+        self.bytes.push(DELETE);
+        key.encode(&mut self.bytes);
+        self.len += 1;
+    }
+
+    pub fn ops(&self) -> impl Iterator<Item = Op<K, V>> + '_ {
+        let mut input = self.bytes.as_slice();
+        (0..self.len).map(move |_| decode_op(&mut input).expect("log was validated when built"))
+    }
+
+    pub fn replay<P: BalancePolicy<K, V> + Default>(&self) -> BinaryTreeMap<K, V, P>
+    where
+        K: Ord,
+    {
+        let mut map = BinaryTreeMap::default();
+        for op in self.ops() {
+            match op {
+                Op::Insert(key, value) => map.insert(key, value),
+                Op::Delete(key) => {
+                    let _ = map.delete(&key);
+                },
+            }
+        }
+        map
+    }
+
+    // The trace as a chain of `let`s over the operations in
+    // BinaryTreeMap.lean, ending in the final map
+    //
+    //     let m := (BinaryTreeMap.empty : BinaryTreeMap Nat String)
+    //     let m := m.insert 1 "a"
+    //     let m := (m.delete 1).1
+    //     m
+    pub fn to_lean(&self) -> String
+    where
+        K: LeanTerm,
+        V: LeanTerm,
+    {
+        let mut out = String::new();
+        writeln!(out, "let m := (BinaryTreeMap.empty : BinaryTreeMap {} {})", K::LEAN_TYPE, V::LEAN_TYPE).unwrap();
+        for op in self.ops() {
+            match op {
+                Op::Insert(key, value) => {
+                    out.push_str("let m := m.insert ");
+                    key.write_lean(&mut out);
+                    out.push(' ');
+                    value.write_lean(&mut out);
+                },
+                Op::Delete(key) => {
+                    out.push_str("let m := (m.delete ");
+                    key.write_lean(&mut out);
+                    out.push_str(").1");
+                },
+            }
+            out.push('\n');
+        }
+        out.push_str("m\n");
+        out
+    }
+}
+
+pub(crate) fn decode_op<K: ByteCodec, V: ByteCodec>(input: &mut &[u8]) -> Result<Op<K, V>, MapError> {
+    let (&tag, rest) = input.split_first().ok_or(MapError::InvalidEncoding)?;
+    *input = rest;
+    match tag {
+        INSERT => Ok(Op::Insert(K::decode(input)?, V::decode(input)?)),
+        DELETE => Ok(Op::Delete(K::decode(input)?)),
+        _ => Err(MapError::InvalidEncoding),
+    }
+}
+
+// A map that appends every insert and delete to an `OpLog`
+pub struct LoggedMap<K, V, P: BalancePolicy<K, V> = Unbalanced> {
+    map: BinaryTreeMap<K, V, P>,
+    log: OpLog<K, V>,
+}
+
+impl<K: Ord + ByteCodec, V: ByteCodec, P: BalancePolicy<K, V> + Default> Default for LoggedMap<K, V, P> {
+    fn default() -> Self {
+        LoggedMap { map: BinaryTreeMap::default(), log: OpLog::new() }
+    }
+}
+
+impl<K: Ord + ByteCodec, V: ByteCodec> LoggedMap<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<K: Ord + ByteCodec, V: ByteCodec, P: BalancePolicy<K, V>> LoggedMap<K, V, P> {
+    pub fn insert(&mut self, key: K, value: V) {
+        self.log.record_insert(&key, &value);
+        self.map.insert(key, value);
+    }
+
+    pub fn delete(&mut self, key: &K) -> Result<V, MapError> {
+        self.log.record_delete(key);
+        self.map.delete(key)
+    }
+
+    pub fn get(&self, key: &K) -> Result<&V, MapError> {
+        self.map.get(key)
+    }
+
+    pub fn map(&self) -> &BinaryTreeMap<K, V, P> {
+        &self.map
+    }
+
+    pub fn log(&self) -> &OpLog<K, V> {
+        &self.log
+    }
+
+    pub fn into_parts(self) -> (BinaryTreeMap<K, V, P>, OpLog<K, V>) {
+        (self.map, self.log)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Avl;
+
+    #[test]
+    fn test_replay_reproduces_the_map() {
+        let mut logged = LoggedMap::new();
+        for key in [5u32, 2, 8, 1, 9, 3] {
+            logged.insert(key, key.to_string());
+        }
+        logged.delete(&5).unwrap();
+        assert_eq!(logged.delete(&7), Err(MapError::KeyNotFound));
+        logged.insert(2, "two".to_string());
+
+        let (map, log) = logged.into_parts();
+        assert_eq!(log.len(), 9);
+        let replayed: BinaryTreeMap<_, _> = log.replay();
+        assert_eq!(replayed.format_tree(), map.format_tree());
+        let balanced: BinaryTreeMap<_, _, Avl> = log.replay();
+        assert!(balanced.iter().eq(map.iter()));
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        let mut log = OpLog::<i32, bool>::new();
+        log.record_insert(&-4, &true);
+        log.record_delete(&-4);
+        let copy = OpLog::<i32, bool>::from_bytes(log.as_bytes().to_vec()).unwrap();
+        assert_eq!(copy.ops().collect::<Vec<_>>(), vec![Op::Insert(-4, true), Op::Delete(-4)]);
+
+        let mut truncated = log.as_bytes().to_vec();
+        truncated.pop();
+        assert_eq!(OpLog::<i32, bool>::from_bytes(truncated).unwrap_err(), MapError::InvalidEncoding);
+        assert_eq!(OpLog::<i32, bool>::from_bytes(vec![9]).unwrap_err(), MapError::InvalidEncoding);
+    }
+
+    #[test]
+    fn test_lean_trace() {
+        let mut log = OpLog::<u8, String>::new();
+        log.record_insert(&1, &"a".to_string());
+        log.record_delete(&1);
+        assert_eq!(
+            log.to_lean(),
+            "let m := (BinaryTreeMap.empty : BinaryTreeMap Nat String)\n\
+             let m := m.insert 1 \"a\"\n\
+             let m := (m.delete 1).1\n\
+             m\n"
+        );
+    }
+}
@@ -0,0 +1,335 @@
+use crate::builder::dedup_keep_last;
+use crate::node::{Link, Node};
+use crate::{BalancePolicy, BinaryTreeMap, ValueIndependent};
+use alloc::vec;
+use rayon::iter::plumbing::{bridge_unindexed, Folder, UnindexedConsumer, UnindexedProducer};
+use rayon::prelude::*;
+
+// Below this many entries a subtree is built on the current thread
+const SEQUENTIAL_CUTOFF: usize = 4096;
+
+impl<K, V, P> BinaryTreeMap<K, V, P>
+where
+    K: Ord + Send,
+    V: Send,
+    P: BalancePolicy<K, V> + Default,
+    P::Meta: Send,
+{
+    // Parallel counterpart of `from_sorted_vec`: the two halves of every
+    // large subtree are built on separate rayon tasks
+    pub fn par_from_sorted(entries: Vec<(K, V)>) -> Self {
+        let mut slots: Vec<Option<(K, V)>> = entries.into_iter().map(Some).collect();
+        BinaryTreeMap::from_root(Self::build_parallel(&mut slots))
+    }
+
+    fn build_parallel(slots: &mut [Option<(K, V)>]) -> Link<K, V, P::Meta> {
+        if slots.len() <= SEQUENTIAL_CUTOFF {
+            let count = slots.len();
+            return Self::build_balanced(&mut slots.iter_mut().map(|slot| slot.take().unwrap()), count);
+        }
+        let (left_slots, rest) = slots.split_at_mut(slots.len() / 2);
+        let (mid_slot, right_slots) = rest.split_first_mut().unwrap();
+        let (key, value) = mid_slot.take().unwrap();
+        let (left, right) = rayon::join(|| Self::build_parallel(left_slots), || Self::build_parallel(right_slots));
+
+        let mut node = Node::leaf::<P>(key, value);
+        node.left = left;
+        node.right = right;
+        node.refresh::<P>();
+        Some(node)
+    }
+}
+
+impl<K, V, P> BinaryTreeMap<K, V, P>
+where
+    K: Send + Sync,
+    V: Send,
+    P: BalancePolicy<K, V>,
+    P::Meta: Send,
+{
+    // Applies `f` to every value in parallel, then recomputes every node's
+    // metadata bottom-up, so summaries that depend on values stay right.
+    // This works for every policy; `par_iter_mut` skips the second pass and
+    // so needs a `ValueIndependent` one.
+    pub fn par_update_values<F: Fn(&K, &mut V) + Sync + Send>(&mut self, f: F) {
+        ParIterMut { root: self.root.as_deref_mut() }.for_each(|(key, value)| f(key, value));
+        refresh_parallel::<K, V, P>(&mut self.root);
+    }
+}
+
+fn size_of<K, V, M>(link: &Link<K, V, M>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+// Refreshes every node below `link`, children first. Large subtrees whose
+// halves are both sizeable refresh them on separate tasks, which bounds
+// the recursion by the logarithm of the size; anything lopsided, such as a
+// degenerate spine, is done on this thread without recursing.
+fn refresh_parallel<K: Send, V: Send, P: BalancePolicy<K, V>>(link: &mut Link<K, V, P::Meta>)
+where
+    P::Meta: Send,
+{
+    let Some(node) = link else {
+        return;
+    };
+    let smaller = size_of(&node.left).min(size_of(&node.right));
+    if node.size <= SEQUENTIAL_CUTOFF || smaller * 4 < node.size {
+        return refresh_sequential::<K, V, P>(link);
+    }
+    let (left, right) = (&mut node.left, &mut node.right);
+    rayon::join(|| refresh_parallel::<K, V, P>(left), || refresh_parallel::<K, V, P>(right));
+    node.refresh::<P>();
+}
+
+// Takes the subtree apart in preorder, then rebuilds it from the back, by
+// which point each node's children are already refreshed
+fn refresh_sequential<K, V, P: BalancePolicy<K, V>>(link: &mut Link<K, V, P::Meta>) {
+    let mut order = Vec::new();
+    let mut pending = vec![link.take()];
+    while let Some(current) = pending.pop() {
+        if let Some(mut node) = current {
+            let (left, right) = (node.left.take(), node.right.take());
+            order.push((node, left.is_some(), right.is_some()));
+            pending.push(right);
+            pending.push(left);
+        }
+    }
+    let mut done = Vec::new();
+    while let Some((mut node, has_left, has_right)) = order.pop() {
+        if has_left {
+            node.left = done.pop();
+        }
+        if has_right {
+            node.right = done.pop();
+        }
+        node.refresh::<P>();
+        done.push(node);
+    }
+    *link = done.pop();
+}
+
+// Collects, sorts and deduplicates (last entry wins) in parallel, then builds
+// a balanced tree with `par_from_sorted`
+impl<K, V, P> FromParallelIterator<(K, V)> for BinaryTreeMap<K, V, P>
+where
+    K: Ord + Send,
+    V: Send,
+    P: BalancePolicy<K, V> + Default,
+    P::Meta: Send,
+{
+    fn from_par_iter<I: IntoParallelIterator<Item = (K, V)>>(par_iter: I) -> Self {
+        let mut entries: Vec<(K, V)> = par_iter.into_par_iter().collect();
+        entries.par_sort_by(|a, b| a.0.cmp(&b.0));
+        dedup_keep_last(&mut entries);
+        Self::par_from_sorted(entries)
+    }
+}
+
+// Something that can be cut into its left subtree, root entry and right
+// subtree: a shared or mutable borrow of a node
+trait Subtree: Sized + Send {
+    type Item: Send;
+
+    fn expand(self) -> (Option<Self>, Self::Item, Option<Self>);
+}
+
+impl<'a, K: Sync, V: Sync, M: Sync> Subtree for &'a Node<K, V, M> {
+    type Item = (&'a K, &'a V);
+
+    fn expand(self) -> (Option<Self>, Self::Item, Option<Self>) {
+        (self.left.as_deref(), (&self.key, &self.value), self.right.as_deref())
+    }
+}
+
+impl<'a, K: Send + Sync, V: Send, M: Send> Subtree for &'a mut Node<K, V, M> {
+    type Item = (&'a K, &'a mut V);
+
+    fn expand(self) -> (Option<Self>, Self::Item, Option<Self>) {
+        let Node { key, value, left, right, .. } = self;
+        (left.as_deref_mut(), (&*key, value), right.as_deref_mut())
+    }
+}
+
+enum Piece<T: Subtree> {
+    Tree(T),
+    Entry(T::Item),
+}
+
+// An in-order run of subtrees and single entries. Splitting first cuts a
+// lone subtree at its root, then hands half of the run to another task.
+struct Pieces<T: Subtree>(Vec<Piece<T>>);
+
+impl<T: Subtree> UnindexedProducer for Pieces<T> {
+    type Item = T::Item;
+
+    fn split(mut self) -> (Self, Option<Self>) {
+        if let [Piece::Tree(_)] = self.0.as_slice() {
+            if let Some(Piece::Tree(tree)) = self.0.pop() {
+                let (left, entry, right) = tree.expand();
+                self.0.extend(left.map(Piece::Tree));
+                self.0.push(Piece::Entry(entry));
+                self.0.extend(right.map(Piece::Tree));
+            }
+        }
+        if self.0.len() < 2 {
+            return (self, None);
+        }
+        let rest = self.0.split_off(self.0.len() / 2);
+        (self, Some(Pieces(rest)))
+    }
+
+    fn fold_with<F: Folder<Self::Item>>(self, mut folder: F) -> F {
+        let mut stack = self.0;
+        stack.reverse();
+        while let Some(piece) = stack.pop() {
+            if folder.full() {
+                break;
+            }
+            match piece {
+                Piece::Entry(item) => folder = folder.consume(item),
+                Piece::Tree(tree) => {
+                    let (left, entry, right) = tree.expand();
+                    stack.extend(right.map(Piece::Tree));
+                    stack.push(Piece::Entry(entry));
+                    stack.extend(left.map(Piece::Tree));
+                },
+            }
+        }
+        folder
+    }
+}
+
+// Parallel iterator over `(&K, &V)` in key order
+pub struct ParIter<'a, K, V, M = ()> {
+    root: Option<&'a Node<K, V, M>>,
+}
+
+impl<'a, K: Sync, V: Sync, M: Sync> ParallelIterator for ParIter<'a, K, V, M> {
+    type Item = (&'a K, &'a V);
+
+    fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+        bridge_unindexed(Pieces(self.root.map(Piece::Tree).into_iter().collect()), consumer)
+    }
+}
+
+// Parallel iterator over `(&K, &mut V)` in key order. Only maps whose
+// policy is `ValueIndependent` hand one out, since nothing refreshes the
+// metadata after the values change.
+pub struct ParIterMut<'a, K, V, M = ()> {
+    root: Option<&'a mut Node<K, V, M>>,
+}
+
+impl<'a, K: Send + Sync, V: Send, M: Send> ParallelIterator for ParIterMut<'a, K, V, M> {
+    type Item = (&'a K, &'a mut V);
+
+    fn drive_unindexed<C: UnindexedConsumer<Self::Item>>(self, consumer: C) -> C::Result {
+        bridge_unindexed(Pieces(self.root.map(Piece::Tree).into_iter().collect()), consumer)
+    }
+}
+
+impl<'a, K: Sync, V: Sync, P: BalancePolicy<K, V>> IntoParallelIterator for &'a BinaryTreeMap<K, V, P>
+where
+    P::Meta: Sync,
+{
+    type Iter = ParIter<'a, K, V, P::Meta>;
+    type Item = (&'a K, &'a V);
+
+    fn into_par_iter(self) -> Self::Iter {
+        ParIter { root: self.root.as_deref() }
+    }
+}
+
+impl<'a, K: Send + Sync, V: Send, P: ValueIndependent<K, V>> IntoParallelIterator for &'a mut BinaryTreeMap<K, V, P>
+where
+    P::Meta: Send,
+{
+    type Iter = ParIterMut<'a, K, V, P::Meta>;
+    type Item = (&'a K, &'a mut V);
+
+    fn into_par_iter(self) -> Self::Iter {
+        ParIterMut { root: self.root.as_deref_mut() }
+    }
+}
+
+// Owned entries are moved out in order first, which also keeps the
+// iterator indexed
+impl<K: Ord + Send, V: Send, P: BalancePolicy<K, V>> IntoParallelIterator for BinaryTreeMap<K, V, P> {
+    type Iter = rayon::vec::IntoIter<(K, V)>;
+    type Item = (K, V);
+
+    fn into_par_iter(self) -> Self::Iter {
+        self.into_sorted_vec().into_par_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Avl;
+
+    #[test]
+    fn test_par_from_sorted_matches_sequential_shape() {
+        let entries: Vec<_> = (0..100_000).map(|key| (key, key * 2)).collect();
+        let map: BinaryTreeMap<_, _> = BinaryTreeMap::par_from_sorted(entries);
+
+        assert_eq!(map.len(), 100_000);
+        assert_eq!(map.height(), 17);
+        assert_eq!(map.get(&77_777), Ok(&155_554));
+        assert_eq!(map.nth(50_000), Some((&50_000, &100_000)));
+    }
+
+    #[test]
+    fn test_from_par_iter_sorts_and_dedups() {
+        let map: BinaryTreeMap<_, _, Avl> = (0..50_000u32)
+            .into_par_iter()
+            .map(|n| ((n * 7919) % 10_000, n))
+            .collect();
+
+        assert_eq!(map.len(), 10_000);
+        assert!(map.is_balanced());
+        // 7919 is coprime to 10_000, so key 0 comes from n = 0, 10_000, ...
+        assert_eq!(map.get(&0), Ok(&40_000));
+    }
+
+    #[test]
+    fn test_par_iter_visits_entries_in_order() {
+        let map: BinaryTreeMap<_, _> = BinaryTreeMap::par_from_sorted((0..20_000u64).map(|k| (k, k * 3)).collect());
+
+        let entries: Vec<(u64, u64)> = map.par_iter().map(|(k, v)| (*k, *v)).collect();
+        assert_eq!(entries, (0..20_000).map(|k| (k, k * 3)).collect::<Vec<_>>());
+        assert_eq!(map.par_iter().map(|(_, v)| v).sum::<u64>(), 3 * (0..20_000).sum::<u64>());
+        assert_eq!(map.par_iter().find_any(|(k, _)| **k == 12_345), Some((&12_345, &37_035)));
+    }
+
+    #[test]
+    fn test_par_iter_mut_and_into_par_iter() {
+        let mut map = BinaryTreeMap::new();
+        for key in (0..1000u32).map(|i| i * 7919 % 1000) {
+            map.insert(key, key);
+        }
+        map.par_iter_mut().for_each(|(k, v)| *v += *k);
+        assert_eq!(map.get(&500), Ok(&1000));
+
+        let entries: Vec<(u32, u32)> = map.into_par_iter().collect();
+        assert_eq!(entries, (0..1000).map(|k| (k, k * 2)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_par_update_values_refreshes_summaries() {
+        let mut map = crate::MerkleTreeMap::default();
+        let mut expected = crate::MerkleTreeMap::default();
+        for key in 0..50_000u32 {
+            map.insert(key, key);
+            expected.insert(key, key + 1);
+        }
+        map.par_update_values(|_, value| *value += 1);
+        assert_eq!(map.root_hash(), expected.root_hash());
+        assert!(map.check_invariants().is_ok());
+
+        // A spine takes the sequential path
+        let mut spine = crate::right_spine(100_000);
+        spine.par_update_values(|key, value| *value = key * 2);
+        assert_eq!(spine.get(&99_999), Ok(&199_998));
+        assert!(spine.check_invariants().is_ok());
+    }
+}
@@ -0,0 +1,337 @@
+use crate::MapError;
+use core::cmp::Ordering;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use alloc::vec;
+
+pub(crate) type Link<K, V> = Option<Arc<Node<K, V>>>;
+
+#[derive(Debug)]
+pub(crate) struct Node<K, V> {
+    key: K,
+    value: V,
+    size: usize,
+    // Longest path down from here, counting this node
+    height: usize,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+fn size_of<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |n| n.size)
+}
+
+fn height_of<K, V>(link: &Link<K, V>) -> usize {
+    link.as_ref().map_or(0, |n| n.height)
+}
+
+pub(crate) fn node<K, V>(key: K, value: V, left: Link<K, V>, right: Link<K, V>) -> Link<K, V> {
+    let size = 1 + size_of(&left) + size_of(&right);
+    let height = 1 + height_of(&left).max(height_of(&right));
+    Some(Arc::new(Node { key, value, size, height, left, right }))
+}
+
+// Like `node`, but first rotates when one side is more than one level
+// taller than the other. The rotated nodes are shared, so their keys and
+// values are copied into new nodes and the originals left alone.
+fn balanced<K: Clone, V: Clone>(key: K, value: V, left: Link<K, V>, right: Link<K, V>) -> Link<K, V> {
+    let (hl, hr) = (height_of(&left), height_of(&right));
+    if hl > hr + 1 {
+        let l = left.as_deref().expect("taller side is not empty");
+        if height_of(&l.left) >= height_of(&l.right) {
+            let right = node(key, value, l.right.clone(), right);
+            node(l.key.clone(), l.value.clone(), l.left.clone(), right)
+        } else {
+            let lr = l.right.as_deref().expect("taller grandchild is not empty");
+            let left = node(l.key.clone(), l.value.clone(), l.left.clone(), lr.left.clone());
+            node(lr.key.clone(), lr.value.clone(), left, node(key, value, lr.right.clone(), right))
+        }
+    } else if hr > hl + 1 {
+        let r = right.as_deref().expect("taller side is not empty");
+        if height_of(&r.right) >= height_of(&r.left) {
+            let left = node(key, value, left, r.left.clone());
+            node(r.key.clone(), r.value.clone(), left, r.right.clone())
+        } else {
+            let rl = r.left.as_deref().expect("taller grandchild is not empty");
+            let right = node(r.key.clone(), r.value.clone(), rl.right.clone(), r.right.clone());
+            node(rl.key.clone(), rl.value.clone(), node(key, value, left, rl.left.clone()), right)
+        }
+    } else {
+        node(key, value, left, right)
+    }
+}
+
+// Balanced subtree of the next `count` entries, which must be ascending
+pub(crate) fn from_sorted<K, V>(entries: &mut impl Iterator<Item = (K, V)>, count: usize) -> Link<K, V> {
+    if count == 0 {
+        return None;
+    }
+    let left = from_sorted(entries, count / 2);
+    let (key, value) = entries.next().expect("count matches the entries");
+    let right = from_sorted(entries, count - count / 2 - 1);
+    node(key, value, left, right)
+}
+
+// AVL-balanced map whose updates never modify existing nodes. `insert` and
+// `delete` copy the O(log n) nodes on the path from the root to the change,
+// plus any they rotate, and share every other subtree with the map they
+// started from, so old versions stay valid and cloning a map is a single
+// reference-count bump.
+#[derive(Debug)]
+pub struct BinaryTreeMap<K, V> {
+    root: Link<K, V>,
+}
+
+impl<K, V> Clone for BinaryTreeMap<K, V> {
+    fn clone(&self) -> Self {
+        BinaryTreeMap { root: self.root.clone() }
+    }
+}
+
+impl<K: Ord, V> Default for BinaryTreeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Frees the nodes no other version shares without recursing: a node whose
+// last reference is ours gets unwrapped and its children queued, anything
+// still shared just loses a count
+impl<K, V> Drop for BinaryTreeMap<K, V> {
+    fn drop(&mut self) {
+        let mut pending = vec![self.root.take()];
+        while let Some(link) = pending.pop() {
+            if let Some(Ok(node)) = link.map(Arc::try_unwrap) {
+                pending.push(node.left);
+                pending.push(node.right);
+            }
+        }
+    }
+}
+
+impl<K: Ord, V> BinaryTreeMap<K, V> {
+    pub fn new() -> Self {
+        BinaryTreeMap { root: None }
+    }
+
+    pub(crate) fn from_root(root: Link<K, V>) -> Self {
+        BinaryTreeMap { root }
+    }
+
+    pub fn len(&self) -> usize {
+        size_of(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn get(&self, key: &K) -> Result<&V, MapError> {
+        let mut current = &self.root;
+        while let Some(n) = current {
+            current = match key.cmp(&n.key) {
+                Ordering::Equal => return Ok(&n.value),
+                Ordering::Less => &n.left,
+                Ordering::Greater => &n.right,
+            };
+        }
+        Err(MapError::KeyNotFound)
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter { stack: Vec::new(), remaining: self.len() };
+        iter.push_left(&self.root);
+        iter
+    }
+
+    // True when both maps are the same version, or share their whole tree
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.root, &other.root) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> BinaryTreeMap<K, V> {
+    pub fn insert(&self, key: K, value: V) -> Self {
+        let mut path = Vec::new();
+        let mut current = &self.root;
+        let subtree = loop {
+            match current {
+                None => break node(key, value, None, None),
+                Some(n) => match key.cmp(&n.key) {
+                    // Update existing value in a copy of the node
+                    Ordering::Equal => break node(key, value, n.left.clone(), n.right.clone()),
+                    Ordering::Less => {
+                        path.push((&**n, Ordering::Less));
+                        current = &n.left;
+                    },
+                    Ordering::Greater => {
+                        path.push((&**n, Ordering::Greater));
+                        current = &n.right;
+                    },
+                },
+            }
+        };
+        BinaryTreeMap { root: Self::copy_path(path, subtree) }
+    }
+
+    pub fn delete(&self, key: &K) -> Result<Self, MapError> {
+        let mut path = Vec::new();
+        let mut current = &self.root;
+        while let Some(n) = current {
+            match key.cmp(&n.key) {
+                Ordering::Less => {
+                    path.push((&**n, Ordering::Less));
+                    current = &n.left;
+                },
+                Ordering::Greater => {
+                    path.push((&**n, Ordering::Greater));
+                    current = &n.right;
+                },
+                Ordering::Equal => {
+                    let replacement = match (&n.left, &n.right) {
+                        (None, right) => right.clone(),
+                        (left, None) => left.clone(),
+                        // Two children: the in-order successor takes the
+                        // node's place, copying the spine leading to it
+                        (left, Some(right)) => {
+                            let mut spine = Vec::new();
+                            let mut successor = &**right;
+                            while let Some(next) = &successor.left {
+                                spine.push((successor, Ordering::Less));
+                                successor = next;
+                            }
+                            let rest = Self::copy_path(spine, successor.right.clone());
+                            balanced(successor.key.clone(), successor.value.clone(), left.clone(), rest)
+                        },
+                    };
+                    return Ok(BinaryTreeMap { root: Self::copy_path(path, replacement) });
+                },
+            }
+        }
+        Err(MapError::KeyNotFound)
+    }
+
+    // Rebuilds the nodes on `path` bottom-up around the new `subtree`,
+    // sharing their other child and rebalancing each as it goes
+    fn copy_path(mut path: Vec<(&Node<K, V>, Ordering)>, mut subtree: Link<K, V>) -> Link<K, V> {
+        while let Some((parent, side)) = path.pop() {
+            let (left, right) = match side {
+                Ordering::Less => (subtree, parent.right.clone()),
+                _ => (parent.left.clone(), subtree),
+            };
+            subtree = balanced(parent.key.clone(), parent.value.clone(), left, right);
+        }
+        subtree
+    }
+}
+
+// In-order iterator keeping the unvisited ancestors on a stack
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iter<'a, K, V> {
+    fn push_left(&mut self, mut link: &'a Link<K, V>) {
+        while let Some(n) = link {
+            self.stack.push(n);
+            link = &n.left;
+        }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.stack.pop()?;
+        self.push_left(&n.right);
+        self.remaining -= 1;
+        Some((&n.key, &n.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_updates_leave_old_versions_intact() {
+        let empty = BinaryTreeMap::new();
+        let one = empty.insert(5, "five");
+        let two = one.insert(3, "three");
+        let updated = two.insert(5, "FIVE");
+
+        assert!(empty.is_empty());
+        assert_eq!(one.len(), 1);
+        assert_eq!(one.get(&3), Err(MapError::KeyNotFound));
+        assert_eq!(two.get(&5), Ok(&"five"));
+        assert_eq!(updated.get(&5), Ok(&"FIVE"));
+        assert_eq!(updated.get(&3), Ok(&"three"));
+        assert_eq!(updated.iter().collect::<Vec<_>>(), vec![(&3, &"three"), (&5, &"FIVE")]);
+        assert_eq!(one.iter().len(), 1);
+    }
+
+    #[test]
+    fn test_delete_shares_untouched_subtrees() {
+        let mut map = BinaryTreeMap::new();
+        for key in [50, 25, 75, 10, 30, 60, 90, 55, 65] {
+            map = map.insert(key, key * 10);
+        }
+        let without = map.delete(&50).unwrap();
+        assert_eq!(without.len(), 8);
+        assert_eq!(without.get(&50), Err(MapError::KeyNotFound));
+        assert_eq!(map.get(&50), Ok(&500));
+        // Successor 55 moved up; the left subtree is the same allocation
+        let root = without.root.as_ref().unwrap();
+        assert_eq!(root.key, 55);
+        let old_left = map.root.as_ref().unwrap().left.as_ref().unwrap();
+        assert!(Arc::ptr_eq(root.left.as_ref().unwrap(), old_left));
+
+        assert_eq!(without.delete(&50).unwrap_err(), MapError::KeyNotFound);
+        assert!(map.clone().ptr_eq(&map));
+        assert!(!map.ptr_eq(&without));
+    }
+
+    #[test]
+    fn test_ascending_inserts_stay_balanced() {
+        let mut map = BinaryTreeMap::new();
+        for key in 0..10_000 {
+            map = map.insert(key, key);
+        }
+        let old = map.clone();
+        for key in (0..10_000).step_by(3) {
+            map = map.delete(&key).unwrap();
+        }
+        // AVL trees of n nodes are at most 1.44 log2 n tall
+        assert!(old.root.as_ref().unwrap().height <= 19);
+        assert!(map.root.as_ref().unwrap().height <= 19);
+        assert_eq!(map.len(), 6_666);
+        assert_eq!(old.get(&3), Ok(&3));
+        assert!(map.iter().map(|(k, _)| *k).eq((0..10_000).filter(|k| k % 3 != 0)));
+    }
+
+    #[test]
+    fn test_drop_deep_versions() {
+        // Build a 1M-deep spine directly; inserting it would copy every path
+        let mut root = None;
+        for key in 0..1_000_000 {
+            root = node(key, (), root, None);
+        }
+        let map = BinaryTreeMap { root };
+        let versions: Vec<_> = (0..10).map(|key| map.insert(1_000_000 + key, ())).collect();
+        assert_eq!(versions[9].len(), 1_000_001);
+        drop(map);
+        drop(versions);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::node::Node;
+use crate::{stats, BalancePolicy};
+use allocator_api2::alloc::AllocError;
+use core::alloc::Layout;
+use core::mem::MaybeUninit;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+// Allocations of deleted nodes, kept for reuse by later inserts so that
+// churn-heavy workloads stop round-tripping through the global allocator.
+// Holds at most `limit` spare nodes; the default limit of 0 disables pooling.
+pub(crate) struct NodePool<K, V, M> {
+    free: Vec<Box<MaybeUninit<Node<K, V, M>>>>,
+    limit: usize,
+}
+
+impl<K, V, M: Default> NodePool<K, V, M> {
+    pub(crate) fn new() -> Self {
+        NodePool { free: Vec::new(), limit: 0 }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.free.len()
+    }
+
+    pub(crate) fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.free.truncate(limit);
+    }
+
+    // A fresh leaf, reusing a pooled allocation when one is available
+    pub(crate) fn leaf<P: BalancePolicy<K, V, Meta = M>>(&mut self, key: K, value: V) -> Box<Node<K, V, M>> {
+        match self.free.pop() {
+            Some(slot) => Self::fill::<P>(slot, key, value),
+            None => Node::leaf::<P>(key, value),
+        }
+    }
+
+    // Like `leaf`, but a failed allocation is an error instead of an abort
+    pub(crate) fn try_leaf<P: BalancePolicy<K, V, Meta = M>>(
+        &mut self,
+        key: K,
+        value: V,
+    ) -> Result<Box<Node<K, V, M>>, AllocError> {
+        let slot = match self.free.pop() {
+            Some(slot) => slot,
+            None => {
+                let layout = Layout::new::<Node<K, V, M>>();
+                // SAFETY: a node always holds its `size` field, so the layout
+                // is never zero-sized, and a non-null result is a fresh
+                // allocation of that layout, which is what `Box` expects
+                let raw = unsafe { alloc::alloc::alloc(layout) };
+                if raw.is_null() {
+                    return Err(AllocError);
+                }
+                stats::count_allocation();
+                unsafe { Box::from_raw(raw.cast::<MaybeUninit<Node<K, V, M>>>()) }
+            },
+        };
+        Ok(Self::fill::<P>(slot, key, value))
+    }
+
+    fn fill<P: BalancePolicy<K, V, Meta = M>>(slot: Box<MaybeUninit<Node<K, V, M>>>, key: K, value: V) -> Box<Node<K, V, M>> {
+        let mut node = Box::write(slot, Node {
+            key,
+            value,
+            size: 1,
+            meta: M::default(),
+            left: None,
+            right: None,
+        });
+        node.refresh::<P>();
+        node
+    }
+
+    // Moves the node out of its box, keeping the allocation if there is room
+    pub(crate) fn recycle(&mut self, node: Box<Node<K, V, M>>) -> Node<K, V, M> {
+        if self.free.len() >= self.limit {
+            return *node;
+        }
+        let raw = Box::into_raw(node);
+        // SAFETY: `raw` came from `Box::into_raw`, so it is valid for reads
+        // and the value is read exactly once. Re-boxing it as `MaybeUninit`
+        // keeps the allocation without dropping the moved-out value again.
+        unsafe {
+            let value = raw.read();
+            self.free.push(Box::from_raw(raw.cast::<MaybeUninit<Node<K, V, M>>>()));
+            value
+        }
+    }
+}
+
+impl<K, V, M> core::fmt::Debug for NodePool<K, V, M> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("NodePool").field("free", &self.free.len()).field("limit", &self.limit).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::BinaryTreeMap;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_pool_is_off_by_default() {
+        let mut map = BinaryTreeMap::new();
+        map.insert(1, ());
+        map.delete(&1).unwrap();
+        assert_eq!(map.pooled_nodes(), 0);
+    }
+
+    #[test]
+    fn test_deleted_nodes_are_reused() {
+        let mut map = BinaryTreeMap::new();
+        map.set_node_pool_limit(2);
+        for key in 0..5 {
+            map.insert(key, key.to_string());
+        }
+        for key in 0..4 {
+            map.delete(&key).unwrap();
+        }
+        assert_eq!(map.pooled_nodes(), 2);
+
+        map.insert(10, "ten".to_string());
+        assert_eq!(map.pooled_nodes(), 1);
+        assert_eq!(map.get(&10), Ok(&"ten".to_string()));
+        assert_eq!(map.len(), 2);
+
+        map.set_node_pool_limit(0);
+        assert_eq!(map.pooled_nodes(), 0);
+    }
+
+    #[test]
+    fn test_pooled_nodes_do_not_keep_values_alive() {
+        let value = Rc::new(());
+        let mut map = BinaryTreeMap::new();
+        map.set_node_pool_limit(8);
+        map.insert(1, Rc::clone(&value));
+        map.insert(2, Rc::clone(&value));
+
+        drop(map.delete(&1));
+        assert_eq!(Rc::strong_count(&value), 2);
+        drop(map);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+}
@@ -0,0 +1,92 @@
+use crate::iter::Range;
+use crate::{BalancePolicy, BinaryTreeMap};
+use core::ops::Bound;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+type Bounds<K> = (Bound<K>, Bound<K>);
+
+// Keys that can be matched by prefix. The keys starting with a prefix are
+// exactly those from the prefix itself up to, but excluding, the prefix
+// with its last element incremented, so a prefix query is a key range.
+pub trait PrefixKey: Ord + Sized {
+    type Prefix: ?Sized;
+
+    fn prefix_bounds(prefix: &Self::Prefix) -> Bounds<Self>;
+}
+
+impl PrefixKey for String {
+    type Prefix = str;
+
+    fn prefix_bounds(prefix: &str) -> Bounds<String> {
+        // UTF-8 orders strings by code point, so the next code point gives
+        // the upper bound; `char::MAX` has none and moves on to the one
+        // before it
+        let mut end = String::from(prefix);
+        while let Some(last) = end.pop() {
+            let next = match last {
+                '\u{D7FF}' => Some('\u{E000}'),
+                _ => char::from_u32(last as u32 + 1),
+            };
+            if let Some(next) = next {
+                end.push(next);
+                return (Bound::Included(String::from(prefix)), Bound::Excluded(end));
+            }
+        }
+        (Bound::Included(String::from(prefix)), Bound::Unbounded)
+    }
+}
+
+impl PrefixKey for Vec<u8> {
+    type Prefix = [u8];
+
+    fn prefix_bounds(prefix: &[u8]) -> Bounds<Vec<u8>> {
+        let mut end = prefix.to_vec();
+        while let Some(last) = end.pop() {
+            if last < u8::MAX {
+                end.push(last + 1);
+                return (Bound::Included(prefix.to_vec()), Bound::Excluded(end));
+            }
+        }
+        (Bound::Included(prefix.to_vec()), Bound::Unbounded)
+    }
+}
+
+impl<K: PrefixKey, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Entries whose keys start with `prefix`, in key order
+    pub fn prefix_range(&self, prefix: &K::Prefix) -> Range<'_, K, V, Bounds<K>, P::Meta> {
+        self.range(K::prefix_bounds(prefix))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_string_prefix() {
+        let mut map = BinaryTreeMap::new();
+        for word in ["car", "cart", "carbon", "cat", "ca", "dog", "c", "car\u{10FFFF}"] {
+            map.insert(String::from(word), ());
+        }
+        let keys = |prefix| map.prefix_range(prefix).map(|(k, _)| k.as_str()).collect::<Vec<_>>();
+        assert_eq!(keys("car"), ["car", "carbon", "cart", "car\u{10FFFF}"]);
+        assert_eq!(keys("ca"), ["ca", "car", "carbon", "cart", "car\u{10FFFF}", "cat"]);
+        assert_eq!(keys("car\u{10FFFF}"), ["car\u{10FFFF}"]);
+        assert_eq!(keys("x"), Vec::<&str>::new());
+        assert_eq!(keys("").len(), 8);
+    }
+
+    #[test]
+    fn test_byte_prefix_wraps_max_bytes() {
+        let mut map = BinaryTreeMap::new();
+        for key in [vec![1, 255], vec![1, 255, 0], vec![2], vec![1, 254, 9], vec![255, 255, 1]] {
+            map.insert(key, ());
+        }
+        let keys = |prefix: &[u8]| map.prefix_range(prefix).map(|(k, _)| k.clone()).collect::<Vec<_>>();
+        assert_eq!(keys(&[1, 255]), [vec![1, 255], vec![1, 255, 0]]);
+        assert_eq!(keys(&[1]).len(), 3);
+        assert_eq!(keys(&[255, 255]), [vec![255, 255, 1]]);
+    }
+}
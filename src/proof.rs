@@ -0,0 +1,88 @@
+use crate::{BalancePolicy, BinaryTreeMap, MapError};
+use core::cmp::Ordering;
+use alloc::vec::Vec;
+
+// The keys a successful search compared against, root first, each with the
+// outcome of comparing the searched key to it: `Less` means the search went
+// left, `Greater` right, and the final step is `Equal`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchProof<'a, K> {
+    pub path: Vec<(&'a K, Ordering)>,
+}
+
+impl<K: Ord> SearchProof<'_, K> {
+    // Re-checks the certificate without the tree: every recorded comparison
+    // must be what `key` actually compares to, the path must end on `key`,
+    // and each key must lie strictly inside the bounds the turns above it
+    // set, as the ordering invariant requires
+    pub fn verify(&self, key: &K) -> bool {
+        let mut lower: Option<&K> = None;
+        let mut upper: Option<&K> = None;
+        let Some((last, steps)) = self.path.split_last() else {
+            return false;
+        };
+        for &(node_key, turn) in &self.path {
+            if lower.is_some_and(|lower| node_key <= lower) || upper.is_some_and(|upper| node_key >= upper) {
+                return false;
+            }
+            if key.cmp(node_key) != turn {
+                return false;
+            }
+            match turn {
+                Ordering::Less => upper = Some(node_key),
+                Ordering::Greater => lower = Some(node_key),
+                Ordering::Equal => {},
+            }
+        }
+        steps.iter().all(|&(_, turn)| turn != Ordering::Equal) && last.1 == Ordering::Equal
+    }
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    pub fn get_with_proof(&self, key: &K) -> Result<(&V, SearchProof<'_, K>), MapError> {
+        let mut path = Vec::new();
+        let mut current = self.root.as_deref();
+        while let Some(node) = current {
+            let turn = key.cmp(&node.key);
+            path.push((&node.key, turn));
+            current = match turn {
+                Ordering::Equal => return Ok((&node.value, SearchProof { path })),
+                Ordering::Less => node.left(),
+                Ordering::Greater => node.right(),
+            };
+        }
+        Err(MapError::KeyNotFound)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proof_records_the_search() {
+        let mut map = BinaryTreeMap::new();
+        for key in [50, 30, 70, 40, 35] {
+            map.insert(key, key * 2);
+        }
+        let (value, proof) = map.get_with_proof(&35).unwrap();
+        assert_eq!(*value, 70);
+        assert_eq!(
+            proof.path,
+            vec![(&50, Ordering::Less), (&30, Ordering::Greater), (&40, Ordering::Less), (&35, Ordering::Equal)]
+        );
+        assert!(proof.verify(&35));
+        assert!(!proof.verify(&40));
+        assert_eq!(map.get_with_proof(&36).unwrap_err(), MapError::KeyNotFound);
+    }
+
+    #[test]
+    fn test_forged_proofs_fail() {
+        // Consistent comparisons, but 60 cannot sit left of 50
+        let forged = SearchProof { path: vec![(&50, Ordering::Less), (&60, Ordering::Greater), (&55, Ordering::Equal)] };
+        assert!(!forged.verify(&55));
+        let unfinished = SearchProof { path: vec![(&50, Ordering::Less)] };
+        assert!(!unfinished.verify(&10));
+        assert!(!SearchProof::<u8> { path: Vec::new() }.verify(&1));
+    }
+}
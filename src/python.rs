@@ -0,0 +1,147 @@
+// Python bindings. The class is `binary_tree_map.BinaryTreeMap`, behaving
+// like an ordered dict: keys are `int` (64-bit) or `str`, with all ints
+// sorting before all strings; values are any Python object. Build the
+// extension with maturin and `--features python,pyo3/extension-module`.
+use crate::{Avl, BinaryTreeMap};
+use pyo3::exceptions::{PyKeyError, PyTypeError};
+use pyo3::prelude::*;
+use pyo3::types::PyString;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum PyKey {
+    Int(i64),
+    Str(String),
+}
+
+impl PyKey {
+    fn extract(key: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if let Ok(s) = key.downcast::<PyString>() {
+            return Ok(PyKey::Str(s.to_str()?.to_owned()));
+        }
+        key.extract().map(PyKey::Int).map_err(|_| PyTypeError::new_err("keys must be int or str"))
+    }
+
+    fn to_object(&self, py: Python<'_>) -> PyResult<Py<PyAny>> {
+        Ok(match self {
+            PyKey::Int(n) => n.into_pyobject(py)?.into_any().unbind(),
+            PyKey::Str(s) => s.into_pyobject(py)?.into_any().unbind(),
+        })
+    }
+}
+
+#[pyclass(name = "BinaryTreeMap", module = "binary_tree_map")]
+#[derive(Debug, Default)]
+pub struct PyBinaryTreeMap {
+    map: BinaryTreeMap<PyKey, Py<PyAny>, Avl>,
+}
+
+#[pymethods]
+impl PyBinaryTreeMap {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn __len__(&self) -> usize {
+        self.map.len()
+    }
+
+    fn __contains__(&self, key: &Bound<'_, PyAny>) -> PyResult<bool> {
+        Ok(self.map.get(&PyKey::extract(key)?).is_ok())
+    }
+
+    fn __getitem__(&self, key: &Bound<'_, PyAny>) -> PyResult<Py<PyAny>> {
+        match self.map.get(&PyKey::extract(key)?) {
+            Ok(value) => Ok(value.clone_ref(key.py())),
+            Err(_) => Err(PyKeyError::new_err(key.clone().unbind())),
+        }
+    }
+
+    fn __setitem__(&mut self, key: &Bound<'_, PyAny>, value: Py<PyAny>) -> PyResult<()> {
+        self.map.insert(PyKey::extract(key)?, value);
+        Ok(())
+    }
+
+    fn __delitem__(&mut self, key: &Bound<'_, PyAny>) -> PyResult<()> {
+        self.map
+            .delete(&PyKey::extract(key)?)
+            .map(drop)
+            .map_err(|_| PyKeyError::new_err(key.clone().unbind()))
+    }
+
+    // Iterates over the keys in order, like a dict. The keys are copied up
+    // front, so changing the map while iterating is safe.
+    fn __iter__(&self) -> KeyIterator {
+        KeyIterator { keys: self.map.iter().map(|(key, _)| key.clone()).collect::<Vec<_>>().into_iter() }
+    }
+
+    fn items(&self, py: Python<'_>) -> PyResult<Vec<(Py<PyAny>, Py<PyAny>)>> {
+        self.map.iter().map(|(key, value)| Ok((key.to_object(py)?, value.clone_ref(py)))).collect()
+    }
+}
+
+#[pyclass]
+pub struct KeyIterator {
+    keys: std::vec::IntoIter<PyKey>,
+}
+
+#[pymethods]
+impl KeyIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<Py<PyAny>>> {
+        self.keys.next().map(|key| key.to_object(py)).transpose()
+    }
+}
+
+#[pymodule]
+fn binary_tree_map(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyBinaryTreeMap>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::PyDict;
+
+    #[test]
+    fn test_dict_protocol_from_python() {
+        Python::initialize();
+        Python::attach(|py| {
+            let locals = PyDict::new(py);
+            locals.set_item("BinaryTreeMap", py.get_type::<PyBinaryTreeMap>()).unwrap();
+            let script = c"
+m = BinaryTreeMap()
+m['b'] = 2
+m[3] = 'three'
+m[-1] = None
+m['a'] = [1]
+del m[3]
+try:
+    del m[3]
+    missing = False
+except KeyError:
+    missing = True
+keys = list(m)
+items = m.items()
+size = len(m)
+has_a = 'a' in m
+try:
+    m[1.5] = 0
+    bad_key = False
+except TypeError:
+    bad_key = True
+";
+            py.run(script, None, Some(&locals)).unwrap();
+            let get = |name: &str| locals.get_item(name).unwrap().unwrap();
+            assert_eq!(get("keys").repr().unwrap().to_str().unwrap(), "[-1, 'a', 'b']");
+            assert_eq!(get("items").repr().unwrap().to_str().unwrap(), "[(-1, None), ('a', [1]), ('b', 2)]");
+            assert_eq!(get("size").extract::<usize>().unwrap(), 3);
+            assert!(get("missing").extract::<bool>().unwrap());
+            assert!(get("has_a").extract::<bool>().unwrap());
+            assert!(get("bad_key").extract::<bool>().unwrap());
+        });
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{Avl, BalancePolicy, BinaryTreeMap};
+
+// Priority-queue views over a map: the key is the priority, so an entry can
+// still be looked up, updated or deleted by key through `map_mut`. Pushing
+// a priority already present replaces its value. AVL by default, since
+// schedulers tend to push priorities in increasing order.
+macro_rules! priority_queue {
+    ($name:ident, $peek:ident, $pop:ident) => {
+        pub struct $name<K, V, P: BalancePolicy<K, V> = Avl> {
+            map: BinaryTreeMap<K, V, P>,
+        }
+
+        impl<K: Ord, V, P: BalancePolicy<K, V> + Default> Default for $name<K, V, P> {
+            fn default() -> Self {
+                $name { map: BinaryTreeMap::default() }
+            }
+        }
+
+        impl<K: Ord, V, P: BalancePolicy<K, V>> From<BinaryTreeMap<K, V, P>> for $name<K, V, P> {
+            fn from(map: BinaryTreeMap<K, V, P>) -> Self {
+                $name { map }
+            }
+        }
+
+        impl<K: Ord, V, P: BalancePolicy<K, V>> $name<K, V, P> {
+            pub fn push(&mut self, key: K, value: V) {
+                self.map.insert(key, value);
+            }
+
+            pub fn peek(&self) -> Option<(&K, &V)> {
+                self.map.$peek()
+            }
+
+            pub fn pop(&mut self) -> Option<(K, V)> {
+                self.map.$pop()
+            }
+
+            pub fn len(&self) -> usize {
+                self.map.len()
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.map.is_empty()
+            }
+
+            pub fn map(&self) -> &BinaryTreeMap<K, V, P> {
+                &self.map
+            }
+
+            pub fn map_mut(&mut self) -> &mut BinaryTreeMap<K, V, P> {
+                &mut self.map
+            }
+
+            pub fn into_map(self) -> BinaryTreeMap<K, V, P> {
+                self.map
+            }
+        }
+    };
+}
+
+priority_queue!(MinQueue, first_key_value, pop_first);
+priority_queue!(MaxQueue, last_key_value, pop_last);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_min_queue_pops_in_order() {
+        let mut queue: MinQueue<u32, &str> = MinQueue::default();
+        for (deadline, job) in [(30, "c"), (10, "a"), (20, "b")] {
+            queue.push(deadline, job);
+        }
+        assert_eq!(queue.peek(), Some((&10, &"a")));
+        // Keyed access alongside the queue order
+        assert_eq!(queue.map_mut().delete(&20), Ok("b"));
+        assert_eq!(queue.pop(), Some((10, "a")));
+        assert_eq!(queue.pop(), Some((30, "c")));
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn test_max_queue_over_existing_map() {
+        let mut map = BinaryTreeMap::with_policy(Avl);
+        for key in 0..100 {
+            map.insert(key, ());
+        }
+        let mut queue = MaxQueue::from(map);
+        let top: Vec<_> = core::iter::from_fn(|| queue.pop().map(|(k, _)| k)).take(3).collect();
+        assert_eq!(top, [99, 98, 97]);
+        assert_eq!(queue.len(), 97);
+        assert_eq!(queue.into_map().check_invariants(), Ok(()));
+    }
+}
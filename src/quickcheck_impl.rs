@@ -0,0 +1,83 @@
+use crate::{BalancePolicy, BinaryTreeMap};
+use quickcheck::{Arbitrary, Gen};
+use std::rc::Rc;
+
+// Built from random inserts and deletes like the `arbitrary` impl. Shrinking
+// removes entries with `delete` rather than rebuilding, so a smaller
+// candidate keeps the shape of whatever is left of the failing map.
+impl<K, V, P> Arbitrary for BinaryTreeMap<K, V, P>
+where
+    K: Ord + Clone + Arbitrary,
+    V: Clone + Arbitrary,
+    P: BalancePolicy<K, V> + Default + 'static,
+    P::Meta: Clone,
+{
+    fn arbitrary(g: &mut Gen) -> Self {
+        let mut map = BinaryTreeMap::default();
+        let steps = usize::arbitrary(g) % (g.size() + 1);
+        for _ in 0..steps {
+            // Roughly one delete for every three inserts
+            if u8::arbitrary(g) % 4 == 0 {
+                let _ = map.delete(&K::arbitrary(g));
+            } else {
+                map.insert(K::arbitrary(g), V::arbitrary(g));
+            }
+        }
+        map
+    }
+
+    // Drops runs of keys, halving the run length each round down to single
+    // keys, the same schedule quickcheck uses for `Vec`
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let keys: Vec<K> = self.iter().map(|(key, _)| key.clone()).collect();
+        let len = keys.len();
+        let source = Rc::new((self.clone(), keys));
+        let runs = core::iter::successors(Some(len), |&run| (run > 1).then_some(run / 2))
+            .filter(|&run| run > 0)
+            .flat_map(move |run| (0..len).step_by(run).map(move |start| (start, run)));
+        Box::new(runs.map(move |(start, run)| {
+            let (map, keys) = &*source;
+            let mut smaller = map.clone();
+            for key in keys.iter().skip(start).take(run) {
+                let _ = smaller.delete(key);
+            }
+            smaller
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shrink_drops_entries() {
+        let mut map = BinaryTreeMap::new();
+        for key in [4u8, 2, 6, 1, 3, 5, 7] {
+            map.insert(key, ());
+        }
+        let candidates: Vec<_> = map.shrink().collect();
+        assert!(candidates[0].is_empty());
+        assert!(candidates.iter().all(|smaller| smaller.len() < map.len()));
+        assert!(candidates.iter().all(|smaller| smaller.iter().all(|(key, _)| map.get(key).is_ok())));
+        // Runs of 7, then 3, then 1
+        assert_eq!(candidates.len(), 1 + 3 + 7);
+        assert!(candidates[4..].iter().all(|smaller| smaller.len() == 6));
+    }
+
+    #[test]
+    fn test_failing_property_minimizes() {
+        // Fails for any map holding a key above 200; greedily shrinking the
+        // way quickcheck does must end at a single offending entry
+        let fails = |map: &BinaryTreeMap<u8, bool>| map.iter().any(|(key, _)| *key > 200);
+        let mut map = (0..100)
+            .map(|_| BinaryTreeMap::<u8, bool>::arbitrary(&mut Gen::new(400)))
+            .find(fails)
+            .expect("some map holds a large key");
+        while let Some(smaller) = map.shrink().find(fails) {
+            map = smaller;
+        }
+        assert_eq!(map.len(), 1);
+        assert!(map.iter().all(|(key, _)| *key > 200));
+    }
+}
@@ -0,0 +1,108 @@
+use crate::node::{Link, Node};
+use crate::{BalancePolicy, BinaryTreeMap};
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Rebuilds the tree to minimal height with the Day–Stout–Warren
+    // algorithm: O(n) rotations and no allocation beyond the nodes themselves.
+    pub fn rebalance(&mut self) {
+        let count = self.len();
+        Self::tree_to_vine(&mut self.root);
+        Self::vine_to_tree(&mut self.root, count);
+        Self::refresh_all(&mut self.root);
+    }
+
+    // Rotates every left child up until the tree is a right-leaning spine
+    fn tree_to_vine(root: &mut Link<K, V, P::Meta>) {
+        let mut cursor = root;
+        while let Some(node) = cursor.take() {
+            if node.left.is_some() {
+                *cursor = Some(Node::rotate_right::<P>(node));
+            } else {
+                cursor = &mut cursor.insert(node).right;
+            }
+        }
+    }
+
+    fn vine_to_tree(root: &mut Link<K, V, P::Meta>, count: usize) {
+        // Largest complete tree that fits; the rest become the bottom level
+        let mut complete = (1 << (count + 1).ilog2()) - 1;
+        Self::compress(root, count - complete);
+        while complete > 1 {
+            complete /= 2;
+            Self::compress(root, complete);
+        }
+    }
+
+    // Left-rotates every other node along the spine, `times` times
+    fn compress(root: &mut Link<K, V, P::Meta>, times: usize) {
+        let mut cursor = root;
+        for _ in 0..times {
+            let Some(node) = cursor.take() else {
+                return;
+            };
+            cursor = &mut cursor.insert(Node::rotate_left::<P>(node)).right;
+        }
+    }
+
+    // Rotations only refresh the two nodes involved, so recompute metadata
+    // such as AVL heights once the final shape is in place. The tree is
+    // balanced by now, so the recursion depth is logarithmic.
+    fn refresh_all(node: &mut Link<K, V, P::Meta>) {
+        if let Some(n) = node {
+            Self::refresh_all(&mut n.left);
+            Self::refresh_all(&mut n.right);
+            n.refresh::<P>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Avl, BinaryTreeMap};
+
+    #[test]
+    fn test_rebalance_degenerate_tree() {
+        let mut map = BinaryTreeMap::new();
+        for key in 0..1000 {
+            map.insert(key, key * 2);
+        }
+        assert_eq!(map.height(), 1000);
+
+        map.rebalance();
+
+        assert_eq!(map.height(), 10);
+        assert_eq!(map.len(), 1000);
+        for key in 0..1000 {
+            assert_eq!(map.get(&key), Ok(&(key * 2)));
+        }
+        assert_eq!(map.nth(500), Some((&500, &1000)));
+    }
+
+    #[test]
+    fn test_rebalance_sizes_that_fill_levels_exactly() {
+        for count in [0usize, 1, 2, 3, 7, 8, 15, 16] {
+            let mut map = BinaryTreeMap::new();
+            for key in (0..count).rev() {
+                map.insert(key, ());
+            }
+            map.rebalance();
+
+            let expected = (usize::BITS - count.leading_zeros()) as usize;
+            assert_eq!(map.height(), expected, "count = {count}");
+            assert_eq!(map.len(), count);
+        }
+    }
+
+    #[test]
+    fn test_rebalance_refreshes_policy_metadata() {
+        let mut map = BinaryTreeMap::with_policy(Avl);
+        for key in 0..100 {
+            map.insert(key, ());
+        }
+        map.rebalance();
+
+        assert_eq!(*map.root.as_ref().unwrap().meta(), map.height());
+        map.insert(100, ());
+        assert_eq!(map.len(), 101);
+    }
+}
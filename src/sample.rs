@@ -0,0 +1,66 @@
+use crate::{BalancePolicy, BinaryTreeMap};
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use rand::{Rng, RngExt};
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // An entry chosen uniformly at random: a random rank, found through the
+    // subtree sizes in O(log n)
+    pub fn random_entry<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<(&K, &V)> {
+        if self.is_empty() {
+            return None;
+        }
+        self.nth(rng.random_range(0..self.len()))
+    }
+
+    // `n` distinct entries chosen uniformly at random (all of them if the
+    // map is smaller), in key order. Floyd's algorithm draws the ranks with
+    // one random number each, then each is looked up in O(log n).
+    pub fn sample_n<R: Rng + ?Sized>(&self, rng: &mut R, n: usize) -> Vec<(&K, &V)> {
+        let len = self.len();
+        let mut ranks = BTreeSet::new();
+        for upper in len - n.min(len)..len {
+            let rank = rng.random_range(0..=upper);
+            if !ranks.insert(rank) {
+                ranks.insert(upper);
+            }
+        }
+        ranks.into_iter().filter_map(|rank| self.nth(rank)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_random_entry_is_uniform() {
+        let mut rng = XorShiftRng::seed_from_u64(7);
+        let mut map = BinaryTreeMap::new();
+        assert_eq!(map.random_entry(&mut rng), None);
+        for key in 0..10 {
+            map.insert(key, ());
+        }
+        let mut counts = [0; 10];
+        for _ in 0..10_000 {
+            counts[*map.random_entry(&mut rng).unwrap().0] += 1;
+        }
+        assert!(counts.iter().all(|&count| (800..1200).contains(&count)), "{counts:?}");
+    }
+
+    #[test]
+    fn test_sample_n_distinct_in_key_order() {
+        let mut rng = XorShiftRng::seed_from_u64(11);
+        let mut map = BinaryTreeMap::with_policy(crate::Avl);
+        for key in 0..1000 {
+            map.insert(key, key);
+        }
+        let sample = map.sample_n(&mut rng, 50);
+        assert_eq!(sample.len(), 50);
+        assert!(sample.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(map.sample_n(&mut rng, 5000).len(), 1000);
+        assert!(map.sample_n(&mut rng, 0).is_empty());
+    }
+}
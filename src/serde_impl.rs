@@ -0,0 +1,81 @@
+use crate::{BalancePolicy, BinaryTreeMap, MapBuilder};
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use core::fmt;
+use core::marker::PhantomData;
+
+// Serialized as a plain map in ascending key order, the same as `BTreeMap`
+impl<K, V, P> Serialize for BinaryTreeMap<K, V, P>
+where
+    K: Ord + Serialize,
+    V: Serialize,
+    P: BalancePolicy<K, V>,
+{
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (key, value) in self {
+            map.serialize_entry(key, value)?;
+        }
+        map.end()
+    }
+}
+
+// Entries may arrive in any order; they go through `MapBuilder`, so the
+// result is balanced and a repeated key keeps its last value
+impl<'de, K, V, P> Deserialize<'de> for BinaryTreeMap<K, V, P>
+where
+    K: Ord + Deserialize<'de>,
+    V: Deserialize<'de>,
+    P: BalancePolicy<K, V> + Default,
+{
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(MapVisitor(PhantomData))
+    }
+}
+
+struct MapVisitor<K, V, P>(PhantomData<(K, V, P)>);
+
+impl<'de, K, V, P> Visitor<'de> for MapVisitor<K, V, P>
+where
+    K: Ord + Deserialize<'de>,
+    V: Deserialize<'de>,
+    P: BalancePolicy<K, V> + Default,
+{
+    type Value = BinaryTreeMap<K, V, P>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Self::Value, A::Error> {
+        let mut builder = MapBuilder::with_capacity(access.size_hint().unwrap_or(0));
+        while let Some((key, value)) = access.next_entry()? {
+            builder.push(key, value);
+        }
+        Ok(builder.build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Avl, BinaryTreeMap, MapError};
+
+    #[test]
+    fn test_serializes_as_ordered_map() {
+        let mut map = BinaryTreeMap::new();
+        for key in [3, 1, 2] {
+            map.insert(key, key.to_string());
+        }
+        assert_eq!(serde_json::to_string(&map).unwrap(), r#"{"1":"1","2":"2","3":"3"}"#);
+    }
+
+    #[test]
+    fn test_deserializes_unordered_entries() {
+        let map: BinaryTreeMap<u32, bool, Avl> = serde_json::from_str(r#"{"9":true,"4":false,"9":false}"#).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&9), Ok(&false));
+        assert_eq!(map.get(&5), Err(MapError::KeyNotFound));
+        assert!(serde_json::from_str::<BinaryTreeMap<u32, bool>>("[1]").is_err());
+    }
+}
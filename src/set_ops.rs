@@ -0,0 +1,442 @@
+use crate::node::Link;
+use crate::{BalancePolicy, BinaryTreeMap};
+use core::ops::RangeBounds;
+use alloc::vec;
+use alloc::vec::Vec;
+
+// What `divide_and_conquer` does with one subproblem: settle it outright,
+// or split it into a left and a right subproblem and the state needed to
+// merge their solutions
+enum Divided<T, S, R> {
+    Solved(R),
+    Split(T, S, T),
+}
+
+// Solves a problem over a tree recursively in effect, but with explicit
+// stacks instead of the call stack, so degenerate trees of any depth are
+// fine. The left subproblem is solved before the right one, and each merge
+// runs once both are done, as plain recursion would order them.
+fn divide_and_conquer<T, S, R>(
+    problem: T,
+    mut divide: impl FnMut(T) -> Divided<T, S, R>,
+    mut merge: impl FnMut(R, S, R) -> R,
+) -> R {
+    enum Work<T, S> {
+        Solve(T),
+        Merge(S),
+    }
+    let mut work = vec![Work::Solve(problem)];
+    let mut solved = Vec::new();
+    while let Some(item) = work.pop() {
+        match item {
+            Work::Solve(problem) => match divide(problem) {
+                Divided::Solved(solution) => solved.push(solution),
+                Divided::Split(left, state, right) => {
+                    work.push(Work::Merge(state));
+                    work.push(Work::Solve(right));
+                    work.push(Work::Solve(left));
+                },
+            },
+            Work::Merge(state) => {
+                let right = solved.pop().expect("right half was solved");
+                let left = solved.pop().expect("left half was solved");
+                solved.push(merge(left, state, right));
+            },
+        }
+    }
+    solved.pop().expect("the whole problem was solved")
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Merges `other` into `self`. Keys present in both are combined with
+    // `resolve(key, self_value, other_value)`. Runs in O(m log(n/m + 1))
+    // for balanced policies by splitting rather than reinserting entries.
+    pub fn union<F>(mut self, mut other: Self, mut resolve: F) -> Self
+    where
+        F: FnMut(&K, V, V) -> V,
+    {
+        let root = Self::union_links(self.root.take(), other.root.take(), &mut resolve);
+        BinaryTreeMap::from_root(root)
+    }
+
+    fn union_links<F>(a: Link<K, V, P::Meta>, b: Link<K, V, P::Meta>, resolve: &mut F) -> Link<K, V, P::Meta>
+    where
+        F: FnMut(&K, V, V) -> V,
+    {
+        divide_and_conquer(
+            (a, b),
+            |(a, b)| {
+                let Some(mut a) = a else {
+                    return Divided::Solved(b);
+                };
+                if b.is_none() {
+                    return Divided::Solved(Some(a));
+                }
+                let (b_below, b_match, b_above) = Self::split_link(b, &a.key);
+                let (left, right) = (a.left.take(), a.right.take());
+                Divided::Split((left, b_below), (a, b_match), (right, b_above))
+            },
+            |left, (mut a, b_match), right| {
+                if let Some(b_match) = b_match {
+                    a.value = resolve(&a.key, a.value, b_match.value);
+                }
+                Some(Self::join_links(left, a, right))
+            },
+        )
+    }
+
+    // Keeps only keys present in both maps, combining their values
+    pub fn intersection<F>(mut self, mut other: Self, mut combine: F) -> Self
+    where
+        F: FnMut(&K, V, V) -> V,
+    {
+        let root = Self::intersection_links(self.root.take(), other.root.take(), &mut combine);
+        BinaryTreeMap::from_root(root)
+    }
+
+    fn intersection_links<F>(a: Link<K, V, P::Meta>, b: Link<K, V, P::Meta>, combine: &mut F) -> Link<K, V, P::Meta>
+    where
+        F: FnMut(&K, V, V) -> V,
+    {
+        divide_and_conquer(
+            (a, b),
+            |(a, b)| {
+                let (Some(mut a), Some(b)) = (a, b) else {
+                    return Divided::Solved(None);
+                };
+                let (b_below, b_match, b_above) = Self::split_link(Some(b), &a.key);
+                let (left, right) = (a.left.take(), a.right.take());
+                Divided::Split((left, b_below), (a, b_match), (right, b_above))
+            },
+            |left, (mut a, b_match), right| match b_match {
+                Some(b_match) => {
+                    a.value = combine(&a.key, a.value, b_match.value);
+                    Some(Self::join_links(left, a, right))
+                },
+                None => Self::join_pair(left, right),
+            },
+        )
+    }
+
+    // Keeps the entries of `self` whose keys are absent from `other`
+    pub fn difference(mut self, mut other: Self) -> Self {
+        let root = Self::difference_links(self.root.take(), other.root.take());
+        BinaryTreeMap::from_root(root)
+    }
+
+    fn difference_links(a: Link<K, V, P::Meta>, b: Link<K, V, P::Meta>) -> Link<K, V, P::Meta> {
+        divide_and_conquer(
+            (a, b),
+            |(a, b)| {
+                let (a, b) = match (a, b) {
+                    (Some(a), Some(b)) => (a, b),
+                    (a, _) => return Divided::Solved(a),
+                };
+                let (a_below, _, a_above) = Self::split_link(Some(a), &b.key);
+                Divided::Split((a_below, b.left), (), (a_above, b.right))
+            },
+            |left, (), right| Self::join_pair(left, right),
+        )
+    }
+
+    // Removes every listed key in a single pass over the tree, returning the
+    // removed entries in key order. Absent keys are ignored. The observer
+    // hears of each removal, in key order, once the tree is whole again.
+    pub fn delete_many<I: IntoIterator<Item = K>>(&mut self, keys: I) -> Vec<(K, V)> {
+        let mut keys: Vec<K> = keys.into_iter().collect();
+        keys.sort();
+        keys.dedup();
+        let mut removed = Vec::new();
+        self.root = Self::delete_many_links(self.root.take(), &keys, &mut removed);
+        for (key, value) in &removed {
+            self.observer.notify(|o| o.on_remove(key, value));
+        }
+        removed
+    }
+
+    // Removes the entries in `range` that fail `pred`, returning them in
+    // key order. Only the range is visited, and the removals then take the
+    // paths to those entries alone.
+    pub fn retain_range<R: RangeBounds<K>>(&mut self, range: R, mut pred: impl FnMut(&K, &V) -> bool) -> Vec<(K, V)>
+    where
+        K: Clone,
+    {
+        let failing: Vec<K> = self.range(range).filter(|(key, value)| !pred(key, value)).map(|(key, _)| key.clone()).collect();
+        self.delete_many(failing)
+    }
+
+    fn delete_many_links(link: Link<K, V, P::Meta>, keys: &[K], removed: &mut Vec<(K, V)>) -> Link<K, V, P::Meta> {
+        let start = removed.len();
+        let root = divide_and_conquer(
+            (link, keys),
+            |(link, keys)| {
+                let mut node = match link {
+                    Some(node) if !keys.is_empty() => node,
+                    link => return Divided::Solved(link),
+                };
+                let below = keys.partition_point(|key| *key < node.key);
+                let matched = keys.get(below).is_some_and(|key| *key == node.key);
+                let above = if matched { below + 1 } else { below };
+                let (left, right) = (node.left.take(), node.right.take());
+                Divided::Split((left, &keys[..below]), (node, matched), (right, &keys[above..]))
+            },
+            |left, (node, matched), right| {
+                if matched {
+                    let node = *node;
+                    removed.push((node.key, node.value));
+                    Self::join_pair(left, right)
+                } else {
+                    Some(Self::join_links(left, node, right))
+                }
+            },
+        );
+        // Merges run children first, so the removals arrive in post-order
+        removed[start..].sort_by(|a, b| a.0.cmp(&b.0));
+        root
+    }
+
+    // Keeps the entries whose keys appear in exactly one of the two maps
+    pub fn symmetric_difference(mut self, mut other: Self) -> Self {
+        let root = Self::symmetric_difference_links(self.root.take(), other.root.take());
+        BinaryTreeMap::from_root(root)
+    }
+
+    fn symmetric_difference_links(a: Link<K, V, P::Meta>, b: Link<K, V, P::Meta>) -> Link<K, V, P::Meta> {
+        divide_and_conquer(
+            (a, b),
+            |(a, b)| {
+                let Some(a) = a else {
+                    return Divided::Solved(b);
+                };
+                let Some(mut b) = b else {
+                    return Divided::Solved(Some(a));
+                };
+                let (a_below, a_match, a_above) = Self::split_link(Some(a), &b.key);
+                let (left, right) = (b.left.take(), b.right.take());
+                Divided::Split((a_below, left), (b, a_match.is_some()), (a_above, right))
+            },
+            |left, (b, matched), right| match matched {
+                true => Self::join_pair(left, right),
+                false => Some(Self::join_links(left, b, right)),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Avl, BinaryTreeMap, MapError, WeightBalancedTreeMap};
+
+    #[test]
+    fn test_union_resolves_conflicts() {
+        let mut a = BinaryTreeMap::new();
+        let mut b = BinaryTreeMap::new();
+        for key in [5, 1, 9, 3] {
+            a.insert(key, 1);
+        }
+        for key in [3, 7, 9, 11] {
+            b.insert(key, 10);
+        }
+
+        let merged = a.union(b, |_, left, right| left + right);
+
+        assert_eq!(merged.len(), 6);
+        assert_eq!(merged.get(&3), Ok(&11));
+        assert_eq!(merged.get(&9), Ok(&11));
+        assert_eq!(merged.get(&7), Ok(&10));
+        assert_eq!(merged.get(&1), Ok(&1));
+        assert_eq!(merged.get(&4), Err(MapError::KeyNotFound));
+    }
+
+    #[test]
+    fn test_union_keeps_avl_balance() {
+        let mut a = BinaryTreeMap::with_policy(Avl);
+        let mut b = BinaryTreeMap::with_policy(Avl);
+        for key in 0..1000 {
+            a.insert(key, "a");
+        }
+        for key in (500..1500).step_by(7) {
+            b.insert(key, "b");
+        }
+
+        let merged = a.union(b, |_, left, _| left);
+
+        assert!(merged.is_balanced());
+        assert_eq!(merged.len(), 1000 + (500..1500).step_by(7).filter(|key| *key >= 1000).count());
+        assert_eq!(merged.get(&504), Ok(&"a"));
+        assert_eq!(merged.get(&1004), Ok(&"b"));
+        assert_eq!(merged.rank(&1000), 1000);
+    }
+
+    #[test]
+    fn test_union_of_unequal_weight_balanced_maps() {
+        let mut big = WeightBalancedTreeMap::default();
+        let mut small = WeightBalancedTreeMap::default();
+        for key in 0..2000 {
+            big.insert(key * 2, key);
+        }
+        for key in 0..5 {
+            small.insert(key * 1000 + 1, key);
+        }
+
+        let merged = small.union(big, |_, left, _| left);
+
+        assert_eq!(merged.len(), 2005);
+        assert!(merged.height() <= 2 * 11);
+        assert_eq!(merged.get(&3001), Ok(&3));
+    }
+
+    #[test]
+    fn test_union_with_deep_spine() {
+        let spine = crate::right_spine(100_000);
+        let other: BinaryTreeMap<_, _> = BinaryTreeMap::from_sorted_iter([(5, 0), (99_999, 0), (100_000, 0)]);
+
+        let merged = spine.union(other, |_, _, new| new);
+
+        assert_eq!(merged.len(), 100_001);
+        assert_eq!(merged.get(&5), Ok(&0));
+        assert_eq!(merged.get(&6), Ok(&6));
+        assert_eq!(merged.rank(&99_999), 99_999);
+
+        // extend_sorted merges an overlapping batch through union
+        let mut spine = crate::right_spine(100_000);
+        spine.extend_sorted([(7, 0), (99_998, 0)]);
+        assert_eq!(spine.len(), 100_000);
+        assert_eq!(spine.get(&99_998), Ok(&0));
+    }
+
+    fn avl_map(keys: impl IntoIterator<Item = i32>, value: &'static str) -> BinaryTreeMap<i32, &'static str, Avl> {
+        let mut map = BinaryTreeMap::with_policy(Avl);
+        for key in keys {
+            map.insert(key, value);
+        }
+        map
+    }
+
+    fn keys<V>(map: BinaryTreeMap<i32, V, Avl>) -> Vec<i32> {
+        map.into_sorted_vec().into_iter().map(|(key, _)| key).collect()
+    }
+
+    #[test]
+    fn test_intersection_combines_values() {
+        let a = avl_map(0..10, "a");
+        let b = avl_map((0..20).step_by(3), "b");
+
+        let both = a.intersection(b, |_, _, right| right);
+
+        assert!(both.is_balanced());
+        assert_eq!(both.get(&6), Ok(&"b"));
+        assert_eq!(keys(both), vec![0, 3, 6, 9]);
+    }
+
+    #[test]
+    fn test_difference() {
+        let a = avl_map(0..10, "a");
+        let b = avl_map((0..20).step_by(3), "b");
+
+        let only_a = a.difference(b);
+
+        assert!(only_a.is_balanced());
+        assert_eq!(only_a.get(&1), Ok(&"a"));
+        assert_eq!(keys(only_a), vec![1, 2, 4, 5, 7, 8]);
+    }
+
+    #[test]
+    fn test_symmetric_difference() {
+        let a = avl_map(0..10, "a");
+        let b = avl_map((0..20).step_by(3), "b");
+
+        let either = a.symmetric_difference(b);
+
+        assert!(either.is_balanced());
+        assert_eq!(either.get(&2), Ok(&"a"));
+        assert_eq!(either.get(&12), Ok(&"b"));
+        assert_eq!(keys(either), vec![1, 2, 4, 5, 7, 8, 12, 15, 18]);
+    }
+
+    #[test]
+    fn test_set_operations_with_deep_spine() {
+        let other: BinaryTreeMap<_, _> = BinaryTreeMap::from_sorted_iter([(3, 0), (99_999, 0), (100_000, 0)]);
+
+        let both = crate::right_spine(100_000).intersection(other.clone(), |_, _, new| new);
+        assert_eq!(both.into_sorted_vec(), [(3, 0), (99_999, 0)]);
+
+        let only_spine = crate::right_spine(100_000).difference(other.clone());
+        assert_eq!(only_spine.len(), 99_998);
+        assert_eq!(only_spine.get(&99_999), Err(MapError::KeyNotFound));
+        assert_eq!(other.clone().difference(crate::right_spine(100_000)).into_sorted_vec(), [(100_000, 0)]);
+
+        let either = other.symmetric_difference(crate::right_spine(100_000));
+        assert_eq!(either.len(), 99_999);
+        assert_eq!(either.get(&100_000), Ok(&0));
+        assert_eq!(either.get(&3), Err(MapError::KeyNotFound));
+    }
+
+    #[test]
+    fn test_delete_many() {
+        let mut map = avl_map(0..1000, "v");
+
+        let removed = map.delete_many((0..1000).rev().filter(|key| key % 3 == 0).chain([5000, 3]));
+
+        assert_eq!(removed.len(), 334);
+        assert_eq!(removed[0], (0, "v"));
+        assert_eq!(removed[333], (999, "v"));
+        assert!(removed.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(map.len(), 666);
+        assert!(map.is_balanced());
+        assert_eq!(map.get(&3), Err(MapError::KeyNotFound));
+        assert_eq!(map.get(&4), Ok(&"v"));
+    }
+
+    #[test]
+    fn test_delete_many_from_deep_spine() {
+        let mut spine = crate::right_spine(200_000);
+
+        let removed = spine.delete_many([199_999, 10, 150_000]);
+
+        assert_eq!(removed, [(10, 10), (150_000, 150_000), (199_999, 199_999)]);
+        assert_eq!(spine.len(), 199_997);
+        assert_eq!(spine.get(&150_000), Err(MapError::KeyNotFound));
+        assert_eq!(spine.rank(&199_998), 199_996);
+    }
+
+    #[test]
+    fn test_retain_range_visits_only_range() {
+        let mut map = BinaryTreeMap::with_policy(Avl);
+        for minute in 0..300 {
+            map.insert(minute, minute % 3 == 0);
+        }
+        let mut visited = 0;
+        let removed = map.retain_range(240.., |_, fresh| {
+            visited += 1;
+            *fresh
+        });
+        assert_eq!(visited, 60);
+        assert_eq!(removed.len(), 40);
+        assert_eq!(removed[0], (241, false));
+        assert_eq!(map.len(), 260);
+        // Stale entries before the window stay
+        assert_eq!(map.get(&1), Ok(&false));
+        assert_eq!(map.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_retain_range_on_deep_spine() {
+        let mut spine = crate::right_spine(200_000);
+
+        let removed = spine.retain_range(199_990.., |key, _| key % 2 == 0);
+
+        assert_eq!(removed.len(), 5);
+        assert_eq!(removed[4], (199_999, 199_999));
+        assert_eq!(spine.len(), 199_995);
+        assert_eq!(spine.get(&199_998), Ok(&199_998));
+    }
+
+    #[test]
+    fn test_set_operations_with_empty_maps() {
+        let empty = || avl_map([], "");
+        assert_eq!(keys(avl_map(0..3, "a").intersection(empty(), |_, v, _| v)), Vec::<i32>::new());
+        assert_eq!(keys(avl_map(0..3, "a").difference(empty())), vec![0, 1, 2]);
+        assert_eq!(keys(empty().symmetric_difference(avl_map(0..3, "b"))), vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{BalancePolicy, BinaryTreeMap, MapError, Unbalanced};
+use alloc::collections::BTreeMap;
+use core::fmt::Debug;
+
+// Test helper that applies every operation to a `BinaryTreeMap` and to a
+// `BTreeMap` side by side and panics as soon as their answers differ. It is
+// only compiled with debug assertions, so it cannot end up in a release
+// build by accident.
+pub struct ShadowedMap<K, V, P: BalancePolicy<K, V> = Unbalanced> {
+    map: BinaryTreeMap<K, V, P>,
+    shadow: BTreeMap<K, V>,
+}
+
+impl<K, V, P> Default for ShadowedMap<K, V, P>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + PartialEq + Debug,
+    P: BalancePolicy<K, V> + Default,
+{
+    fn default() -> Self {
+        ShadowedMap { map: BinaryTreeMap::default(), shadow: BTreeMap::new() }
+    }
+}
+
+impl<K: Ord + Clone + Debug, V: Clone + PartialEq + Debug> ShadowedMap<K, V> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<K, V, P> ShadowedMap<K, V, P>
+where
+    K: Ord + Clone + Debug,
+    V: Clone + PartialEq + Debug,
+    P: BalancePolicy<K, V>,
+{
+    pub fn insert(&mut self, key: K, value: V) {
+        let expected = self.shadow.insert(key.clone(), value.clone());
+        let found = self.map.get(&key).ok().cloned();
+        assert_eq!(found, expected, "value replaced by insert({key:?}) differs");
+        self.map.insert(key, value);
+        self.check_len();
+    }
+
+    pub fn delete(&mut self, key: &K) -> Result<V, MapError> {
+        let result = self.map.delete(key);
+        let expected = self.shadow.remove(key);
+        assert_eq!(result.as_ref().ok(), expected.as_ref(), "delete({key:?}) differs");
+        self.check_len();
+        result
+    }
+
+    pub fn get(&self, key: &K) -> Result<&V, MapError> {
+        let result = self.map.get(key);
+        assert_eq!(result.as_ref().ok().copied(), self.shadow.get(key), "get({key:?}) differs");
+        result
+    }
+
+    pub fn len(&self) -> usize {
+        self.check_len();
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Compares every entry in order, not just the ones touched so far
+    pub fn assert_consistent(&self) {
+        assert!(self.map.iter().eq(self.shadow.iter()), "contents differ from the shadow map");
+        assert_eq!(self.map.check_invariants(), Ok(()));
+    }
+
+    pub fn inner(&self) -> &BinaryTreeMap<K, V, P> {
+        &self.map
+    }
+
+    pub fn into_inner(self) -> BinaryTreeMap<K, V, P> {
+        self.assert_consistent();
+        self.map
+    }
+
+    fn check_len(&self) {
+        assert_eq!(self.map.len(), self.shadow.len(), "len differs from the shadow map");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Avl;
+
+    #[test]
+    fn test_mirrors_operations() {
+        let mut map = ShadowedMap::<_, _, Avl>::default();
+        for key in (0..200).map(|i| i * 37 % 200) {
+            map.insert(key, key);
+        }
+        for key in (0..250).step_by(3) {
+            let _ = map.delete(&key);
+        }
+        map.insert(3, 0);
+        assert_eq!(map.get(&3), Ok(&0));
+        assert_eq!(map.get(&0), Err(MapError::KeyNotFound));
+        map.assert_consistent();
+        assert_eq!(map.into_inner().len(), 134);
+    }
+
+    #[test]
+    #[should_panic(expected = "contents differ")]
+    fn test_catches_divergence() {
+        let mut map = ShadowedMap::new();
+        map.insert(1, "one");
+        map.insert(2, "two");
+        // Corrupt the real map behind the shadow's back
+        map.map.root.as_mut().unwrap().value = "uno";
+        map.assert_consistent();
+    }
+}
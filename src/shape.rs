@@ -0,0 +1,154 @@
+// Serde representation that keeps the exact tree structure, for use with
+// `#[serde(with = "binary_tree_map::shape")]`. The tree is one flat
+// sequence in preorder: every node is a `[key, value]` pair followed by its
+// left and right subtrees, every empty child a none, so JSON looks like
+// `[[2, "b"], [1, "a"], null, null, null]`. Being flat, it nests no deeper
+// for tall trees, so formats with recursion limits take any shape.
+use crate::bytes::{preorder, PreorderBuilder};
+use crate::node::Link;
+use crate::{BalancePolicy, BinaryTreeMap, MapError};
+use serde::de::{self, Deserializer, IgnoredAny, SeqAccess, Visitor};
+use serde::ser::{SerializeSeq, Serializer};
+use serde::{Deserialize, Serialize};
+use core::fmt;
+use core::marker::PhantomData;
+
+pub fn serialize<K, V, P, S>(map: &BinaryTreeMap<K, V, P>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    K: Serialize,
+    V: Serialize,
+    P: BalancePolicy<K, V>,
+    S: Serializer,
+{
+    // A tree of n nodes has n + 1 empty children
+    let len = map.root.as_ref().map_or(0, |root| root.size);
+    let mut seq = serializer.serialize_seq(Some(2 * len + 1))?;
+    for link in preorder(&map.root) {
+        seq.serialize_element(&link.map(|node| (&node.key, &node.value)))?;
+    }
+    seq.end()
+}
+
+// Rebuilds the serialized shape as is. Policy metadata is recomputed from
+// it, but no rebalancing happens, so the tree must pass `check_invariants`:
+// keys strictly ascending and a shape the policy allows.
+pub fn deserialize<'de, K, V, P, D>(deserializer: D) -> Result<BinaryTreeMap<K, V, P>, D::Error>
+where
+    K: Ord + Deserialize<'de>,
+    V: Deserialize<'de>,
+    P: BalancePolicy<K, V>,
+    D: Deserializer<'de>,
+{
+    let root = deserializer.deserialize_seq(ShapeVisitor::<K, V, P>(PhantomData))?;
+    let map = BinaryTreeMap::from_root(root);
+    if map.check_invariants().is_err() {
+        return Err(de::Error::custom(MapError::InvalidEncoding));
+    }
+    Ok(map)
+}
+
+struct ShapeVisitor<K, V, P>(PhantomData<(K, V, P)>);
+
+impl<'de, K, V, P> Visitor<'de> for ShapeVisitor<K, V, P>
+where
+    K: Deserialize<'de>,
+    V: Deserialize<'de>,
+    P: BalancePolicy<K, V>,
+{
+    type Value = Link<K, V, P::Meta>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a preorder sequence of [key, value] nodes and nones")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut builder = PreorderBuilder::<K, V, P>::new();
+        let mut read = 0;
+        let root = loop {
+            let item: Option<(K, V)> = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(read, &self))?;
+            read += 1;
+            match item {
+                Some((key, value)) => builder.node(key, value),
+                None => {
+                    if let Some(root) = builder.empty() {
+                        break root;
+                    }
+                },
+            }
+        };
+        if seq.next_element::<IgnoredAny>()?.is_some() {
+            return Err(de::Error::custom("elements after the last subtree"));
+        }
+        Ok(root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Avl, BinaryTreeMap};
+
+    fn to_json<V: serde::Serialize, P: crate::BalancePolicy<u32, V>>(map: &BinaryTreeMap<u32, V, P>) -> String {
+        let mut out = Vec::new();
+        super::serialize(map, &mut serde_json::Serializer::new(&mut out)).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn from_json<P: crate::BalancePolicy<u32, char>>(json: &str) -> serde_json::Result<BinaryTreeMap<u32, char, P>> {
+        super::deserialize(&mut serde_json::Deserializer::from_str(json))
+    }
+
+    #[test]
+    fn test_round_trip_keeps_shape() {
+        let mut map = BinaryTreeMap::new();
+        for (key, value) in [(1, 'a'), (3, 'c'), (2, 'b')] {
+            map.insert(key, value);
+        }
+        let json = to_json(&map);
+        assert_eq!(json, r#"[[1,"a"],null,[3,"c"],[2,"b"],null,null,null]"#);
+
+        let copy = from_json::<crate::Unbalanced>(&json).unwrap();
+        assert_eq!(copy.height(), 3);
+        assert_eq!(copy.get(&2), Ok(&'b'));
+        assert_eq!(to_json(&copy), json);
+
+        // Far deeper than serde_json's recursion limit
+        let spine = crate::right_spine(10_000);
+        let json = to_json(&spine);
+        let copy: BinaryTreeMap<u32, u32> = super::deserialize(&mut serde_json::Deserializer::from_str(&json)).unwrap();
+        assert_eq!(copy.height(), 10_000);
+        assert_eq!(copy.get(&9_999), Ok(&9_999));
+    }
+
+    #[test]
+    fn test_truncated_deep_shape_is_an_error() {
+        // A left spine one null short of complete
+        let depth = 200_000;
+        let mut json = String::from("[");
+        for key in (0..depth).rev() {
+            json += &format!("[{key},null],");
+        }
+        json += &vec!["null"; depth - 1].join(",");
+        json += "]";
+        let result: serde_json::Result<BinaryTreeMap<usize, ()>> = super::deserialize(&mut serde_json::Deserializer::from_str(&json));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_policy_metadata_is_recomputed() {
+        let map: BinaryTreeMap<u32, char, Avl> = from_json(r#"[[2,"b"],[1,"a"],null,null,null]"#).unwrap();
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.nth(0), Some((&1, &'a')));
+        assert!(from_json::<Avl>("[null]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_rejects_out_of_order_keys() {
+        assert!(from_json::<Avl>(r#"[[1,"a"],[2,"b"],null,null,null]"#).is_err());
+        assert!(from_json::<Avl>(r#"[[1,"a"],null]"#).is_err());
+        assert!(from_json::<Avl>(r#"[null,null]"#).is_err());
+        // Ordered, but too lopsided for AVL
+        let spine = r#"[[1,"a"],null,[2,"b"],null,[3,"c"],null,null]"#;
+        assert!(from_json::<crate::Unbalanced>(spine).is_ok());
+        assert!(from_json::<Avl>(spine).is_err());
+    }
+}
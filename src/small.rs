@@ -0,0 +1,144 @@
+use crate::{BinaryTreeMap, MapError};
+use alloc::vec::Vec;
+
+// Keeps up to `N` entries in a sorted inline array, searched linearly, and
+// only switches to tree nodes once it outgrows that. Small maps therefore
+// make no per-entry heap allocations. Once promoted, the map stays a tree.
+#[derive(Debug)]
+pub struct SmallTreeMap<K, V, const N: usize = 8> {
+    repr: Repr<K, V, N>,
+}
+
+#[derive(Debug)]
+enum Repr<K, V, const N: usize> {
+    // The first `len` slots are occupied, in ascending key order
+    Inline { entries: [Option<(K, V)>; N], len: usize },
+    Tree(BinaryTreeMap<K, V>),
+}
+
+impl<K: Ord, V, const N: usize> Default for SmallTreeMap<K, V, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V, const N: usize> SmallTreeMap<K, V, N> {
+    pub fn new() -> Self {
+        SmallTreeMap {
+            repr: Repr::Inline { entries: core::array::from_fn(|_| None), len: 0 },
+        }
+    }
+
+    pub fn is_inline(&self) -> bool {
+        matches!(self.repr, Repr::Inline { .. })
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.repr {
+            Repr::Inline { len, .. } => *len,
+            Repr::Tree(tree) => tree.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Position of `key` among the inline entries, or where it would go
+    fn search(entries: &[Option<(K, V)>], key: &K) -> Result<usize, usize> {
+        for (index, entry) in entries.iter().enumerate() {
+            let (existing, _) = entry.as_ref().expect("inline entries are packed");
+            match key.cmp(existing) {
+                core::cmp::Ordering::Equal => return Ok(index),
+                core::cmp::Ordering::Less => return Err(index),
+                core::cmp::Ordering::Greater => {},
+            }
+        }
+        Err(entries.len())
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        let (entries, len) = match &mut self.repr {
+            Repr::Tree(tree) => return tree.insert(key, value),
+            Repr::Inline { entries, len } => (entries, len),
+        };
+        match Self::search(&entries[..*len], &key) {
+            Ok(index) => entries[index] = Some((key, value)),
+            Err(index) if *len < N => {
+                entries[index..=*len].rotate_right(1);
+                entries[index] = Some((key, value));
+                *len += 1;
+            },
+            Err(index) => {
+                // Full: move everything into a balanced tree
+                let mut sorted: Vec<(K, V)> = entries.iter_mut().filter_map(Option::take).collect();
+                sorted.insert(index, (key, value));
+                self.repr = Repr::Tree(BinaryTreeMap::from_sorted_vec(sorted));
+            },
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Result<&V, MapError> {
+        match &self.repr {
+            Repr::Tree(tree) => tree.get(key),
+            Repr::Inline { entries, len } => match Self::search(&entries[..*len], key) {
+                Ok(index) => Ok(&entries[index].as_ref().unwrap().1),
+                Err(_) => Err(MapError::KeyNotFound),
+            },
+        }
+    }
+
+    pub fn delete(&mut self, key: &K) -> Result<V, MapError> {
+        match &mut self.repr {
+            Repr::Tree(tree) => tree.delete(key),
+            Repr::Inline { entries, len } => match Self::search(&entries[..*len], key) {
+                Ok(index) => {
+                    let (_, value) = entries[index].take().unwrap();
+                    entries[index..*len].rotate_left(1);
+                    *len -= 1;
+                    Ok(value)
+                },
+                Err(_) => Err(MapError::KeyNotFound),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_map_stays_inline() {
+        let mut map: SmallTreeMap<i32, &str, 4> = SmallTreeMap::new();
+        map.insert(3, "c");
+        map.insert(1, "a");
+        map.insert(2, "b");
+        map.insert(1, "A");
+
+        assert!(map.is_inline());
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&1), Ok(&"A"));
+        assert_eq!(map.delete(&2), Ok("b"));
+        assert_eq!(map.get(&2), Err(MapError::KeyNotFound));
+        assert_eq!(map.get(&3), Ok(&"c"));
+    }
+
+    #[test]
+    fn test_promotes_to_tree_past_capacity() {
+        let mut map: SmallTreeMap<i32, i32, 4> = SmallTreeMap::new();
+        for key in [5, 1, 4, 2] {
+            map.insert(key, key * 10);
+        }
+        assert!(map.is_inline());
+
+        map.insert(3, 30);
+
+        assert!(!map.is_inline());
+        assert_eq!(map.len(), 5);
+        for key in 1..=5 {
+            assert_eq!(map.get(&key), Ok(&(key * 10)));
+        }
+        assert_eq!(map.delete(&4), Ok(40));
+    }
+}
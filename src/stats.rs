@@ -0,0 +1,178 @@
+// Operation counters behind the `stats` feature. Without the feature every
+// hook below compiles to nothing and `StatsCell` is zero-sized.
+#[cfg(feature = "stats")]
+use core::cell::Cell;
+use core::marker::PhantomData;
+#[cfg(feature = "stats")]
+use core::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+// Totals over every `insert`, `get` and `delete` since the map was created
+// or last reset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpStats {
+    pub operations: u64,
+    pub comparisons: u64,
+    pub rotations: u64,
+    pub allocations: u64,
+    // Deepest level any single operation reached (the root is depth 1)
+    pub max_depth: usize,
+}
+
+// Atomic so that `get` can count through a shared reference without making
+// the map `!Sync`
+#[derive(Debug, Default)]
+pub(crate) struct StatsCell {
+    #[cfg(feature = "stats")]
+    operations: AtomicU64,
+    #[cfg(feature = "stats")]
+    comparisons: AtomicU64,
+    #[cfg(feature = "stats")]
+    rotations: AtomicU64,
+    #[cfg(feature = "stats")]
+    allocations: AtomicU64,
+    #[cfg(feature = "stats")]
+    max_depth: AtomicUsize,
+}
+
+// Rotations and allocations happen deep inside node and policy code that
+// has no handle on the map, so they are tallied per thread and attributed to
+// whichever operation is in progress when they occur.
+#[cfg(feature = "stats")]
+thread_local! {
+    static EVENTS: Cell<(u64, u64)> = const { Cell::new((0, 0)) };
+}
+
+pub(crate) fn count_rotation() {
+    #[cfg(feature = "stats")]
+    EVENTS.with(|events| {
+        let (rotations, allocations) = events.get();
+        events.set((rotations + 1, allocations));
+    });
+}
+
+pub(crate) fn count_allocation() {
+    #[cfg(feature = "stats")]
+    EVENTS.with(|events| {
+        let (rotations, allocations) = events.get();
+        events.set((rotations, allocations + 1));
+    });
+}
+
+impl StatsCell {
+    #[cfg(feature = "stats")]
+    pub(crate) fn get(&self) -> OpStats {
+        OpStats {
+            operations: self.operations.load(Ordering::Relaxed),
+            comparisons: self.comparisons.load(Ordering::Relaxed),
+            rotations: self.rotations.load(Ordering::Relaxed),
+            allocations: self.allocations.load(Ordering::Relaxed),
+            max_depth: self.max_depth.load(Ordering::Relaxed),
+        }
+    }
+
+    #[cfg(feature = "stats")]
+    pub(crate) fn reset(&self) {
+        self.operations.store(0, Ordering::Relaxed);
+        self.comparisons.store(0, Ordering::Relaxed);
+        self.rotations.store(0, Ordering::Relaxed);
+        self.allocations.store(0, Ordering::Relaxed);
+        self.max_depth.store(0, Ordering::Relaxed);
+    }
+}
+
+// Measures one operation; the totals are updated when it is dropped
+pub(crate) struct Probe<'a> {
+    #[cfg(feature = "stats")]
+    cell: &'a StatsCell,
+    #[cfg(feature = "stats")]
+    start: (u64, u64),
+    #[cfg(feature = "stats")]
+    steps: usize,
+    marker: PhantomData<&'a StatsCell>,
+}
+
+impl<'a> Probe<'a> {
+    pub(crate) fn start(_cell: &'a StatsCell) -> Self {
+        Probe {
+            #[cfg(feature = "stats")]
+            cell: _cell,
+            #[cfg(feature = "stats")]
+            start: EVENTS.with(Cell::get),
+            #[cfg(feature = "stats")]
+            steps: 0,
+            marker: PhantomData,
+        }
+    }
+
+    // One key comparison, descending one level
+    pub(crate) fn step(&mut self) {
+        #[cfg(feature = "stats")]
+        {
+            self.steps += 1;
+        }
+    }
+}
+
+#[cfg(feature = "stats")]
+impl Drop for Probe<'_> {
+    fn drop(&mut self) {
+        let (rotations, allocations) = EVENTS.with(Cell::get);
+        let cell = self.cell;
+        cell.operations.fetch_add(1, Ordering::Relaxed);
+        cell.comparisons.fetch_add(self.steps as u64, Ordering::Relaxed);
+        cell.rotations.fetch_add(rotations - self.start.0, Ordering::Relaxed);
+        cell.allocations.fetch_add(allocations - self.start.1, Ordering::Relaxed);
+        cell.max_depth.fetch_max(self.steps, Ordering::Relaxed);
+    }
+}
+
+#[cfg(all(test, feature = "stats"))]
+mod tests {
+    use crate::{Avl, BinaryTreeMap, OpStats};
+
+    #[test]
+    fn test_counts_basic_operations() {
+        let mut map = BinaryTreeMap::new();
+        map.insert(2, ());
+        map.insert(1, ());
+        map.insert(3, ());
+        map.get(&3).unwrap();
+        map.delete(&1).unwrap();
+
+        assert_eq!(
+            map.op_stats(),
+            OpStats {
+                operations: 5,
+                comparisons: 6,
+                rotations: 0,
+                allocations: 3,
+                max_depth: 2,
+            }
+        );
+
+        map.reset_op_stats();
+        assert_eq!(map.op_stats(), OpStats::default());
+    }
+
+    #[test]
+    fn test_compare_unbalanced_and_avl() {
+        let mut plain = BinaryTreeMap::new();
+        let mut avl = BinaryTreeMap::with_policy(Avl);
+        for key in 0..256 {
+            plain.insert(key, ());
+            avl.insert(key, ());
+        }
+        for key in 0..256 {
+            plain.get(&key).unwrap();
+            avl.get(&key).unwrap();
+        }
+
+        let plain = plain.op_stats();
+        let avl = avl.op_stats();
+        assert_eq!(plain.rotations, 0);
+        assert!(avl.rotations > 0);
+        assert_eq!(plain.max_depth, 256);
+        assert!(avl.max_depth <= 9);
+        assert!(avl.comparisons * 10 < plain.comparisons);
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{BalancePolicy, BinaryTreeMap, Unbalanced};
+use core::fmt::Debug;
+use proptest::collection::{btree_map, SizeRange};
+use proptest::prelude::*;
+
+// How the generated entries are inserted. The policy still gets the final
+// say: an `Avl` map is balanced whatever order its keys arrive in, so the
+// degenerate shapes only show up with `Unbalanced`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shape {
+    // Keys inserted in a shuffled order
+    Random,
+    // Built bottom-up from the sorted entries, as `from_sorted_vec` does
+    Balanced,
+    // Ascending inserts: every node hangs off the right of the previous one
+    RightSpine,
+    // Descending inserts: every node hangs off the left of the previous one
+    LeftSpine,
+    // Alternating smallest and largest remaining key, so the single path
+    // turns at every level
+    Zigzag,
+}
+
+pub fn shapes() -> impl Strategy<Value = Shape> {
+    prop_oneof![
+        Just(Shape::Random),
+        Just(Shape::Balanced),
+        Just(Shape::RightSpine),
+        Just(Shape::LeftSpine),
+        Just(Shape::Zigzag),
+    ]
+}
+
+// Maps with `size` distinct keys drawn from `key`, built into `shape`
+pub fn maps_with_policy<K, V, P>(
+    key: impl Strategy<Value = K>,
+    value: impl Strategy<Value = V>,
+    size: impl Into<SizeRange>,
+    shape: Shape,
+) -> impl Strategy<Value = BinaryTreeMap<K, V, P>>
+where
+    K: Ord + Clone + Debug + 'static,
+    V: Clone + Debug + 'static,
+    P: BalancePolicy<K, V> + Default + Debug,
+    P::Meta: Debug,
+{
+    btree_map(key, value, size)
+        .prop_map(|entries| entries.into_iter().collect::<Vec<_>>())
+        .prop_flat_map(move |sorted| {
+            let order = match shape {
+                Shape::Random => Just(sorted).prop_shuffle().boxed(),
+                _ => Just(sorted).boxed(),
+            };
+            order.prop_map(move |entries| build(entries, shape))
+        })
+}
+
+pub fn maps<K, V>(
+    key: impl Strategy<Value = K>,
+    value: impl Strategy<Value = V>,
+    size: impl Into<SizeRange>,
+    shape: Shape,
+) -> impl Strategy<Value = BinaryTreeMap<K, V>>
+where
+    K: Ord + Clone + Debug + 'static,
+    V: Clone + Debug + 'static,
+{
+    maps_with_policy::<K, V, Unbalanced>(key, value, size, shape)
+}
+
+// Maps of any shape, picked per case
+pub fn any_shape_maps<K, V>(
+    key: impl Strategy<Value = K> + Clone,
+    value: impl Strategy<Value = V> + Clone,
+    size: impl Into<SizeRange>,
+) -> impl Strategy<Value = BinaryTreeMap<K, V>>
+where
+    K: Ord + Clone + Debug + 'static,
+    V: Clone + Debug + 'static,
+{
+    let size = size.into();
+    shapes().prop_flat_map(move |shape| maps(key.clone(), value.clone(), size.clone(), shape))
+}
+
+fn build<K: Ord, V, P: BalancePolicy<K, V> + Default>(entries: Vec<(K, V)>, shape: Shape) -> BinaryTreeMap<K, V, P> {
+    let mut map = BinaryTreeMap::default();
+    match shape {
+        Shape::Balanced => return BinaryTreeMap::from_sorted_vec(entries),
+        Shape::Random | Shape::RightSpine => {
+            for (key, value) in entries {
+                map.insert(key, value);
+            }
+        },
+        Shape::LeftSpine => {
+            for (key, value) in entries.into_iter().rev() {
+                map.insert(key, value);
+            }
+        },
+        Shape::Zigzag => {
+            let mut entries = entries.into_iter();
+            let mut from_front = true;
+            while let Some((key, value)) = if from_front { entries.next() } else { entries.next_back() } {
+                map.insert(key, value);
+                from_front = !from_front;
+            }
+        },
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Avl;
+
+    proptest! {
+        #[test]
+        fn test_degenerate_shapes_are_paths(
+            map in prop_oneof![Just(Shape::RightSpine), Just(Shape::LeftSpine), Just(Shape::Zigzag)]
+                .prop_flat_map(|shape| maps(any::<u16>(), any::<u8>(), 0..64, shape)),
+        ) {
+            prop_assert!(map.iter().map(|(k, _)| k).is_sorted());
+            prop_assert_eq!(map.height(), map.len());
+        }
+
+        #[test]
+        fn test_sizes_and_policies(
+            spine in maps(0..1000u32, Just(()), 10..20, Shape::LeftSpine),
+            balanced in maps(0..1000u32, Just(()), 10..20, Shape::Balanced),
+            avl in maps_with_policy::<_, _, Avl>(0..1000u32, Just(()), 10..20, Shape::RightSpine),
+        ) {
+            prop_assert!((10..20).contains(&spine.len()));
+            prop_assert_eq!(spine.height(), spine.len());
+            prop_assert!(balanced.is_balanced());
+            prop_assert!(avl.is_balanced());
+        }
+    }
+}
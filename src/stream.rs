@@ -0,0 +1,148 @@
+use crate::node::{Link, Node};
+use crate::{BalancePolicy, BinaryTreeMap, ByteCodec};
+use std::io::{self, Error, ErrorKind, Read, Write};
+
+const MAGIC: &[u8; 4] = b"BTMS";
+const VERSION: u8 = 1;
+
+fn write_len<W: Write>(w: &mut W, scratch: &mut Vec<u8>, len: usize) -> io::Result<()> {
+    scratch.clear();
+    len.encode(scratch);
+    w.write_all(scratch)
+}
+
+// Reads a `usize` varint one byte at a time, failing past ten bytes
+fn read_len<R: Read>(r: &mut R) -> io::Result<usize> {
+    let mut bytes = Vec::new();
+    loop {
+        let mut byte = [0];
+        r.read_exact(&mut byte)?;
+        bytes.push(byte[0]);
+        if byte[0] & 0x80 == 0 || bytes.len() >= 10 {
+            return usize::decode(&mut bytes.as_slice()).map_err(|e| Error::new(ErrorKind::InvalidData, e));
+        }
+    }
+}
+
+impl<K: Ord + ByteCodec, V: ByteCodec, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Streams a header, the entry count and then each entry in key order,
+    // length-prefixed so the reader can decode it from a buffer of its own
+    // size. Only one entry is encoded in memory at a time. The writer is
+    // not flushed.
+    pub fn write_to<W: Write>(&self, mut w: W) -> io::Result<()> {
+        let mut scratch = Vec::new();
+        w.write_all(MAGIC)?;
+        w.write_all(&[VERSION])?;
+        write_len(&mut w, &mut scratch, self.len())?;
+        let mut entry = Vec::new();
+        for (key, value) in self.iter() {
+            entry.clear();
+            key.encode(&mut entry);
+            value.encode(&mut entry);
+            write_len(&mut w, &mut scratch, entry.len())?;
+            w.write_all(&entry)?;
+        }
+        Ok(())
+    }
+
+    // Builds a perfectly balanced tree as the entries arrive, so memory
+    // stays at the map plus one entry. Reads byte by byte for lengths,
+    // so unbuffered sources should be wrapped in a `BufReader`.
+    pub fn read_from<R: Read>(mut r: R) -> io::Result<Self> {
+        let mut header = [0; 5];
+        r.read_exact(&mut header)?;
+        if &header[..4] != MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, "not a map stream"));
+        }
+        if header[4] != VERSION {
+            return Err(Error::new(ErrorKind::InvalidData, "unsupported stream version"));
+        }
+        let count = read_len(&mut r)?;
+        let mut reader = EntryReader { r, buffer: Vec::new() };
+        let map = BinaryTreeMap::from_root(reader.build::<K, V, P>(count)?);
+        if !map.iter().zip(map.iter().skip(1)).all(|((a, _), (b, _))| a < b) {
+            return Err(Error::new(ErrorKind::InvalidData, "keys out of order"));
+        }
+        Ok(map)
+    }
+}
+
+struct EntryReader<R> {
+    r: R,
+    buffer: Vec<u8>,
+}
+
+impl<R: Read> EntryReader<R> {
+    fn next<K: ByteCodec, V: ByteCodec>(&mut self) -> io::Result<(K, V)> {
+        let invalid = |e| Error::new(ErrorKind::InvalidData, e);
+        let len = read_len(&mut self.r)?;
+        self.buffer.clear();
+        (&mut self.r).take(len as u64).read_to_end(&mut self.buffer)?;
+        if self.buffer.len() != len {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+        let mut input = self.buffer.as_slice();
+        let key = K::decode(&mut input).map_err(invalid)?;
+        let value = V::decode(&mut input).map_err(invalid)?;
+        if !input.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidData, "entry longer than its contents"));
+        }
+        Ok((key, value))
+    }
+
+    // Like `build_balanced`, taking the left half first so entries are
+    // consumed in order, but stopping at the first bad entry. Recursion
+    // depth is log2 of the count. Key order is checked by the caller.
+    fn build<K: ByteCodec, V: ByteCodec, P: BalancePolicy<K, V>>(&mut self, count: usize) -> io::Result<Link<K, V, P::Meta>> {
+        if count == 0 {
+            return Ok(None);
+        }
+        let left_count = count / 2;
+        let left = self.build::<K, V, P>(left_count)?;
+        let (key, value) = self.next()?;
+        let right = self.build::<K, V, P>(count - left_count - 1)?;
+        let mut node = Node::leaf::<P>(key, value);
+        node.left = left;
+        node.right = right;
+        node.refresh::<P>();
+        Ok(Some(node))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Avl, BinaryTreeMap};
+    use std::io::ErrorKind;
+
+    #[test]
+    fn test_stream_round_trip_through_a_pipe() {
+        let mut map: BinaryTreeMap<u64, String, Avl> = BinaryTreeMap::default();
+        for key in 0..10_000 {
+            map.insert(key * 7919 % 10_000, format!("{key:x}"));
+        }
+        let (mut reader, writer) = std::io::pipe().unwrap();
+        std::thread::scope(|s| {
+            s.spawn(|| map.write_to(std::io::BufWriter::new(writer)).unwrap());
+            let read = BinaryTreeMap::<u64, String, Avl>::read_from(std::io::BufReader::new(&mut reader)).unwrap();
+            assert!(read.iter().eq(map.iter()));
+            assert!(read.check_invariants().is_ok());
+        });
+    }
+
+    #[test]
+    fn test_rejects_truncated_and_unordered_streams() {
+        let mut map: BinaryTreeMap<u32, u32> = BinaryTreeMap::new();
+        for key in [3, 1, 2] {
+            map.insert(key, key);
+        }
+        let mut bytes = Vec::new();
+        map.write_to(&mut bytes).unwrap();
+        let err = BinaryTreeMap::<u32, u32>::read_from(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+
+        // Swapping the first two entries' keys breaks the ordering
+        bytes.swap(7, 10);
+        let err = BinaryTreeMap::<u32, u32>::read_from(bytes.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
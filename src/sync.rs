@@ -0,0 +1,259 @@
+use crate::{Augmented, BinaryTreeMap, ByteCodec, MapError, MerkleHash, Node};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::{Bound, RangeBounds};
+
+// Ranges at most this large are shipped whole instead of being split again
+const LEAF_ENTRIES: usize = 16;
+
+// Hash of the keys in `start..end`, where `None` leaves that side unbounded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeHash<K> {
+    pub start: Option<K>,
+    pub end: Option<K>,
+    pub hash: u64,
+}
+
+// Every entry the source holds in `start..end`; the target replaces its own
+// entries in that range with them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeEntries<K, V> {
+    pub start: Option<K>,
+    pub end: Option<K>,
+    pub entries: Vec<(K, V)>,
+}
+
+// One leg of the exchange. An empty message ends it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncMessage<K, V> {
+    pub ranges: Vec<RangeHash<K>>,
+    pub entries: Vec<RangeEntries<K, V>>,
+}
+
+impl<K, V> SyncMessage<K, V> {
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty() && self.entries.is_empty()
+    }
+}
+
+fn bounds<'a, K>(start: &'a Option<K>, end: &'a Option<K>) -> (Bound<&'a K>, Bound<&'a K>) {
+    let start = start.as_ref().map_or(Bound::Unbounded, Bound::Included);
+    let end = end.as_ref().map_or(Bound::Unbounded, Bound::Excluded);
+    (start, end)
+}
+
+// Bounds come off the wire, so a range may be empty or inverted; such a
+// range holds no keys and both sides skip it
+fn is_valid<K: Ord>(start: &Option<K>, end: &Option<K>) -> bool {
+    match (start, end) {
+        (Some(start), Some(end)) => start < end,
+        _ => true,
+    }
+}
+
+// Reconciliation makes a target map equal to a source map. The target
+// opens with `sync_request`, the source answers each message with
+// `sync_respond`, and the target feeds each answer to `sync_apply`, whose
+// reply goes back to the source, until either side sends an empty message.
+// Ranges whose hashes agree are dropped; differing ones are halved at the
+// source's median key until they are small enough to ship, so traffic grows
+// with the number of differences times log n rather than with the map.
+impl<K: Ord + ByteCodec + Clone, V: ByteCodec + Clone> BinaryTreeMap<K, V, Augmented<MerkleHash>> {
+    pub fn sync_request(&self) -> SyncMessage<K, V> {
+        let root = RangeHash { start: None, end: None, hash: self.root_hash() };
+        SyncMessage { ranges: vec![root], entries: Vec::new() }
+    }
+
+    pub fn sync_respond(&self, message: &SyncMessage<K, V>) -> SyncMessage<K, V> {
+        let mut reply = SyncMessage { ranges: Vec::new(), entries: Vec::new() };
+        for theirs in message.ranges.iter().filter(|theirs| is_valid(&theirs.start, &theirs.end)) {
+            let range = bounds(&theirs.start, &theirs.end);
+            if self.range_hash(range) == theirs.hash {
+                continue;
+            }
+            let low = theirs.start.as_ref().map_or(0, |key| Node::rank(&self.root, key));
+            let high = theirs.end.as_ref().map_or(self.len(), |key| Node::rank(&self.root, key));
+            if high - low <= LEAF_ENTRIES {
+                let entries = self.range(range).map(|(k, v)| (k.clone(), v.clone())).collect();
+                reply.entries.push(RangeEntries { start: theirs.start.clone(), end: theirs.end.clone(), entries });
+                continue;
+            }
+            let (mid, _) = Node::nth(&self.root, low + (high - low) / 2).expect("median is in range");
+            for (start, end) in [(theirs.start.clone(), Some(mid.clone())), (Some(mid.clone()), theirs.end.clone())] {
+                let hash = self.range_hash(bounds(&start, &end));
+                reply.ranges.push(RangeHash { start, end, hash });
+            }
+        }
+        reply
+    }
+
+    // Applies the shipped entries and answers the source's range hashes
+    // with this map's own, omitting the ranges that already agree. Shipped
+    // entries outside their range are ignored, as are invalid ranges.
+    pub fn sync_apply(&mut self, message: SyncMessage<K, V>) -> SyncMessage<K, V> {
+        for shipped in message.entries.into_iter().filter(|shipped| is_valid(&shipped.start, &shipped.end)) {
+            let range = bounds(&shipped.start, &shipped.end);
+            let stale: Vec<K> = self.range(range).map(|(k, _)| k.clone()).collect();
+            self.delete_many(stale);
+            for (key, value) in shipped.entries {
+                if range.contains(&&key) {
+                    self.insert(key, value);
+                }
+            }
+        }
+        let mut reply = SyncMessage { ranges: Vec::new(), entries: Vec::new() };
+        for theirs in message.ranges.into_iter().filter(|theirs| is_valid(&theirs.start, &theirs.end)) {
+            let hash = self.range_hash(bounds(&theirs.start, &theirs.end));
+            if hash != theirs.hash {
+                reply.ranges.push(RangeHash { hash, ..theirs });
+            }
+        }
+        reply
+    }
+}
+
+fn encode_bound<K: ByteCodec>(bound: &Option<K>, out: &mut Vec<u8>) {
+    bound.is_some().encode(out);
+    if let Some(key) = bound {
+        key.encode(out);
+    }
+}
+
+fn decode_bound<K: ByteCodec>(input: &mut &[u8]) -> Result<Option<K>, MapError> {
+    if bool::decode(input)? {
+        K::decode(input).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+// Counts come from the wire, so they are never trusted for preallocation
+fn decode_seq<T>(input: &mut &[u8], mut item: impl FnMut(&mut &[u8]) -> Result<T, MapError>) -> Result<Vec<T>, MapError> {
+    let count = usize::decode(input)?;
+    let mut items = Vec::new();
+    for _ in 0..count {
+        items.push(item(input)?);
+    }
+    Ok(items)
+}
+
+impl<K: ByteCodec, V: ByteCodec> ByteCodec for SyncMessage<K, V> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.ranges.len().encode(out);
+        for range in &self.ranges {
+            encode_bound(&range.start, out);
+            encode_bound(&range.end, out);
+            range.hash.encode(out);
+        }
+        self.entries.len().encode(out);
+        for shipped in &self.entries {
+            encode_bound(&shipped.start, out);
+            encode_bound(&shipped.end, out);
+            shipped.entries.len().encode(out);
+            for (key, value) in &shipped.entries {
+                key.encode(out);
+                value.encode(out);
+            }
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, MapError> {
+        let ranges = decode_seq(input, |input| {
+            Ok(RangeHash { start: decode_bound(input)?, end: decode_bound(input)?, hash: u64::decode(input)? })
+        })?;
+        let entries = decode_seq(input, |input| {
+            let start = decode_bound(input)?;
+            let end = decode_bound(input)?;
+            let entries = decode_seq(input, |input| Ok((K::decode(input)?, V::decode(input)?)))?;
+            Ok(RangeEntries { start, end, entries })
+        })?;
+        Ok(SyncMessage { ranges, entries })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ByteCodec, MerkleTreeMap, RangeEntries, RangeHash, SyncMessage};
+
+    // Runs the exchange with every message going through the wire format,
+    // returning how many entries were shipped
+    fn reconcile(source: &MerkleTreeMap<u32, u32>, target: &mut MerkleTreeMap<u32, u32>) -> usize {
+        fn wire(message: SyncMessage<u32, u32>) -> SyncMessage<u32, u32> {
+            let mut bytes = Vec::new();
+            message.encode(&mut bytes);
+            SyncMessage::decode(&mut bytes.as_slice()).unwrap()
+        }
+        let mut shipped = 0;
+        let mut request = wire(target.sync_request());
+        loop {
+            let response = wire(source.sync_respond(&request));
+            if response.is_empty() {
+                return shipped;
+            }
+            shipped += response.entries.iter().map(|range| range.entries.len()).sum::<usize>();
+            request = wire(target.sync_apply(response));
+            if request.is_empty() {
+                return shipped;
+            }
+        }
+    }
+
+    #[test]
+    fn test_divergent_replicas_reconcile() {
+        let mut source = MerkleTreeMap::default();
+        let mut target = MerkleTreeMap::default();
+        for key in 0..1000u32 {
+            source.insert(key * 7 % 1000, key * 7 % 1000);
+            target.insert(key, key);
+        }
+        source.insert(17, 0);
+        source.insert(5000, 1);
+        target.insert(640, 640_000);
+        target.delete(&901).unwrap();
+        target.insert(2500, 2);
+
+        let shipped = reconcile(&source, &mut target);
+        assert!(shipped < 100, "shipped {shipped} entries");
+        assert_eq!(target.root_hash(), source.root_hash());
+        assert!(target.iter().eq(source.iter()));
+    }
+
+    #[test]
+    fn test_malformed_ranges_are_skipped() {
+        let mut map = MerkleTreeMap::default();
+        for key in 0..1000u32 {
+            map.insert(key, key);
+        }
+        let inverted = RangeHash { start: Some(900), end: Some(10), hash: 0 };
+        let request = SyncMessage { ranges: vec![inverted.clone()], entries: Vec::new() };
+        assert!(map.sync_respond(&request).is_empty());
+
+        // Entries outside the range they were shipped for are dropped
+        let shipped = RangeEntries { start: Some(10), end: Some(20), entries: vec![(15, 0), (500, 0)] };
+        let inverted_entries = RangeEntries { start: Some(30), end: Some(30), entries: vec![(30, 0)] };
+        let message = SyncMessage { ranges: vec![inverted], entries: vec![shipped, inverted_entries] };
+        assert!(map.sync_apply(message).is_empty());
+        assert_eq!(map.len(), 991);
+        assert_eq!(map.get(&15), Ok(&0));
+        assert_eq!(map.get(&500), Ok(&500));
+        assert_eq!(map.get(&30), Ok(&30));
+    }
+
+    #[test]
+    fn test_identical_maps_exchange_nothing() {
+        let mut source = MerkleTreeMap::default();
+        for key in 0..100u32 {
+            source.insert(key, key);
+        }
+        let mut target = MerkleTreeMap::default();
+        for key in (0..100u32).rev() {
+            target.insert(key, key);
+        }
+        assert!(source.sync_respond(&target.sync_request()).is_empty());
+        assert_eq!(reconcile(&source, &mut target), 0);
+
+        let mut empty = MerkleTreeMap::default();
+        assert_eq!(reconcile(&source, &mut empty), 100);
+        assert!(empty.iter().eq(source.iter()));
+    }
+}
@@ -0,0 +1,94 @@
+use crate::node::Node;
+use crate::{BalancePolicy, BinaryTreeMap};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+// Manual rotations for demonstrating balancing one step at a time. They keep
+// the ordering, sizes and policy metadata correct but ignore the policy's
+// balance rules, so rotating an `Avl` map by hand can leave it unbalanced.
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Lifts the right child of `key`'s node into its place. Does nothing and
+    // returns false when `key` is absent or its node has no right child.
+    pub fn rotate_left_at(&mut self, key: &K) -> bool {
+        self.rotate_at(key, |node| node.right.is_some(), Node::rotate_left::<P>)
+    }
+
+    // Lifts the left child of `key`'s node into its place
+    pub fn rotate_right_at(&mut self, key: &K) -> bool {
+        self.rotate_at(key, |node| node.left.is_some(), Node::rotate_right::<P>)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn rotate_at(
+        &mut self,
+        key: &K,
+        can_rotate: fn(&Node<K, V, P::Meta>) -> bool,
+        rotate: fn(Box<Node<K, V, P::Meta>>) -> Box<Node<K, V, P::Meta>>,
+    ) -> bool {
+        let mut path = Vec::new();
+        let mut current = self.root.take();
+        while let Some(mut n) = current {
+            match key.cmp(&n.key) {
+                core::cmp::Ordering::Equal => {
+                    let applied = can_rotate(&n);
+                    if applied {
+                        n = rotate(n);
+                        self.observer.notify(|o| o.on_rotate(&n.key));
+                    }
+                    // Ancestors keep their sizes but may need new metadata
+                    self.root = Self::reattach(path, Some(n), |n| n);
+                    return applied;
+                },
+                core::cmp::Ordering::Less => {
+                    current = n.left.take();
+                    path.push((n, core::cmp::Ordering::Less));
+                },
+                core::cmp::Ordering::Greater => {
+                    current = n.right.take();
+                    path.push((n, core::cmp::Ordering::Greater));
+                },
+            }
+        }
+        self.root = Self::reattach(path, None, |n| n);
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Avl, BinaryTreeMap, InvariantViolation, ViolationKind};
+
+    #[test]
+    fn test_rotations_round_trip() {
+        let mut map = BinaryTreeMap::new();
+        for key in [1, 2, 3] {
+            map.insert(key, ());
+        }
+        // A right spine 1 - 2 - 3 becomes 2 over 1 and 3
+        assert!(map.rotate_left_at(&1));
+        assert_eq!(map.format_tree(), "2: ()\n├── 1: ()\n└── 3: ()\n");
+        assert!(map.rotate_right_at(&2));
+        assert_eq!(map.height(), 3);
+        assert_eq!(map.check_invariants(), Ok(()));
+        assert_eq!(map.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_refused_rotations() {
+        let mut map = BinaryTreeMap::with_policy(Avl);
+        for key in 0..7 {
+            map.insert(key, ());
+        }
+        assert!(!map.rotate_left_at(&6));
+        assert!(!map.rotate_right_at(&0));
+        assert!(!map.rotate_left_at(&99));
+        // The rotation unbalances 6, but the root's stored height above it
+        // is refreshed, so the first violation found is at 6 itself
+        assert!(map.rotate_left_at(&5));
+        assert_eq!(map.height(), 4);
+        assert_eq!(
+            map.check_invariants(),
+            Err(InvariantViolation { position: 6, kind: ViolationKind::Policy("subtree heights differ by more than one") })
+        );
+    }
+}
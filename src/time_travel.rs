@@ -0,0 +1,128 @@
+use crate::persistent::BinaryTreeMap;
+use crate::{MapError, VersionedTreeMap};
+use alloc::vec;
+use alloc::vec::Vec;
+
+// Map that keeps every version it has been through, for stepping back
+// through a sequence of operations in a test. Each mutation gets the next
+// tick and a new version of a `VersionedTreeMap`, which shares all but one
+// path with its predecessor, so the history costs O(log n) per mutation.
+// Tick 0 is the empty map.
+//
+// Rewinding discards the versions after the target, but ticks keep
+// counting from the newest ever handed out, so a tick never names two
+// states: after a rewind to 3 from 7, the next mutation is tick 8 and
+// `state_at(5)` is None.
+#[derive(Debug, Clone)]
+pub struct TimeTravel<K, V> {
+    versions: VersionedTreeMap<K, V>,
+    // The tick that made each version, indexed by version, so ascending
+    ticks: Vec<u64>,
+    next_tick: u64,
+}
+
+impl<K: Ord + Clone, V: Clone> Default for TimeTravel<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> TimeTravel<K, V> {
+    pub fn new() -> Self {
+        TimeTravel { versions: VersionedTreeMap::new(), ticks: vec![0], next_tick: 1 }
+    }
+
+    // Tick of the current state
+    pub fn tick(&self) -> u64 {
+        self.ticks[self.versions.version()]
+    }
+
+    pub fn current(&self) -> &BinaryTreeMap<K, V> {
+        self.versions.at(self.versions.version()).expect("the current version exists")
+    }
+
+    pub fn get(&self, key: &K) -> Result<&V, MapError> {
+        self.versions.get(key)
+    }
+
+    // Returns the tick of the new state
+    pub fn insert(&mut self, key: K, value: V) -> u64 {
+        self.versions.insert(key, value);
+        self.push_tick()
+    }
+
+    // Deleting a missing key is not a mutation and takes no tick
+    pub fn delete(&mut self, key: &K) -> Result<u64, MapError> {
+        self.versions.delete(key)?;
+        Ok(self.push_tick())
+    }
+
+    fn push_tick(&mut self) -> u64 {
+        let tick = self.next_tick;
+        self.next_tick += 1;
+        self.ticks.push(tick);
+        tick
+    }
+
+    // The state `tick` made, or None for a tick that was discarded by a
+    // rewind or not handed out yet
+    pub fn state_at(&self, tick: u64) -> Option<&BinaryTreeMap<K, V>> {
+        let version = self.ticks.binary_search(&tick).ok()?;
+        self.versions.at(version)
+    }
+
+    // Makes the newest state no later than `tick` current, discarding the
+    // versions after it. Ticks not handed out yet leave the map as it is.
+    pub fn rewind_to(&mut self, tick: u64) {
+        let keep = self.ticks.partition_point(|t| *t <= tick);
+        self.ticks.truncate(keep);
+        self.versions.truncate(keep - 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(map: &BinaryTreeMap<u32, u32>) -> Vec<u32> {
+        map.iter().map(|(k, _)| *k).collect()
+    }
+
+    #[test]
+    fn test_state_at_earlier_ticks() {
+        let mut history = TimeTravel::new();
+        assert_eq!(history.insert(5, 50), 1);
+        assert_eq!(history.insert(3, 30), 2);
+        assert_eq!(history.delete(&9), Err(MapError::KeyNotFound));
+        assert_eq!(history.delete(&5), Ok(3));
+        assert_eq!(history.insert(3, 31), 4);
+
+        assert!(history.state_at(0).unwrap().is_empty());
+        assert_eq!(keys(history.state_at(2).unwrap()), [3, 5]);
+        assert_eq!(history.state_at(3).unwrap().get(&3), Ok(&30));
+        assert_eq!(history.state_at(4).unwrap().get(&3), Ok(&31));
+        assert!(history.state_at(5).is_none());
+        assert_eq!(history.tick(), 4);
+    }
+
+    #[test]
+    fn test_rewind_keeps_ticks_unique() {
+        let mut history = TimeTravel::new();
+        for key in 0..7 {
+            history.insert(key, key);
+        }
+        history.rewind_to(3);
+        assert_eq!(history.tick(), 3);
+        assert_eq!(keys(history.current()), [0, 1, 2]);
+        let fork = history.clone();
+        assert_eq!(history.insert(10, 10), 8);
+        assert!(history.state_at(5).is_none());
+        assert_eq!(keys(history.state_at(3).unwrap()), [0, 1, 2]);
+        assert_eq!(keys(history.state_at(8).unwrap()), [0, 1, 2, 10]);
+
+        // Rewinding past the present changes nothing
+        history.rewind_to(100);
+        assert_eq!(history.tick(), 8);
+        assert_eq!((fork.tick(), fork.state_at(8).is_none()), (3, true));
+    }
+}
@@ -0,0 +1,52 @@
+use crate::{persistent, BalancePolicy, BinaryTreeMap};
+
+// There is deliberately no copy-on-write `snapshot` of the mutable map.
+// Its nodes are uniquely owned boxes that every mutation, rotation and
+// bulk operation edits in place; sharing them would mean reference-counted
+// links throughout the crate and `K: Clone, V: Clone` on every mutating
+// method. Code that needs cheap versions should hold a
+// `persistent::BinaryTreeMap` from the start.
+impl<K: Ord + Clone, V: Clone, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Copies the entries into a balanced persistent map. Building it is
+    // O(n); after that the copy is independent of this map, and cloning it
+    // is O(1).
+    pub fn to_persistent(&self) -> persistent::BinaryTreeMap<K, V> {
+        let mut entries = self.iter().map(|(key, value)| (key.clone(), value.clone()));
+        persistent::BinaryTreeMap::from_root(persistent::from_sorted(&mut entries, self.len()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Avl, BinaryTreeMap, MapError};
+
+    #[test]
+    fn test_persistent_copy_survives_mutation() {
+        let mut map = BinaryTreeMap::new();
+        for key in [4, 2, 6, 1, 3] {
+            map.insert(key, key * 10);
+        }
+        let copy = map.to_persistent();
+        let clone = copy.clone();
+        map.insert(2, 0);
+        map.delete(&4).unwrap();
+        map.insert(9, 90);
+
+        assert_eq!(copy.len(), 5);
+        assert_eq!(copy.get(&2), Ok(&20));
+        assert_eq!(clone.get(&4), Ok(&40));
+        assert_eq!(clone.get(&9), Err(MapError::KeyNotFound));
+        assert!(clone.ptr_eq(&copy));
+    }
+
+    #[test]
+    fn test_persistent_copy_of_balanced_map_derives_new_versions() {
+        let map: BinaryTreeMap<u32, u32, Avl> = BinaryTreeMap::from_sorted_vec((0..1000).map(|k| (k, k)).collect());
+        let copy = map.to_persistent();
+        let next = copy.insert(1000, 1000);
+
+        assert_eq!(next.len(), 1001);
+        assert_eq!(copy.len(), 1000);
+        assert!(!copy.is_empty());
+    }
+}
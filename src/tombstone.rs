@@ -0,0 +1,118 @@
+use crate::{Avl, BalancePolicy, BinaryTreeMap, MapError};
+
+// Map whose deletes leave a tombstone in the deleted entry's node instead
+// of unlinking it, so a delete costs one search and never rebalances.
+// Inserting the key again revives the node. Tombstones still take up room
+// and lengthen searches until `vacuum` drops them all in one O(n) rebuild,
+// which the caller can schedule at a quiet moment; `tombstones` says how
+// many are waiting.
+pub struct TombstoneTreeMap<K, V, P: BalancePolicy<K, Option<V>> = Avl> {
+    map: BinaryTreeMap<K, Option<V>, P>,
+    tombstones: usize,
+}
+
+impl<K: Ord, V> Default for TombstoneTreeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> TombstoneTreeMap<K, V> {
+    pub fn new() -> Self {
+        Self::with_policy(Avl)
+    }
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, Option<V>>> TombstoneTreeMap<K, V, P> {
+    pub fn with_policy(policy: P) -> Self {
+        TombstoneTreeMap { map: BinaryTreeMap::with_policy(policy), tombstones: 0 }
+    }
+
+    // Live entries only
+    pub fn len(&self) -> usize {
+        self.map.len() - self.tombstones
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn tombstones(&self) -> usize {
+        self.tombstones
+    }
+
+    pub fn get(&self, key: &K) -> Result<&V, MapError> {
+        self.map.get(key)?.as_ref().ok_or(MapError::KeyNotFound)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        let mut revived = false;
+        let mut value = Some(value);
+        let found = self.map.update(&key, |slot| {
+            revived = slot.is_none();
+            *slot = value.take();
+        });
+        if !found {
+            self.map.insert(key, value);
+        } else if revived {
+            self.tombstones -= 1;
+        }
+    }
+
+    pub fn delete(&mut self, key: &K) -> Result<V, MapError> {
+        let mut removed = None;
+        self.map.update(key, |slot| removed = slot.take());
+        let value = removed.ok_or(MapError::KeyNotFound)?;
+        self.tombstones += 1;
+        Ok(value)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map.iter().filter_map(|(key, value)| value.as_ref().map(|value| (key, value)))
+    }
+
+    // Rebuilds the tree balanced from the live entries only
+    pub fn vacuum(&mut self)
+    where
+        P: Default,
+    {
+        let map = core::mem::take(&mut self.map);
+        self.map = map.filter_map(|_, value| value.map(Some));
+        self.tombstones = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_delete_leaves_tombstones_until_vacuum() {
+        let mut map = TombstoneTreeMap::new();
+        for key in 0..100u32 {
+            map.insert(key, key * 2);
+        }
+        let height = map.map.height();
+        for key in (0..100).filter(|k| k % 4 != 0) {
+            assert_eq!(map.delete(&key), Ok(key * 2));
+        }
+        assert_eq!(map.delete(&1), Err(MapError::KeyNotFound));
+        assert_eq!(map.delete(&1000), Err(MapError::KeyNotFound));
+        assert_eq!((map.len(), map.tombstones()), (25, 75));
+        assert_eq!(map.map.height(), height);
+        assert_eq!(map.get(&3), Err(MapError::KeyNotFound));
+
+        // Reinserting revives the node in place
+        map.insert(3, 33);
+        assert_eq!((map.len(), map.tombstones()), (26, 74));
+        assert_eq!(map.get(&3), Ok(&33));
+
+        map.vacuum();
+        assert_eq!((map.len(), map.tombstones(), map.map.len()), (26, 0, 26));
+        assert!(map.map.height() < height);
+        assert!(map.map.check_invariants().is_ok());
+        let keys: Vec<u32> = map.iter().map(|(k, _)| *k).collect();
+        assert_eq!(keys[..3], [0, 3, 4]);
+    }
+}
@@ -0,0 +1,195 @@
+use crate::node::{Link, Node};
+use crate::{stats, BalancePolicy, BinaryTreeMap};
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Converts the values without comparing keys: the result has the same
+    // shape, with only the policy metadata recomputed for the new values
+    pub fn map_values<W>(self, mut f: impl FnMut(V) -> W) -> BinaryTreeMap<K, W, P>
+    where
+        P: BalancePolicy<K, W>,
+    {
+        self.map_entries(|key, value| (key, f(value)))
+    }
+
+    // Converts the keys keeping the tree shape, in O(n) with no comparisons.
+    // `f` must preserve order: `a < b` must imply `f(a) < f(b)`, or the
+    // result is not a valid search tree. Debug builds check this.
+    pub fn map_keys<L: Ord>(self, mut f: impl FnMut(K) -> L) -> BinaryTreeMap<L, V, P>
+    where
+        P: BalancePolicy<L, V>,
+    {
+        let map = self.map_entries(|key, value| (f(key), value));
+        debug_assert!(map.check_invariants().is_ok(), "map_keys requires an order-preserving function");
+        map
+    }
+
+    // Keeps the entries `f` maps to `Some`, converted, in one ordered pass.
+    // The survivors are rebuilt into a balanced tree.
+    pub fn filter_map<W>(self, mut f: impl FnMut(&K, V) -> Option<W>) -> BinaryTreeMap<K, W, P>
+    where
+        P: BalancePolicy<K, W>,
+    {
+        let kept: Vec<(K, W)> = self
+            .into_sorted_vec()
+            .into_iter()
+            .filter_map(|(key, value)| f(&key, value).map(|value| (key, value)))
+            .collect();
+        let count = kept.len();
+        BinaryTreeMap::from_root(BinaryTreeMap::<K, W, P>::build_balanced(&mut kept.into_iter(), count))
+    }
+
+    // Splits the map into the entries `pred` accepts and those it rejects.
+    // Keys and values are moved, never cloned, and both halves are rebuilt
+    // balanced.
+    pub fn partition(self, mut pred: impl FnMut(&K, &V) -> bool) -> (Self, Self) {
+        let (accepted, rejected): (Vec<_>, Vec<_>) = self.into_sorted_vec().into_iter().partition(|(key, value)| pred(key, value));
+        let (accepted_count, rejected_count) = (accepted.len(), rejected.len());
+        (
+            Self::from_root(Self::build_balanced(&mut accepted.into_iter(), accepted_count)),
+            Self::from_root(Self::build_balanced(&mut rejected.into_iter(), rejected_count)),
+        )
+    }
+
+    // Rebuilds the tree node for node with `f` applied to each entry in key
+    // order. Children are built before their parent, from an explicit stack.
+    fn map_entries<L: Ord, W, Q: BalancePolicy<L, W>>(mut self, mut f: impl FnMut(K, V) -> (L, W)) -> BinaryTreeMap<L, W, Q> {
+        enum Visit<K, V, M> {
+            Enter(Link<K, V, M>),
+            Apply(K, V),
+            Exit,
+        }
+        let mut pending = vec![Visit::Enter(self.root.take())];
+        let mut entries = Vec::new();
+        let mut built: Vec<Link<L, W, Q::Meta>> = Vec::new();
+        while let Some(visit) = pending.pop() {
+            match visit {
+                Visit::Enter(None) => built.push(None),
+                Visit::Enter(Some(mut node)) => {
+                    P::push_down(&mut node);
+                    let Node { key, value, left, right, .. } = *node;
+                    pending.push(Visit::Exit);
+                    pending.push(Visit::Enter(right));
+                    pending.push(Visit::Apply(key, value));
+                    pending.push(Visit::Enter(left));
+                },
+                Visit::Apply(key, value) => entries.push(f(key, value)),
+                Visit::Exit => {
+                    let right = built.pop().expect("right subtree was built");
+                    let left = built.pop().expect("left subtree was built");
+                    let (key, value) = entries.pop().expect("entry was mapped");
+                    stats::count_allocation();
+                    let mut node = Box::new(Node { key, value, size: 1, meta: Default::default(), left, right });
+                    node.refresh::<Q>();
+                    built.push(Some(node));
+                },
+            }
+        }
+        BinaryTreeMap::from_root(built.pop().expect("root was built"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Augment, AugmentedTreeMap, Avl};
+
+    #[test]
+    fn test_map_values_keeps_shape() {
+        let mut map = BinaryTreeMap::new();
+        for key in [5, 2, 8, 1, 3, 9] {
+            map.insert(key, key * 10);
+        }
+        let before = map.format_tree().replace('0', "");
+        let mapped = map.map_values(|v| v / 10);
+        assert_eq!(mapped.format_tree(), before);
+
+        let mut avl = BinaryTreeMap::with_policy(Avl);
+        for key in 0..100 {
+            avl.insert(key, key);
+        }
+        let strings = avl.map_values(|v| v.to_string());
+        assert_eq!(strings.get(&42), Ok(&"42".to_string()));
+        assert_eq!(strings.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    fn test_map_values_recomputes_summaries() {
+        struct Total;
+
+        impl<K, V: Copy + Into<u64>> Augment<K, V> for Total {
+            type Summary = u64;
+
+            fn summarize(_: &K, value: &V) -> u64 {
+                (*value).into()
+            }
+
+            fn combine(left: &u64, right: &u64) -> u64 {
+                left + right
+            }
+        }
+
+        let mut map: AugmentedTreeMap<u32, u8, Total> = AugmentedTreeMap::default();
+        for key in 1..=10 {
+            map.insert(key, 1);
+        }
+        let doubled: AugmentedTreeMap<u32, u32, Total> = map.map_values(|v| u32::from(v) * 2);
+        assert_eq!(doubled.summary(), 20);
+        assert_eq!(doubled.range_fold(3..=5), 6);
+    }
+
+    #[test]
+    fn test_filter_map_rebuilds_balanced() {
+        // Sorted inserts leave an unbalanced map as a spine
+        let mut map = BinaryTreeMap::new();
+        for key in 0..1000 {
+            map.insert(key, key);
+        }
+        let evens = map.filter_map(|key, value| (key % 2 == 0).then(|| value.to_string()));
+        assert_eq!(evens.len(), 500);
+        assert_eq!(evens.get(&998), Ok(&"998".to_string()));
+        assert_eq!(evens.get(&999), Err(crate::MapError::KeyNotFound));
+        assert_eq!(evens.stats().height, 9);
+    }
+
+    #[test]
+    fn test_partition_moves_entries() {
+        // Not `Clone`, so the entries can only have been moved
+        struct Job(u32);
+
+        let mut map = BinaryTreeMap::new();
+        for id in 0..100 {
+            map.insert(id, Job(id));
+        }
+        let (urgent, rest) = map.partition(|_, job| job.0 % 10 == 0);
+        assert_eq!(urgent.iter().map(|(_, job)| job.0).collect::<Vec<_>>(), [0, 10, 20, 30, 40, 50, 60, 70, 80, 90]);
+        assert_eq!(rest.len(), 90);
+        assert_eq!(rest.stats().height, 7);
+        assert!(rest.get(&10).is_err());
+    }
+
+    #[test]
+    fn test_map_keys_rescales_timestamps() {
+        let mut map = BinaryTreeMap::with_policy(Avl);
+        for seconds in [30u64, 10, 50, 20, 40] {
+            map.insert(seconds, seconds / 10);
+        }
+        let millis = map.map_keys(|seconds| seconds * 1000);
+        assert_eq!(millis.get(&20_000), Ok(&2));
+        assert_eq!(millis.iter().map(|(k, _)| *k).collect::<Vec<_>>(), [10_000, 20_000, 30_000, 40_000, 50_000]);
+        assert_eq!(millis.check_invariants(), Ok(()));
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "order-preserving")]
+    fn test_map_keys_rejects_reordering() {
+        let mut map = BinaryTreeMap::new();
+        for key in [2i32, 1, 3] {
+            map.insert(key, ());
+        }
+        let _ = map.map_keys(|key| -key);
+    }
+}
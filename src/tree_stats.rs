@@ -0,0 +1,84 @@
+use crate::{BalancePolicy, BinaryTreeMap};
+use alloc::vec::Vec;
+
+// Shape summary gathered in one pass. Depths count edges from the root, so
+// the root is at depth 0 and `height` is one more than the deepest depth.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeStats {
+    pub nodes: usize,
+    pub leaves: usize,
+    pub height: usize,
+    // Mean depth over all nodes; 0.0 for an empty map. A balanced tree keeps
+    // this near log2(nodes), a degenerate one near nodes / 2.
+    pub average_depth: f64,
+    // Number of nodes at each depth, indexed by depth
+    pub depth_histogram: Vec<usize>,
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    pub fn stats(&self) -> TreeStats {
+        let mut leaves = 0;
+        let mut depth_total = 0;
+        let mut depth_histogram: Vec<usize> = Vec::new();
+        let mut pending: Vec<_> = self.root.as_deref().map(|root| (root, 0)).into_iter().collect();
+        while let Some((node, depth)) = pending.pop() {
+            if depth_histogram.len() == depth {
+                depth_histogram.push(0);
+            }
+            depth_histogram[depth] += 1;
+            depth_total += depth;
+            if node.left.is_none() && node.right.is_none() {
+                leaves += 1;
+            }
+            pending.extend(node.left().map(|left| (left, depth + 1)));
+            pending.extend(node.right().map(|right| (right, depth + 1)));
+        }
+        let nodes = self.len();
+        TreeStats {
+            nodes,
+            leaves,
+            height: depth_histogram.len(),
+            average_depth: if nodes == 0 { 0.0 } else { depth_total as f64 / nodes as f64 },
+            depth_histogram,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_of_small_tree() {
+        //       4
+        //     2   6
+        //    1     7
+        //            8
+        let mut map = BinaryTreeMap::new();
+        for key in [4, 2, 6, 1, 7, 8] {
+            map.insert(key, ());
+        }
+        let stats = map.stats();
+        assert_eq!(stats.nodes, 6);
+        assert_eq!(stats.leaves, 2);
+        assert_eq!(stats.height, map.height());
+        assert_eq!(stats.depth_histogram, vec![1, 2, 2, 1]);
+        assert_eq!(stats.average_depth, 9.0 / 6.0);
+    }
+
+    #[test]
+    fn test_degenerate_and_empty() {
+        let mut map = BinaryTreeMap::new();
+        for key in 0..100 {
+            map.insert(key, ());
+        }
+        let stats = map.stats();
+        assert_eq!(stats.leaves, 1);
+        assert_eq!(stats.depth_histogram, vec![1; 100]);
+        assert_eq!(stats.average_depth, 49.5);
+
+        let empty = BinaryTreeMap::<u8, ()>::new().stats();
+        assert_eq!((empty.nodes, empty.height, empty.average_depth), (0, 0, 0.0));
+        assert!(empty.depth_histogram.is_empty());
+    }
+}
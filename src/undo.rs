@@ -0,0 +1,167 @@
+use crate::{Avl, BalancePolicy, BinaryTreeMap, MapError, Op};
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+// Map that remembers, for each mutation, the operation that reverses it.
+// `undo` applies the newest inverse and keeps the inverse of that for
+// `redo`; any new mutation clears the redo history. At most `depth`
+// mutations can be undone, the oldest being forgotten first.
+pub struct UndoableTreeMap<K, V, P: BalancePolicy<K, V> = Avl> {
+    map: BinaryTreeMap<K, V, P>,
+    undo: VecDeque<Op<K, V>>,
+    redo: Vec<Op<K, V>>,
+    depth: usize,
+}
+
+impl<K: Ord + Clone, V> UndoableTreeMap<K, V> {
+    pub fn new(depth: usize) -> Self {
+        Self::with_policy(depth, Avl)
+    }
+}
+
+impl<K: Ord + Clone, V, P: BalancePolicy<K, V>> UndoableTreeMap<K, V, P> {
+    pub fn with_policy(depth: usize, policy: P) -> Self {
+        UndoableTreeMap { map: BinaryTreeMap::with_policy(policy), undo: VecDeque::new(), redo: Vec::new(), depth }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn get(&self, key: &K) -> Result<&V, MapError> {
+        self.map.get(key)
+    }
+
+    pub fn map(&self) -> &BinaryTreeMap<K, V, P> {
+        &self.map
+    }
+
+    pub fn into_map(self) -> BinaryTreeMap<K, V, P> {
+        self.map
+    }
+
+    // Mutations that `undo` and `redo` can currently step through
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        let inverse = self.apply(Op::Insert(key, value));
+        self.record(inverse);
+    }
+
+    // Deleting a missing key changes nothing, so nothing is recorded. The
+    // history keeps the removed value, so the caller gets a copy.
+    pub fn delete(&mut self, key: &K) -> Result<V, MapError>
+    where
+        V: Clone,
+    {
+        let value = self.map.delete(key)?;
+        self.record(Some(Op::Insert(key.clone(), value.clone())));
+        Ok(value)
+    }
+
+    // Returns false when there is nothing to undo
+    pub fn undo(&mut self) -> bool {
+        let Some(op) = self.undo.pop_back() else {
+            return false;
+        };
+        let inverse = self.apply(op).expect("inverse operations always apply");
+        self.redo.push(inverse);
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some(op) = self.redo.pop() else {
+            return false;
+        };
+        let inverse = self.apply(op).expect("inverse operations always apply");
+        self.push_undo(inverse);
+        true
+    }
+
+    // Performs `op` and returns the operation undoing it, if it changed
+    // anything
+    fn apply(&mut self, op: Op<K, V>) -> Option<Op<K, V>> {
+        match op {
+            Op::Insert(key, value) => match self.map.replace(key.clone(), value) {
+                Some((old_key, old_value)) => Some(Op::Insert(old_key, old_value)),
+                None => Some(Op::Delete(key)),
+            },
+            Op::Delete(key) => self.map.delete(&key).ok().map(|value| Op::Insert(key, value)),
+        }
+    }
+
+    fn record(&mut self, inverse: Option<Op<K, V>>) {
+        if let Some(inverse) = inverse {
+            self.redo.clear();
+            self.push_undo(inverse);
+        }
+    }
+
+    fn push_undo(&mut self, inverse: Op<K, V>) {
+        if self.depth == 0 {
+            return;
+        }
+        if self.undo.len() == self.depth {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(inverse);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(map: &UndoableTreeMap<u32, &'static str>) -> Vec<(u32, &'static str)> {
+        map.map().iter().map(|(k, v)| (*k, *v)).collect()
+    }
+
+    #[test]
+    fn test_undo_and_redo_walk_the_history() {
+        let mut map = UndoableTreeMap::new(10);
+        map.insert(1, "one");
+        map.insert(2, "two");
+        map.insert(1, "uno");
+        assert_eq!(map.delete(&2), Ok("two"));
+        assert_eq!(map.delete(&2), Err(MapError::KeyNotFound));
+        assert_eq!(map.undo_len(), 4);
+
+        assert!(map.undo());
+        assert_eq!(entries(&map), [(1, "uno"), (2, "two")]);
+        assert!(map.undo());
+        assert_eq!(entries(&map), [(1, "one"), (2, "two")]);
+        assert!(map.redo());
+        assert_eq!(entries(&map), [(1, "uno"), (2, "two")]);
+        assert!(map.undo() && map.undo() && map.undo());
+        assert!(map.is_empty());
+        assert!(!map.undo());
+
+        // A new mutation forgets what could have been redone
+        assert!(map.redo());
+        map.insert(3, "three");
+        assert_eq!(map.redo_len(), 0);
+        assert!(!map.redo());
+        assert_eq!(entries(&map), [(1, "one"), (3, "three")]);
+    }
+
+    #[test]
+    fn test_history_depth_drops_oldest() {
+        let mut map = UndoableTreeMap::new(2);
+        for key in 0..5 {
+            map.insert(key, "x");
+        }
+        assert!(map.undo() && map.undo());
+        assert!(!map.undo());
+        assert_eq!(entries(&map), [(0, "x"), (1, "x"), (2, "x")]);
+    }
+}
@@ -0,0 +1,177 @@
+use crate::bytes::preorder;
+use crate::{Avl, BinaryTreeMap, MapError};
+use alloc::sync::{Arc, Weak};
+use core::ops::Deref;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+// A value of a `StableTreeMap` in its own allocation, which stays put
+// while the map rebalances, so its address is fixed for as long as the map
+// or any `Pinned` handle to it is alive
+#[derive(Debug)]
+pub struct Pinned<V> {
+    value: V,
+    // Set once the map lets go of the value
+    removed: AtomicBool,
+}
+
+impl<V> Deref for Pinned<V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        &self.value
+    }
+}
+
+// Non-owning handle to a map entry's value. `upgrade` succeeds exactly
+// while the map still holds that value: deleting the key, or inserting a
+// new value for it, invalidates every handle to the old one, even if an
+// upgraded `Pinned` keeps the old value's memory alive. Dropping the map
+// invalidates every handle the same way.
+#[derive(Debug)]
+pub struct ValueRef<V> {
+    entry: Weak<Pinned<V>>,
+}
+
+impl<V> Clone for ValueRef<V> {
+    fn clone(&self) -> Self {
+        ValueRef { entry: self.entry.clone() }
+    }
+}
+
+impl<V> ValueRef<V> {
+    pub fn upgrade(&self) -> Result<Arc<Pinned<V>>, MapError> {
+        let entry = self.entry.upgrade().ok_or(MapError::KeyNotFound)?;
+        if entry.removed.load(Ordering::Acquire) {
+            return Err(MapError::KeyNotFound);
+        }
+        Ok(entry)
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.upgrade().is_ok()
+    }
+}
+
+// Map whose values can be referred to by `ValueRef` across mutations
+pub struct StableTreeMap<K, V> {
+    map: BinaryTreeMap<K, Arc<Pinned<V>>, Avl>,
+}
+
+impl<K: Ord, V> Default for StableTreeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn release<V>(entry: &Pinned<V>) {
+    entry.removed.store(true, Ordering::Release);
+}
+
+// Releases the values still held, including ones an upgraded handle keeps
+// alive past the map. The walk needs no key order, so it works for any `K`.
+impl<K, V> Drop for StableTreeMap<K, V> {
+    fn drop(&mut self) {
+        for node in preorder(&self.map.root).flatten() {
+            release(&node.value);
+        }
+    }
+}
+
+impl<K: Ord, V> StableTreeMap<K, V> {
+    pub fn new() -> Self {
+        StableTreeMap { map: BinaryTreeMap::default() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn get(&self, key: &K) -> Result<&V, MapError> {
+        self.map.get(key).map(|entry| &entry.value)
+    }
+
+    pub fn value_ref(&self, key: &K) -> Result<ValueRef<V>, MapError> {
+        self.map.get(key).map(|entry| ValueRef { entry: Arc::downgrade(entry) })
+    }
+
+    // Returns a handle to the new value; handles to a value it replaces
+    // stop upgrading
+    pub fn insert(&mut self, key: K, value: V) -> ValueRef<V> {
+        let entry = Arc::new(Pinned { value, removed: AtomicBool::new(false) });
+        let handle = ValueRef { entry: Arc::downgrade(&entry) };
+        if let Some((_, old)) = self.map.replace(key, entry) {
+            release(&old);
+        }
+        handle
+    }
+
+    // The value is moved out unless an upgraded handle still shares it, in
+    // which case it is cloned
+    pub fn delete(&mut self, key: &K) -> Result<V, MapError>
+    where
+        V: Clone,
+    {
+        let entry = self.map.delete(key)?;
+        release(&entry);
+        Ok(Arc::try_unwrap(entry).map_or_else(|shared| shared.value.clone(), |entry| entry.value))
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&K, &V)> {
+        self.map.iter().map(|(key, entry)| (key, &entry.value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_value_address_survives_rebalancing() {
+        let mut map = StableTreeMap::new();
+        let handle = map.insert(0u32, "zero".to_string());
+        let address: *const String = &**handle.upgrade().unwrap();
+        for key in 1..1000 {
+            map.insert(key, key.to_string());
+        }
+        for key in (1..1000).step_by(2) {
+            map.delete(&key).unwrap();
+        }
+        assert!(core::ptr::eq(map.get(&0).unwrap(), address));
+        assert_eq!(**handle.upgrade().unwrap(), "zero");
+    }
+
+    #[test]
+    fn test_upgrade_fails_after_delete_or_replace() {
+        let mut map = StableTreeMap::new();
+        let first = map.insert(1, "a".to_string());
+        let held = first.upgrade().unwrap();
+        let second = map.insert(1, "b".to_string());
+        assert_eq!(first.upgrade().unwrap_err(), MapError::KeyNotFound);
+        assert_eq!(**held, "a");
+        assert_eq!(**second.upgrade().unwrap(), "b");
+
+        let again = map.value_ref(&1).unwrap();
+        let shared = again.upgrade().unwrap();
+        assert_eq!(map.delete(&1), Ok("b".to_string()));
+        assert!(!second.is_live() && !again.is_live());
+        assert_eq!(**shared, "b");
+        assert!(map.is_empty());
+
+        let kept = map.insert(2, "c".to_string());
+        let outliving = map.insert(3, "d".to_string());
+        let upgraded = outliving.upgrade().unwrap();
+        let entries: Vec<_> = map.iter().map(|(k, v)| (*k, v.clone())).collect();
+        assert_eq!(entries, [(2, "c".to_string()), (3, "d".to_string())]);
+        drop(map);
+        assert!(!kept.is_live());
+        // The upgraded Arc keeps the value, but the handle is dead
+        assert_eq!(outliving.upgrade().unwrap_err(), MapError::KeyNotFound);
+        assert_eq!(**upgraded, "d");
+    }
+}
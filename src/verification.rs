@@ -0,0 +1,69 @@
+// Kani proof harnesses, run with `cargo kani --features verification`. Keys
+// come from a small domain and operation sequences are short, so every
+// interleaving of inserts and deletes up to that bound is covered
+// exhaustively rather than sampled.
+use crate::{Avl, BalancePolicy, BinaryTreeMap, MapError};
+
+const KEYS: u8 = 4;
+const STEPS: usize = 3;
+
+fn any_key() -> u8 {
+    let key: u8 = kani::any();
+    kani::assume(key < KEYS);
+    key
+}
+
+// A map reached by `STEPS` arbitrary inserts and deletes
+fn any_map<P: BalancePolicy<u8, u8> + Default>() -> BinaryTreeMap<u8, u8, P> {
+    let mut map = BinaryTreeMap::default();
+    for _ in 0..STEPS {
+        if kani::any() {
+            map.insert(any_key(), kani::any());
+        } else {
+            let _ = map.delete(&any_key());
+        }
+    }
+    map
+}
+
+#[kani::proof]
+#[kani::unwind(8)]
+fn insert_then_get() {
+    let mut map = any_map::<crate::Unbalanced>();
+    let key = any_key();
+    let value: u8 = kani::any();
+    map.insert(key, value);
+    assert_eq!(map.get(&key), Ok(&value));
+}
+
+#[kani::proof]
+#[kani::unwind(8)]
+fn delete_removes_exactly_one_key() {
+    let mut map = any_map::<crate::Unbalanced>();
+    let key = any_key();
+    let other = any_key();
+    kani::assume(key != other);
+    let before = map.len();
+    let other_before = map.get(&other).copied();
+    match map.delete(&key) {
+        Ok(_) => assert_eq!(map.len(), before - 1),
+        Err(error) => {
+            assert_eq!(error, MapError::KeyNotFound);
+            assert_eq!(map.len(), before);
+        },
+    }
+    assert_eq!(map.get(&key), Err(MapError::KeyNotFound));
+    assert_eq!(map.get(&other).copied(), other_before);
+}
+
+#[kani::proof]
+#[kani::unwind(8)]
+fn ordering_invariant_preserved() {
+    assert!(any_map::<crate::Unbalanced>().check_invariants().is_ok());
+}
+
+#[kani::proof]
+#[kani::unwind(8)]
+fn avl_invariants_preserved() {
+    assert!(any_map::<Avl>().check_invariants().is_ok());
+}
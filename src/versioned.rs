@@ -0,0 +1,119 @@
+use crate::{persistent, MapError};
+use alloc::vec::Vec;
+use alloc::vec;
+
+// Keeps every state the map has been in. Each mutation stores a new
+// persistent version that shares all untouched subtrees with the previous
+// one. The persistent map stays balanced, so a version costs O(log n)
+// copied nodes rather than a full copy, whatever order keys arrive in.
+// Version 0 is the empty map.
+#[derive(Debug, Clone)]
+pub struct VersionedTreeMap<K, V> {
+    versions: Vec<persistent::BinaryTreeMap<K, V>>,
+}
+
+impl<K: Ord, V> Default for VersionedTreeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> VersionedTreeMap<K, V> {
+    pub fn new() -> Self {
+        VersionedTreeMap { versions: vec![persistent::BinaryTreeMap::new()] }
+    }
+
+    pub fn version(&self) -> usize {
+        self.versions.len() - 1
+    }
+
+    pub fn len(&self) -> usize {
+        self.current().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.current().is_empty()
+    }
+
+    pub fn get(&self, key: &K) -> Result<&V, MapError> {
+        self.current().get(key)
+    }
+
+    // The map as it was at `version`. Versions after the current one do not
+    // exist yet and read as `None`.
+    pub fn at(&self, version: usize) -> Option<&persistent::BinaryTreeMap<K, V>> {
+        self.versions.get(version)
+    }
+
+    // Panics if `version` is newer than the current version
+    pub fn get_at(&self, version: usize, key: &K) -> Result<&V, MapError> {
+        self.expect_version(version).get(key)
+    }
+
+    // Panics if `version` is newer than the current version
+    pub fn iter_at(&self, version: usize) -> persistent::Iter<'_, K, V> {
+        self.expect_version(version).iter()
+    }
+
+    fn current(&self) -> &persistent::BinaryTreeMap<K, V> {
+        &self.versions[self.version()]
+    }
+
+    // Forgets the versions after `version`, which becomes the current one
+    pub(crate) fn truncate(&mut self, version: usize) {
+        self.versions.truncate(version + 1);
+    }
+
+    fn expect_version(&self, version: usize) -> &persistent::BinaryTreeMap<K, V> {
+        self.at(version)
+            .unwrap_or_else(|| panic!("version {version} does not exist yet (current is {})", self.version()))
+    }
+}
+
+impl<K: Ord + Clone, V: Clone> VersionedTreeMap<K, V> {
+    // Returns the version the insert created
+    pub fn insert(&mut self, key: K, value: V) -> usize {
+        let next = self.current().insert(key, value);
+        self.versions.push(next);
+        self.version()
+    }
+
+    // A failed delete changes nothing and creates no version
+    pub fn delete(&mut self, key: &K) -> Result<V, MapError> {
+        let value = self.current().get(key)?.clone();
+        let next = self.current().delete(key)?;
+        self.versions.push(next);
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_at_every_version() {
+        let mut map = VersionedTreeMap::new();
+        assert_eq!(map.insert("b", 1), 1);
+        assert_eq!(map.insert("a", 2), 2);
+        assert_eq!(map.insert("b", 3), 3);
+        assert_eq!(map.delete(&"a"), Ok(2));
+        assert_eq!(map.delete(&"a"), Err(MapError::KeyNotFound));
+
+        assert_eq!(map.version(), 4);
+        assert_eq!(map.get_at(0, &"b"), Err(MapError::KeyNotFound));
+        assert_eq!(map.get_at(1, &"b"), Ok(&1));
+        assert_eq!(map.get_at(3, &"b"), Ok(&3));
+        assert_eq!(map.get(&"a"), Err(MapError::KeyNotFound));
+        assert_eq!(map.iter_at(2).collect::<Vec<_>>(), vec![(&"a", &2), (&"b", &1)]);
+        assert_eq!(map.iter_at(4).collect::<Vec<_>>(), vec![(&"b", &3)]);
+        assert!(map.at(5).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "version 1 does not exist yet")]
+    fn test_future_version_panics() {
+        let map: VersionedTreeMap<i32, i32> = VersionedTreeMap::new();
+        let _ = map.get_at(1, &0);
+    }
+}
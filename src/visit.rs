@@ -0,0 +1,269 @@
+use crate::augment::{after_start, before_end};
+use crate::node::Node;
+use crate::{BalancePolicy, BinaryTreeMap};
+use core::ops::{ControlFlow, RangeBounds};
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+// Callback driven by the `visit_*` traversals with each node's entry and
+// depth (the root is at depth 0). Closures taking `(&K, &V, usize)` are
+// visitors too.
+pub trait TreeVisitor<K, V> {
+    fn visit(&mut self, key: &K, value: &V, depth: usize);
+}
+
+impl<K, V, F: FnMut(&K, &V, usize)> TreeVisitor<K, V> for F {
+    fn visit(&mut self, key: &K, value: &V, depth: usize) {
+        self(key, value, depth)
+    }
+}
+
+// All four traversals use an explicit stack or queue, so any depth is safe
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Each node before its subtrees, left first
+    pub fn visit_preorder(&self, visitor: &mut impl TreeVisitor<K, V>) {
+        let mut pending: Vec<_> = self.root.as_deref().map(|root| (root, 0)).into_iter().collect();
+        while let Some((node, depth)) = pending.pop() {
+            visitor.visit(&node.key, &node.value, depth);
+            pending.extend(node.right().map(|right| (right, depth + 1)));
+            pending.extend(node.left().map(|left| (left, depth + 1)));
+        }
+    }
+
+    // Ascending key order
+    pub fn visit_inorder(&self, visitor: &mut impl TreeVisitor<K, V>) {
+        let mut stack = Vec::new();
+        let mut current = self.root.as_deref().map(|root| (root, 0));
+        loop {
+            while let Some((node, depth)) = current {
+                stack.push((node, depth));
+                current = node.left().map(|left| (left, depth + 1));
+            }
+            let Some((node, depth)) = stack.pop() else {
+                return;
+            };
+            visitor.visit(&node.key, &node.value, depth);
+            current = node.right().map(|right| (right, depth + 1));
+        }
+    }
+
+    // Each node after both its subtrees, left first
+    pub fn visit_postorder(&self, visitor: &mut impl TreeVisitor<K, V>) {
+        let mut pending: Vec<_> = self.root.as_deref().map(|root| (root, 0, false)).into_iter().collect();
+        while let Some((node, depth, children_done)) = pending.pop() {
+            if children_done {
+                visitor.visit(&node.key, &node.value, depth);
+                continue;
+            }
+            pending.push((node, depth, true));
+            pending.extend(node.right().map(|right| (right, depth + 1, false)));
+            pending.extend(node.left().map(|left| (left, depth + 1, false)));
+        }
+    }
+
+    // Breadth-first, left to right within each level
+    pub fn visit_levelorder(&self, visitor: &mut impl TreeVisitor<K, V>) {
+        let mut queue: VecDeque<_> = self.root.as_deref().map(|root| (root, 0)).into_iter().collect();
+        while let Some((node, depth)) = queue.pop_front() {
+            visitor.visit(&node.key, &node.value, depth);
+            queue.extend(node.left().map(|left| (left, depth + 1)));
+            queue.extend(node.right().map(|right| (right, depth + 1)));
+        }
+    }
+
+    // Ascending key order with no heap allocation. The ancestors wait on a
+    // fixed stack; once a deep tree overflows it, the oldest are dropped and
+    // found again later by searching from the root for the successor of the
+    // last key visited. Trees up to 64 levels never need that.
+    pub fn for_each(&self, mut f: impl FnMut(&K, &V)) {
+        let mut stack = FixedStack::new();
+        let mut current = self.root.as_deref();
+        loop {
+            while let Some(node) = current {
+                stack.push(node);
+                current = node.left();
+            }
+            let node = match stack.pop() {
+                Some(node) => node,
+                None if stack.truncated => {
+                    // Only the nodes above the last visited key are left,
+                    // so they are the ones a search for it turns left at
+                    let mut candidate = Some(&**self.root.as_ref().expect("visited a node"));
+                    let last = &stack.last.take().expect("visited a node").key;
+                    stack.truncated = false;
+                    while let Some(node) = candidate {
+                        if node.key > *last {
+                            stack.push(node);
+                            candidate = node.left();
+                        } else {
+                            candidate = node.right();
+                        }
+                    }
+                    continue;
+                },
+                None => return,
+            };
+            f(&node.key, &node.value);
+            stack.last = Some(node);
+            current = node.right();
+        }
+    }
+
+    // Ascending key order until `f` breaks, returning its break value. Uses
+    // the same stack as `visit_inorder`, with no iterator in between.
+    pub fn try_for_each_in_order<B>(&self, f: impl FnMut(&K, &V) -> ControlFlow<B>) -> ControlFlow<B> {
+        self.try_range_for_each(.., f)
+    }
+
+    // Like `try_for_each_in_order`, over the keys in `range` only
+    pub fn try_range_for_each<B, R: RangeBounds<K>>(&self, range: R, mut f: impl FnMut(&K, &V) -> ControlFlow<B>) -> ControlFlow<B> {
+        let mut stack = Vec::new();
+        let mut current = self.root.as_deref();
+        loop {
+            // Descend to the smallest key not below the start
+            while let Some(node) = current {
+                if after_start(&node.key, range.start_bound()) {
+                    stack.push(node);
+                    current = node.left();
+                } else {
+                    current = node.right();
+                }
+            }
+            let Some(node) = stack.pop() else {
+                return ControlFlow::Continue(());
+            };
+            if !before_end(&node.key, range.end_bound()) {
+                return ControlFlow::Continue(());
+            }
+            f(&node.key, &node.value)?;
+            current = node.right();
+        }
+    }
+}
+
+const FIXED_DEPTH: usize = 64;
+
+// Ring buffer of ancestors that drops the oldest when full, noting it in
+// `truncated`, plus the node visited last for finding them again
+struct FixedStack<'a, K, V, M> {
+    nodes: [Option<&'a Node<K, V, M>>; FIXED_DEPTH],
+    bottom: usize,
+    len: usize,
+    truncated: bool,
+    last: Option<&'a Node<K, V, M>>,
+}
+
+impl<'a, K, V, M> FixedStack<'a, K, V, M> {
+    fn new() -> Self {
+        FixedStack { nodes: [None; FIXED_DEPTH], bottom: 0, len: 0, truncated: false, last: None }
+    }
+
+    fn push(&mut self, node: &'a Node<K, V, M>) {
+        if self.len == FIXED_DEPTH {
+            self.bottom = (self.bottom + 1) % FIXED_DEPTH;
+            self.len -= 1;
+            self.truncated = true;
+        }
+        self.nodes[(self.bottom + self.len) % FIXED_DEPTH] = Some(node);
+        self.len += 1;
+    }
+
+    fn pop(&mut self) -> Option<&'a Node<K, V, M>> {
+        self.len = self.len.checked_sub(1)?;
+        self.nodes[(self.bottom + self.len) % FIXED_DEPTH].take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //       4
+    //     2   6
+    //    1 3   7
+    fn sample() -> BinaryTreeMap<i32, ()> {
+        let mut map = BinaryTreeMap::new();
+        for key in [4, 2, 6, 1, 3, 7] {
+            map.insert(key, ());
+        }
+        map
+    }
+
+    #[test]
+    fn test_traversal_orders() {
+        let map = sample();
+        let mut seen = Vec::new();
+        let mut record = |key: &i32, _: &(), depth: usize| seen.push((*key, depth));
+        map.visit_preorder(&mut record);
+        map.visit_inorder(&mut record);
+        map.visit_postorder(&mut record);
+        map.visit_levelorder(&mut record);
+        let keys: Vec<i32> = seen.iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys[0..6], [4, 2, 1, 3, 6, 7]);
+        assert_eq!(keys[6..12], [1, 2, 3, 4, 6, 7]);
+        assert_eq!(keys[12..18], [1, 3, 2, 7, 6, 4]);
+        assert_eq!(keys[18..24], [4, 2, 6, 1, 3, 7]);
+        assert_eq!(seen[6..12].iter().map(|(_, depth)| *depth).collect::<Vec<_>>(), [2, 1, 2, 0, 1, 2]);
+    }
+
+    #[test]
+    fn test_stateful_visitor() {
+        struct DeepestKey(Option<(i32, usize)>);
+
+        impl TreeVisitor<i32, ()> for DeepestKey {
+            fn visit(&mut self, key: &i32, _: &(), depth: usize) {
+                if self.0.is_none_or(|(_, deepest)| depth > deepest) {
+                    self.0 = Some((*key, depth));
+                }
+            }
+        }
+
+        let mut deepest = DeepestKey(None);
+        sample().visit_levelorder(&mut deepest);
+        assert_eq!(deepest.0, Some((1, 2)));
+        BinaryTreeMap::<i32, ()>::new().visit_postorder(&mut deepest);
+        assert_eq!(deepest.0, Some((1, 2)));
+    }
+
+    #[test]
+    fn test_try_for_each_stops_early() {
+        let map = sample();
+        let mut seen = Vec::new();
+        let found = map.try_for_each_in_order(|key, _| {
+            seen.push(*key);
+            if *key > 2 { ControlFlow::Break(*key) } else { ControlFlow::Continue(()) }
+        });
+        assert_eq!(found, ControlFlow::Break(3));
+        assert_eq!(seen, [1, 2, 3]);
+
+        let mut in_range = Vec::new();
+        let done: ControlFlow<()> = map.try_range_for_each(2..7, |key, _| {
+            in_range.push(*key);
+            ControlFlow::Continue(())
+        });
+        assert_eq!(done, ControlFlow::Continue(()));
+        assert_eq!(in_range, [2, 3, 4, 6]);
+        // Errors propagate the same way
+        let failed = map.try_range_for_each(5.., |key, _| if *key == 7 { ControlFlow::Break("seven") } else { ControlFlow::Continue(()) });
+        assert_eq!(failed, ControlFlow::Break("seven"));
+    }
+
+    #[test]
+    fn test_for_each_past_fixed_depth() {
+        let mut keys = Vec::new();
+        sample().for_each(|key, _| keys.push(*key));
+        assert_eq!(keys, [1, 2, 3, 4, 6, 7]);
+
+        // Left and right spines, and a zigzag, all far deeper than the stack
+        let shapes: [Vec<i32>; 3] = [(0..500).rev().collect(), (0..500).collect(), (0..500).map(|i| if i % 2 == 0 { i / 2 } else { 499 - i / 2 }).collect()];
+        for order in shapes {
+            let mut map = BinaryTreeMap::new();
+            for key in order {
+                map.insert(key, ());
+            }
+            let mut keys = Vec::new();
+            map.for_each(|key, _| keys.push(*key));
+            assert_eq!(keys, (0..500).collect::<Vec<_>>());
+        }
+    }
+}
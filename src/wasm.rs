@@ -0,0 +1,126 @@
+// JavaScript bindings. From JS the class is `BinaryTreeMap`; keys are
+// numbers or strings (all numbers sort before all strings), values any JS
+// value.
+use crate::{Avl, BinaryTreeMap};
+use js_sys::Array;
+use std::cmp::Ordering;
+use std::ops::Bound;
+use wasm_bindgen::prelude::*;
+
+#[derive(Debug, Clone, PartialEq)]
+enum JsKey {
+    Number(f64),
+    String(String),
+}
+
+impl JsKey {
+    // NaN has no place in the order, so it is rejected with other non-keys
+    fn from_js(value: &JsValue) -> Result<Self, JsError> {
+        match (value.as_f64(), value.as_string()) {
+            (Some(n), _) if !n.is_nan() => Ok(JsKey::Number(n)),
+            (_, Some(s)) => Ok(JsKey::String(s)),
+            _ => Err(JsError::new("keys must be numbers (not NaN) or strings")),
+        }
+    }
+
+    // `undefined` and `null` leave that end of a range open
+    fn bound(value: &JsValue, end: bool) -> Result<Bound<Self>, JsError> {
+        if value.is_undefined() || value.is_null() {
+            return Ok(Bound::Unbounded);
+        }
+        let key = Self::from_js(value)?;
+        Ok(if end { Bound::Excluded(key) } else { Bound::Included(key) })
+    }
+
+    fn to_js(&self) -> JsValue {
+        match self {
+            JsKey::Number(n) => JsValue::from_f64(*n),
+            JsKey::String(s) => JsValue::from_str(s),
+        }
+    }
+}
+
+impl Eq for JsKey {}
+
+impl PartialOrd for JsKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for JsKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (JsKey::Number(a), JsKey::Number(b)) => a.total_cmp(b),
+            (JsKey::Number(_), JsKey::String(_)) => Ordering::Less,
+            (JsKey::String(_), JsKey::Number(_)) => Ordering::Greater,
+            (JsKey::String(a), JsKey::String(b)) => a.cmp(b),
+        }
+    }
+}
+
+#[wasm_bindgen(js_name = BinaryTreeMap)]
+#[derive(Debug, Default)]
+pub struct JsBinaryTreeMap {
+    map: BinaryTreeMap<JsKey, JsValue, Avl>,
+}
+
+#[wasm_bindgen(js_class = BinaryTreeMap)]
+impl JsBinaryTreeMap {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn size(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn insert(&mut self, key: JsValue, value: JsValue) -> Result<(), JsError> {
+        self.map.insert(JsKey::from_js(&key)?, value);
+        Ok(())
+    }
+
+    // `undefined` when the key is absent, like `Map.prototype.get`
+    pub fn get(&self, key: JsValue) -> Result<JsValue, JsError> {
+        let key = JsKey::from_js(&key)?;
+        Ok(self.map.get(&key).cloned().unwrap_or(JsValue::UNDEFINED))
+    }
+
+    pub fn delete(&mut self, key: JsValue) -> Result<bool, JsError> {
+        Ok(self.map.delete(&JsKey::from_js(&key)?).is_ok())
+    }
+
+    // `[key, value]` pairs with `start <= key < end` in key order
+    pub fn range(&self, start: JsValue, end: JsValue) -> Result<Array, JsError> {
+        let bounds = (JsKey::bound(&start, false)?, JsKey::bound(&end, true)?);
+        Ok(self.map.range(bounds).map(|(key, value)| Array::of2(&key.to_js(), value)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JsKey;
+
+    // JS values only exist on wasm32; the key order is plain Rust
+    #[test]
+    fn test_numbers_sort_before_strings() {
+        let mut keys = vec![
+            JsKey::String("b".into()),
+            JsKey::Number(10.0),
+            JsKey::String("a".into()),
+            JsKey::Number(-0.5),
+        ];
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                JsKey::Number(-0.5),
+                JsKey::Number(10.0),
+                JsKey::String("a".into()),
+                JsKey::String("b".into()),
+            ]
+        );
+    }
+}
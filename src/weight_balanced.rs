@@ -0,0 +1,150 @@
+use crate::node::{Link, Node};
+use crate::{BalancePolicy, BinaryTreeMap, ValueIndependent};
+use alloc::boxed::Box;
+
+// Balance parameters (delta, gamma) = (3, 2) from Adams' weight-balanced
+// trees; Hirai and Yamamoto showed this is the only integer pair that keeps
+// the tree balanced under single inserts and deletes.
+const DELTA: usize = 3;
+const GAMMA: usize = 2;
+
+// BB[α] balancing: the subtree sizes that back `nth`/`rank` double as the
+// balance metadata, so no extra per-node field is needed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WeightBalanced;
+
+pub type WeightBalancedTreeMap<K, V> = BinaryTreeMap<K, V, WeightBalanced>;
+
+// The balancing below only looks at subtree sizes, which every policy
+// maintains, so other size-balanced policies (such as the interval tree's)
+// reuse it with their own metadata
+impl WeightBalanced {
+    fn weight<K, V, M>(node: &Link<K, V, M>) -> usize {
+        Node::size_of(node) + 1
+    }
+
+    // Restores the weight invariant at `node`, assuming both children are
+    // already balanced and differ by at most one insert or delete.
+    pub(crate) fn balance<K, V, P: BalancePolicy<K, V>>(mut node: Box<Node<K, V, P::Meta>>) -> Box<Node<K, V, P::Meta>> {
+        let left_weight = Self::weight(&node.left);
+        let right_weight = Self::weight(&node.right);
+
+        if right_weight > DELTA * left_weight {
+            // Double rotation when the inner grandchild is the heavy one
+            let right = node.right.as_ref().unwrap();
+            if Self::weight(&right.left) >= GAMMA * Self::weight(&right.right) {
+                node.map_right::<P, _>(Node::rotate_right::<P>);
+            }
+            Node::rotate_left::<P>(node)
+        } else if left_weight > DELTA * right_weight {
+            let left = node.left.as_ref().unwrap();
+            if Self::weight(&left.right) >= GAMMA * Self::weight(&left.left) {
+                node.map_left::<P, _>(Node::rotate_left::<P>);
+            }
+            Node::rotate_right::<P>(node)
+        } else {
+            node
+        }
+    }
+
+    pub(crate) fn check_weights<K, V, M>(node: &Node<K, V, M>) -> Result<(), &'static str> {
+        let left_weight = Self::weight(&node.left);
+        let right_weight = Self::weight(&node.right);
+        if left_weight > DELTA * right_weight || right_weight > DELTA * left_weight {
+            return Err("subtree weights differ by more than the balance factor");
+        }
+        Ok(())
+    }
+
+    pub(crate) fn join_by_weight<K, V, M>(left: Option<&Node<K, V, M>>, right: Option<&Node<K, V, M>>) -> core::cmp::Ordering {
+        let left_weight = left.map_or(0, |n| n.size) + 1;
+        let right_weight = right.map_or(0, |n| n.size) + 1;
+        if left_weight > DELTA * right_weight {
+            core::cmp::Ordering::Greater
+        } else if right_weight > DELTA * left_weight {
+            core::cmp::Ordering::Less
+        } else {
+            core::cmp::Ordering::Equal
+        }
+    }
+}
+
+impl<K, V> ValueIndependent<K, V> for WeightBalanced {}
+
+impl<K, V> BalancePolicy<K, V> for WeightBalanced {
+    type Meta = ();
+
+    fn after_insert(node: Box<Node<K, V, ()>>) -> Box<Node<K, V, ()>> {
+        Self::balance::<K, V, Self>(node)
+    }
+
+    fn after_delete(node: Box<Node<K, V, ()>>) -> Box<Node<K, V, ()>> {
+        Self::balance::<K, V, Self>(node)
+    }
+
+    fn check(node: &Node<K, V, ()>) -> Result<(), &'static str> {
+        Self::check_weights(node)
+    }
+
+    fn join_direction(left: Option<&Node<K, V, ()>>, right: Option<&Node<K, V, ()>>) -> core::cmp::Ordering {
+        Self::join_by_weight(left, right)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MapError;
+
+    fn assert_weight_balanced<K, V>(node: &Link<K, V, ()>) {
+        if let Some(n) = node {
+            let left_weight = Node::size_of(&n.left) + 1;
+            let right_weight = Node::size_of(&n.right) + 1;
+            assert!(left_weight <= DELTA * right_weight);
+            assert!(right_weight <= DELTA * left_weight);
+            assert_weight_balanced(&n.left);
+            assert_weight_balanced(&n.right);
+        }
+    }
+
+    #[test]
+    fn test_sorted_inserts_stay_balanced() {
+        let mut map = WeightBalancedTreeMap::default();
+        for key in 0..1024 {
+            map.insert(key, key);
+        }
+
+        assert_eq!(map.len(), 1024);
+        assert_weight_balanced(&map.root);
+        // A BB[α] tree with α = 1/4 has height at most log_{4/3}(n + 1)
+        assert!(map.height() <= 25);
+    }
+
+    #[test]
+    fn test_delete_rebalances() {
+        let mut map = WeightBalancedTreeMap::default();
+        for key in 0..256 {
+            map.insert(key, key.to_string());
+        }
+        for key in 0..200 {
+            assert_eq!(map.delete(&key), Ok(key.to_string()));
+        }
+
+        assert_eq!(map.len(), 56);
+        assert_weight_balanced(&map.root);
+        assert_eq!(map.get(&100), Err(MapError::KeyNotFound));
+        assert_eq!(map.get(&200), Ok(&"200".to_string()));
+    }
+
+    #[test]
+    fn test_nth_and_rank() {
+        let mut map = WeightBalancedTreeMap::default();
+        for key in (0..100).rev() {
+            map.insert(key * 2, ());
+        }
+
+        assert_eq!(map.nth(10), Some((&20, &())));
+        assert_eq!(map.rank(&21), 11);
+        assert_eq!(map.nth(100), None);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::{Augment, Augmented, AugmentedTreeMap, BinaryTreeMap};
+#[cfg(feature = "rand")]
+use rand::{Rng, RngExt};
+
+// A value's share in weighted selection
+pub trait HasWeight {
+    fn weight(&self) -> u64;
+}
+
+macro_rules! integer_weight {
+    ($($t:ty),*) => {$(
+        impl HasWeight for $t {
+            fn weight(&self) -> u64 {
+                *self as u64
+            }
+        }
+    )*};
+}
+
+integer_weight!(u8, u16, u32, u64, usize);
+
+// Sum of the values' weights per subtree. Sums are kept as `u128`: fewer
+// than 2^64 entries of at most 2^64 - 1 each cannot overflow it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TotalWeight;
+
+impl<K, V: HasWeight> Augment<K, V> for TotalWeight {
+    type Summary = u128;
+
+    fn summarize(_: &K, value: &V) -> u128 {
+        u128::from(value.weight())
+    }
+
+    fn combine(left: &u128, right: &u128) -> u128 {
+        left + right
+    }
+}
+
+pub type WeightedTreeMap<K, V> = AugmentedTreeMap<K, V, TotalWeight>;
+
+impl<K: Ord, V: HasWeight> BinaryTreeMap<K, V, Augmented<TotalWeight>> {
+    pub fn total_weight(&self) -> u128 {
+        self.summary()
+    }
+
+    // The entry covering `target` when the entries are laid end to end in
+    // key order, each as long as its weight. O(log n) through the subtree
+    // totals; `None` once `target` reaches the total weight.
+    pub fn entry_at_weight(&self, mut target: u128) -> Option<(&K, &V)> {
+        let mut current = &self.root;
+        while let Some(n) = current {
+            let left = n.left.as_ref().map_or(0, |left| left.meta);
+            if target < left {
+                current = &n.left;
+                continue;
+            }
+            target -= left;
+            let own = u128::from(n.value.weight());
+            if target < own {
+                return Some((&n.key, &n.value));
+            }
+            target -= own;
+            current = &n.right;
+        }
+        None
+    }
+
+    // An entry chosen with probability proportional to its weight
+    #[cfg(feature = "rand")]
+    pub fn sample_weighted<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<(&K, &V)> {
+        match self.total_weight() {
+            0 => None,
+            total => self.entry_at_weight(rng.random_range(0..total)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_at_weight_covers_each_share() {
+        let mut map = WeightedTreeMap::default();
+        for (variant, weight) in [("a", 70u32), ("b", 0), ("c", 20), ("d", 10)] {
+            map.insert(variant, weight);
+        }
+        assert_eq!(map.total_weight(), 100);
+        assert_eq!(map.entry_at_weight(0), Some((&"a", &70)));
+        assert_eq!(map.entry_at_weight(69), Some((&"a", &70)));
+        assert_eq!(map.entry_at_weight(70), Some((&"c", &20)));
+        assert_eq!(map.entry_at_weight(99), Some((&"d", &10)));
+        assert_eq!(map.entry_at_weight(100), None);
+        map.insert("a", 0);
+        assert_eq!(map.entry_at_weight(0), Some((&"c", &20)));
+    }
+
+    #[test]
+    fn test_total_weight_does_not_overflow() {
+        let mut map = WeightedTreeMap::default();
+        for key in 0..4 {
+            map.insert(key, u64::MAX);
+        }
+        assert_eq!(map.total_weight(), 4 * u128::from(u64::MAX));
+        assert_eq!(map.entry_at_weight(3 * u128::from(u64::MAX)), Some((&3, &u64::MAX)));
+    }
+
+    #[cfg(feature = "rand")]
+    #[test]
+    fn test_sample_weighted_proportions() {
+        use rand::SeedableRng;
+
+        let mut rng = rand_xorshift::XorShiftRng::seed_from_u64(3);
+        let mut map = WeightedTreeMap::default();
+        assert_eq!(map.sample_weighted(&mut rng), None);
+        for key in 0..4u64 {
+            map.insert(key, key);
+        }
+        let mut counts = [0; 4];
+        for _ in 0..6000 {
+            counts[*map.sample_weighted(&mut rng).unwrap().0 as usize] += 1;
+        }
+        assert_eq!(counts[0], 0);
+        assert!((900..1100).contains(&counts[1]) && (1850..2150).contains(&counts[2]), "{counts:?}");
+    }
+}
@@ -0,0 +1,107 @@
+use crate::iter::Iter;
+use crate::{BalancePolicy, BinaryTreeMap};
+use core::cmp::Ordering;
+use core::iter::Peekable;
+
+// One key's presence across two maps, as yielded by `iter_joined`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZipEntry<'a, K, V, W> {
+    Left(&'a K, &'a V),
+    Right(&'a K, &'a W),
+    Both(&'a K, &'a V, &'a W),
+}
+
+impl<'a, K, V, W> ZipEntry<'a, K, V, W> {
+    pub fn key(&self) -> &'a K {
+        match *self {
+            ZipEntry::Left(key, _) | ZipEntry::Right(key, _) | ZipEntry::Both(key, _, _) => key,
+        }
+    }
+}
+
+// Merge-join of two maps' in-order iterators, O(n + m) in total
+pub struct Joined<'a, K, V, W, M, N> {
+    left: Peekable<Iter<'a, K, V, M>>,
+    right: Peekable<Iter<'a, K, W, N>>,
+}
+
+impl<'a, K: Ord, V, W, M, N> Iterator for Joined<'a, K, V, W, M, N> {
+    type Item = ZipEntry<'a, K, V, W>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let order = match (self.left.peek(), self.right.peek()) {
+            (Some((left, _)), Some((right, _))) => left.cmp(right),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => return None,
+        };
+        Some(match order {
+            Ordering::Less => {
+                let (key, value) = self.left.next()?;
+                ZipEntry::Left(key, value)
+            },
+            Ordering::Greater => {
+                let (key, value) = self.right.next()?;
+                ZipEntry::Right(key, value)
+            },
+            Ordering::Equal => {
+                let (key, left) = self.left.next()?;
+                let (_, right) = self.right.next()?;
+                ZipEntry::Both(key, left, right)
+            },
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (left, _) = self.left.size_hint();
+        let (right, _) = self.right.size_hint();
+        (left.max(right), Some(left + right))
+    }
+}
+
+impl<K: Ord, V, P: BalancePolicy<K, V>> BinaryTreeMap<K, V, P> {
+    // Walks both maps together in key order; `other` may hold another value
+    // type under another policy
+    pub fn iter_joined<'a, W, Q: BalancePolicy<K, W>>(&'a self, other: &'a BinaryTreeMap<K, W, Q>) -> Joined<'a, K, V, W, P::Meta, Q::Meta> {
+        Joined { left: self.iter().peekable(), right: other.iter().peekable() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Avl;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_joined_in_key_order() {
+        let mut left = BinaryTreeMap::new();
+        for key in [1, 3, 5, 7] {
+            left.insert(key, key * 10);
+        }
+        let mut right = BinaryTreeMap::with_policy(Avl);
+        for key in [3, 4, 7, 9] {
+            right.insert(key, "r");
+        }
+        let joined: Vec<_> = left.iter_joined(&right).collect();
+        assert_eq!(joined, [
+            ZipEntry::Left(&1, &10),
+            ZipEntry::Both(&3, &30, &"r"),
+            ZipEntry::Right(&4, &"r"),
+            ZipEntry::Left(&5, &50),
+            ZipEntry::Both(&7, &70, &"r"),
+            ZipEntry::Right(&9, &"r"),
+        ]);
+        assert_eq!(joined.iter().map(ZipEntry::key).copied().collect::<Vec<_>>(), [1, 3, 4, 5, 7, 9]);
+    }
+
+    #[test]
+    fn test_joined_with_empty_side() {
+        let empty: BinaryTreeMap<u32, ()> = BinaryTreeMap::new();
+        let mut full = BinaryTreeMap::new();
+        full.insert(1, ());
+        assert_eq!(empty.iter_joined(&empty).next(), None);
+        assert_eq!(full.iter_joined(&empty).collect::<Vec<_>>(), [ZipEntry::Left(&1, &())]);
+        assert_eq!(empty.iter_joined(&full).size_hint(), (1, Some(1)));
+    }
+}